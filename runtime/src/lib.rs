@@ -59,6 +59,22 @@ impl Runtime {
         }
     }
 
+    /// Runs the given `future` as a detached task.
+    #[allow(unused_variables)]
+    pub fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        match self {
+            #[cfg(feature = "tokio_1")]
+            Self::Tokio1 => drop(tokio_1::spawn(future)),
+            #[cfg(feature = "async-std_1")]
+            Self::AsyncStd1 => drop(async_std_1::task::spawn(future)),
+            #[allow(unreachable_patterns)]
+            _ => unreachable!(),
+        }
+    }
+
     /// Runs the given closure on a thread where blocking is acceptable.
     ///
     /// # Errors
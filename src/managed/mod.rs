@@ -589,6 +589,55 @@ impl<M: Manager, W: From<Object<M>>> Pool<M, W> {
     pub fn manager(&self) -> &M {
         &self.inner.manager
     }
+
+    /// Returns a [`WeakPool`] of this [`Pool`].
+    #[must_use]
+    pub fn weak(&self) -> WeakPool<M, W> {
+        WeakPool {
+            inner: Arc::downgrade(&self.inner),
+            _wrapper: PhantomData,
+        }
+    }
+}
+
+/// A weak reference to a [`Pool`], used to avoid keeping the [`Pool`] alive.
+///
+/// This is useful for background tasks which should stop once the last
+/// [`Pool`] is dropped.
+pub struct WeakPool<M: Manager, W: From<Object<M>> = Object<M>> {
+    inner: Weak<PoolInner<M>>,
+    _wrapper: PhantomData<fn() -> W>,
+}
+
+impl<M: Manager, W: From<Object<M>>> WeakPool<M, W> {
+    /// Upgrades this [`WeakPool`] to a [`Pool`], returning [`None`] if the
+    /// [`Pool`] has been dropped.
+    pub fn upgrade(&self) -> Option<Pool<M, W>> {
+        Some(Pool {
+            inner: self.inner.upgrade()?,
+            _wrapper: PhantomData,
+        })
+    }
+}
+
+// Implemented manually to avoid unnecessary trait bound on `W` type parameter.
+impl<M: Manager, W: From<Object<M>>> Clone for WeakPool<M, W> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _wrapper: PhantomData,
+        }
+    }
+}
+
+// Implemented manually to avoid unnecessary trait bound on `W` type parameter.
+impl<M: Manager, W: From<Object<M>>> fmt::Debug for WeakPool<M, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeakPool")
+            .field("inner", &self.inner)
+            .field("wrapper", &self._wrapper)
+            .finish()
+    }
 }
 
 struct PoolInner<M: Manager> {
@@ -0,0 +1,107 @@
+#![doc = include_str!("../README.md")]
+#![deny(
+    nonstandard_style,
+    rust_2018_idioms,
+    rustdoc::broken_intra_doc_links,
+    rustdoc::private_intra_doc_links
+)]
+#![forbid(non_ascii_idents, unsafe_code)]
+#![warn(
+    deprecated_in_future,
+    missing_copy_implementations,
+    missing_debug_implementations,
+    missing_docs,
+    unreachable_pub,
+    unused_import_braces,
+    unused_labels,
+    unused_lifetimes,
+    unused_qualifications,
+    unused_results
+)]
+#![allow(clippy::uninlined_format_args)]
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput, Expr, Lit, LitStr, Meta};
+
+/// Validates the syntax of a SurrealQL query at compile time and expands to
+/// a `deadpool_surrealdb::client::PreparedQuery`.
+///
+/// Syntax errors are reported as compile errors pointing at the query.
+/// Tables, fields and parameters are not checked, as they are only known to
+/// the server.
+///
+/// ```rust,ignore
+/// let people: Vec<Person> = surql!("SELECT * FROM person WHERE age > $age")
+///     .on_pool(&pool)
+///     .bind(("age", 18))
+///     .await?
+///     .take(0)?;
+/// ```
+#[proc_macro]
+pub fn surql(input: TokenStream) -> TokenStream {
+    let query = parse_macro_input!(input as LitStr);
+    if let Err(e) = surrealdb_core::syn::parse(&query.value()) {
+        let message = format!("Invalid SurrealQL: {}", e);
+        return syn::Error::new(query.span(), message)
+            .to_compile_error()
+            .into();
+    }
+    quote!(::deadpool_surrealdb::client::PreparedQuery::new_unchecked(#query)).into()
+}
+
+/// Derives `deadpool_surrealdb::repo::SurrealRecord` for a struct.
+///
+/// The table is set using `#[table = "user"]` and defaults to the name of
+/// the struct in snake case.
+#[proc_macro_derive(SurrealRecord, attributes(table))]
+pub fn derive_surreal_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let mut table = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("table")) {
+        let name = match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(lit) => match &lit.lit {
+                    Lit::Str(name) => Some(name.value()),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        };
+        match name {
+            Some(name) => table = Some(name),
+            None => {
+                return syn::Error::new_spanned(attr, "Expected #[table = \"name\"]")
+                    .to_compile_error()
+                    .into()
+            }
+        }
+    }
+    let table = table.unwrap_or_else(|| snake_case(&input.ident.to_string()));
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote!(
+        impl #impl_generics ::deadpool_surrealdb::repo::SurrealRecord for #name #ty_generics
+        #where_clause
+        {
+            const TABLE: &'static str = #table;
+        }
+    )
+    .into()
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
@@ -0,0 +1,182 @@
+use std::{
+    fmt,
+    future::Future,
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
+
+use actix_web::{
+    dev::Payload,
+    http::StatusCode,
+    web::{Data, ServiceConfig},
+    FromRequest, HttpRequest, HttpResponse, ResponseError,
+};
+use deadpool::managed::Timeouts;
+
+use crate::{checkout, Error, Object, Pool};
+
+/// Registers the `pool` as application data, so [`DbConn`] can be used by
+/// all handlers.
+///
+/// ```rust,ignore
+/// HttpServer::new(move || App::new().configure(configure(pool.clone())).service(index))
+/// ```
+pub fn configure(pool: Pool) -> impl FnOnce(&mut ServiceConfig) {
+    move |cfg| {
+        let _ = cfg.app_data(Data::new(pool));
+    }
+}
+
+type ErrorHandler = dyn Fn(Error, &HttpRequest) -> actix_web::Error + Send + Sync;
+
+/// Configuration of the [`DbConn`] extractor.
+///
+/// It is looked up in the application data like the configuration of the
+/// extractors of `actix-web`:
+///
+/// ```rust,ignore
+/// App::new().app_data(DbConnConfig::default().timeout(Duration::from_millis(500)))
+/// ```
+#[derive(Clone, Default)]
+pub struct DbConnConfig {
+    timeout: Option<Duration>,
+    error_handler: Option<Arc<ErrorHandler>>,
+}
+
+impl DbConnConfig {
+    /// Sets the time to wait for a connection, overriding the wait timeout
+    /// of the [`Pool`].
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets a function converting errors into responses.
+    ///
+    /// By default errors are converted using [`DbError`].
+    #[must_use]
+    pub fn error_handler(
+        mut self,
+        f: impl Fn(Error, &HttpRequest) -> actix_web::Error + Send + Sync + 'static,
+    ) -> Self {
+        self.error_handler = Some(Arc::new(f));
+        self
+    }
+}
+
+impl fmt::Debug for DbConnConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DbConnConfig")
+            .field("timeout", &self.timeout)
+            .field("error_handler", &self.error_handler.is_some())
+            .finish()
+    }
+}
+
+/// Extractor checking a connection out of the [`Pool`] registered as
+/// application data, either as [`Data<Pool>`] or as plain [`Pool`].
+///
+/// ```rust,ignore
+/// #[get("/")]
+/// async fn index(DbConn(conn): DbConn) -> Result<String, DbError> {
+///     Ok(conn.server_version().await?)
+/// }
+/// ```
+#[derive(Debug)]
+pub struct DbConn(pub Object);
+
+impl FromRequest for DbConn {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, actix_web::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move {
+            let config = req.app_data::<DbConnConfig>().cloned().unwrap_or_default();
+            let result = match pool(&req) {
+                Some(pool) => checkout(&pool, config.timeout).await,
+                None => Err(Error::Connection {
+                    message: "No pool registered as application data".into(),
+                    context: None,
+                    source: None,
+                }),
+            };
+            result.map(Self).map_err(|e| match &config.error_handler {
+                Some(handler) => handler(e, &req),
+                None => DbError(e).into(),
+            })
+        })
+    }
+}
+
+fn pool(req: &HttpRequest) -> Option<Pool> {
+    req.app_data::<Data<Pool>>()
+        .map(|data| Pool::clone(data))
+        .or_else(|| req.app_data::<Pool>().cloned())
+}
+
+async fn checkout(pool: &Pool, timeout: Option<Duration>) -> Result<Object, Error> {
+    match timeout {
+        Some(timeout) => {
+            let timeouts = Timeouts {
+                wait: Some(timeout),
+                ..pool.timeouts()
+            };
+            checkout::timeout_get(pool, &timeouts).await
+        }
+        None => checkout::get(pool).await,
+    }
+}
+
+impl Deref for DbConn {
+    type Target = Object;
+
+    fn deref(&self) -> &Object {
+        &self.0
+    }
+}
+
+impl DerefMut for DbConn {
+    fn deref_mut(&mut self) -> &mut Object {
+        &mut self.0
+    }
+}
+
+/// [`Error`] which can be returned from handlers.
+///
+/// The status of the response is taken from [`Error::http_status()`]. The
+/// response body contains no details about the error.
+#[derive(Debug)]
+pub struct DbError(pub Error);
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for DbError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+impl ResponseError for DbError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.0.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        HttpResponse::build(status).body(status.canonical_reason().unwrap_or_default())
+    }
+}
+
+impl From<Error> for DbError {
+    fn from(e: Error) -> Self {
+        Self(e)
+    }
+}
@@ -0,0 +1,255 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    checkout::{self, Pressure},
+    hook,
+    rt::Spawn,
+    shutdown::TaskPool,
+    Error, Pool, Result,
+};
+
+/// Change of the size of a [`Pool`] made by an [`Autoscaler`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Resize {
+    /// Maximum size of the pool before resizing it
+    pub from: usize,
+    /// Maximum size of the pool after resizing it
+    pub to: usize,
+    /// Pressure on the pool which triggered the resize
+    pub pressure: Pressure,
+}
+
+/// Background task which grows a [`Pool`] under sustained pressure and
+/// shrinks it again during quiet periods, saving resources of the server for
+/// spiky workloads.
+///
+/// Every `interval` the [`Pressure`] of the pool is sampled. Once tasks had
+/// to wait for a connection for [`Autoscaler::grow_after()`] samples in a
+/// row, the pool grows by [`Autoscaler::step()`] connections up to
+/// [`Config::max_connections`]. Once less than half of the pool was checked
+/// out for [`Autoscaler::shrink_after()`] samples in a row, it shrinks by the
+/// same step down to [`Autoscaler::min_connections()`]. Two resizes are at
+/// least [`Autoscaler::cooldown()`] apart.
+///
+/// The pool keeps the size it was built with until the first resize, so
+/// build it with the minimum size. The autoscaler doesn't keep the pool
+/// alive, it stops once the pool is dropped.
+///
+/// ```rust,ignore
+/// let pool = Pool::builder(manager).max_size(2).build()?;
+/// Autoscaler::new(&pool)
+///     .min_connections(2)
+///     .on_resize(|resize| println!("Resized pool from {} to {}", resize.from, resize.to))
+///     .spawn()?;
+/// ```
+///
+/// [`Config::max_connections`]: crate::Config::max_connections
+pub struct Autoscaler {
+    pool: TaskPool,
+    min_connections: usize,
+    max_connections: usize,
+    interval: Duration,
+    grow_after: u32,
+    shrink_after: u32,
+    step: usize,
+    cooldown: Duration,
+    on_resize: Option<Box<dyn Fn(&Resize) + Send + Sync>>,
+    busy: u32,
+    quiet: u32,
+    last_resize: Option<Instant>,
+}
+
+impl Autoscaler {
+    /// Creates a new [`Autoscaler`] for `pool`, growing it up to the
+    /// [`Config::max_connections`] of its manager.
+    ///
+    /// [`Config::max_connections`]: crate::Config::max_connections
+    pub fn new(pool: &Pool) -> Self {
+        let max_connections = pool.manager().config().max_connections as usize;
+        Self {
+            pool: TaskPool::new(pool),
+            min_connections: 1,
+            max_connections,
+            interval: Duration::from_secs(1),
+            grow_after: 3,
+            shrink_after: 60,
+            step: 1,
+            cooldown: Duration::from_secs(10),
+            on_resize: None,
+            busy: 0,
+            quiet: 0,
+            last_resize: None,
+        }
+    }
+
+    /// Sets the size the pool is never shrunk below. Defaults to 1.
+    pub fn min_connections(mut self, n: usize) -> Self {
+        self.min_connections = n;
+        self
+    }
+
+    /// Sets the size the pool is never grown beyond. Defaults to
+    /// [`Config::max_connections`].
+    ///
+    /// [`Config::max_connections`]: crate::Config::max_connections
+    pub fn max_connections(mut self, n: usize) -> Self {
+        self.max_connections = n;
+        self
+    }
+
+    /// Sets the time between two samples of the pressure. Defaults to one
+    /// second.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the number of samples in a row with waiting tasks before the
+    /// pool grows. Defaults to 3.
+    pub fn grow_after(mut self, samples: u32) -> Self {
+        self.grow_after = samples;
+        self
+    }
+
+    /// Sets the number of quiet samples in a row before the pool shrinks.
+    /// Defaults to 60.
+    pub fn shrink_after(mut self, samples: u32) -> Self {
+        self.shrink_after = samples;
+        self
+    }
+
+    /// Sets the number of connections the pool grows or shrinks by at once.
+    /// Defaults to 1.
+    pub fn step(mut self, n: usize) -> Self {
+        self.step = n.max(1);
+        self
+    }
+
+    /// Sets the minimum time between two resizes. Defaults to 10 seconds.
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Sets a callback which is invoked after every resize.
+    pub fn on_resize(mut self, f: impl Fn(&Resize) + Send + Sync + 'static) -> Self {
+        self.on_resize = Some(Box::new(f));
+        self
+    }
+
+    /// Samples the pressure on the pool once and resizes it if needed.
+    ///
+    /// This is what the scheduler does every `interval`. Returns the resize
+    /// made, if any. Does nothing once the pool was dropped.
+    pub fn tick(&mut self) -> Option<Resize> {
+        let pool = self.pool.get().ok()?;
+        let pressure = checkout::pressure(&pool);
+        if pressure.saturation > 0.0 {
+            self.busy += 1;
+            self.quiet = 0;
+        } else if pressure.utilization < 0.5 {
+            self.busy = 0;
+            self.quiet += 1;
+        } else {
+            self.busy = 0;
+            self.quiet = 0;
+        }
+
+        let now = pool.manager().clock.now();
+        if self
+            .last_resize
+            .is_some_and(|last| now.saturating_duration_since(last) < self.cooldown)
+        {
+            return None;
+        }
+        let from = pool.status().max_size;
+        let to = if self.busy >= self.grow_after {
+            from.saturating_add(self.step).min(self.max_connections)
+        } else if self.quiet >= self.shrink_after {
+            from.saturating_sub(self.step).max(self.min_connections)
+        } else {
+            return None;
+        };
+        if to == from {
+            return None;
+        }
+
+        pool.resize(to);
+        self.busy = 0;
+        self.quiet = 0;
+        self.last_resize = Some(now);
+        let resize = Resize { from, to, pressure };
+        if let Some(on_resize) = &self.on_resize {
+            if let Err(e) = hook::catch("on_resize", || on_resize(&resize)) {
+                pool.manager().errors.record(&e);
+            }
+        }
+        Some(resize)
+    }
+
+    /// Runs the autoscaler until the [`Pool`] is closed or dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoRuntimeSpecified`] if neither an [`Executor`] nor
+    /// a [`Runtime`] was configured for the [`Manager`] of the pool.
+    ///
+    /// [`Executor`]: crate::rt::Executor
+    /// [`Runtime`]: crate::Runtime
+    /// [`Manager`]: crate::Manager
+    pub async fn run(mut self) -> Result<()> {
+        let executor = self
+            .pool
+            .get()?
+            .manager()
+            .background_executor()
+            .ok_or(Error::NoRuntimeSpecified)?;
+        while self.pool.sleep(&*executor, self.interval).await {
+            let _ = self.tick();
+        }
+        Ok(())
+    }
+
+    /// Runs the autoscaler as a background task on the [`Executor`] of the
+    /// pool until the [`Pool`] is closed or dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoRuntimeSpecified`] if neither an [`Executor`] nor
+    /// a [`Runtime`] was configured for the [`Manager`] of the pool.
+    ///
+    /// [`Executor`]: crate::rt::Executor
+    /// [`Runtime`]: crate::Runtime
+    /// [`Manager`]: crate::Manager
+    pub fn spawn(self) -> Result<()> {
+        let executor = self
+            .pool
+            .get()?
+            .manager()
+            .background_executor()
+            .ok_or(Error::NoRuntimeSpecified)?;
+        let pool = self.pool.clone();
+        executor.spawn(Box::pin(hook::task(pool, "Autoscaler", async move {
+            let _ = self.run().await;
+        })));
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Autoscaler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Autoscaler")
+            .field("pool", &self.pool)
+            .field("min_connections", &self.min_connections)
+            .field("max_connections", &self.max_connections)
+            .field("interval", &self.interval)
+            .field("grow_after", &self.grow_after)
+            .field("shrink_after", &self.shrink_after)
+            .field("step", &self.step)
+            .field("cooldown", &self.cooldown)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    convert::Infallible,
+    ops::{Deref, DerefMut},
+};
+
+use axum_core::{
+    extract::{FromRef, FromRequestParts},
+    response::{IntoResponse, Response},
+};
+use http::{request::Parts, StatusCode};
+
+use crate::{checkout, request::RequestConn, Error, Object, Pool, PoolError};
+
+/// Extractor checking a connection out of the [`Pool`] stored in the state
+/// of the router.
+///
+/// ```rust,ignore
+/// async fn handler(DbConn(conn): DbConn) -> Result<String, DbError> {
+///     let version = conn.server_version().await?;
+///     Ok(version)
+/// }
+///
+/// let app = Router::new().route("/", get(handler)).with_state(pool);
+/// ```
+///
+/// The pool is taken from the state using [`FromRef`], so it can be a field
+/// of a larger state as well.
+#[derive(Debug)]
+pub struct DbConn(pub Object);
+
+impl<S> FromRequestParts<S> for DbConn
+where
+    Pool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = DbError;
+
+    async fn from_request_parts(_parts: &mut Parts, state: &S) -> Result<Self, DbError> {
+        let pool = Pool::from_ref(state);
+        Ok(Self(checkout::get(&pool).await?))
+    }
+}
+
+/// Extracts the [`RequestConn`] of the request, creating it from the
+/// [`Pool`] stored in the state of the router unless a [`PoolLayer`] added
+/// one already.
+///
+/// All extractors and handlers of a request share the same [`RequestConn`].
+///
+/// [`PoolLayer`]: crate::tower::PoolLayer
+impl<S> FromRequestParts<S> for RequestConn
+where
+    Pool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Infallible> {
+        if let Some(conn) = parts.extensions.get::<RequestConn>() {
+            return Ok(conn.clone());
+        }
+        let conn = RequestConn::new(&Pool::from_ref(state));
+        let _ = parts.extensions.insert(conn.clone());
+        Ok(conn)
+    }
+}
+
+impl Deref for DbConn {
+    type Target = Object;
+
+    fn deref(&self) -> &Object {
+        &self.0
+    }
+}
+
+impl DerefMut for DbConn {
+    fn deref_mut(&mut self) -> &mut Object {
+        &mut self.0
+    }
+}
+
+/// [`Error`] which can be returned from handlers.
+///
+/// The status of the response is taken from [`Error::http_status()`]. The
+/// response body contains no details about the error.
+#[derive(Debug)]
+pub struct DbError(pub Error);
+
+impl DbError {
+    /// Returns the [`StatusCode`] of the response.
+    #[must_use]
+    pub fn status(&self) -> StatusCode {
+        status(&self.0)
+    }
+}
+
+fn status(e: &Error) -> StatusCode {
+    StatusCode::from_u16(e.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+fn response(status: StatusCode) -> Response {
+    (status, status.canonical_reason().unwrap_or_default()).into_response()
+}
+
+impl IntoResponse for DbError {
+    fn into_response(self) -> Response {
+        response(self.status())
+    }
+}
+
+/// Responds with the status of [`Error::http_status()`] and no details about
+/// the error, like [`DbError`].
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        response(status(&self))
+    }
+}
+
+impl From<Error> for DbError {
+    fn from(e: Error) -> Self {
+        Self(e)
+    }
+}
+
+impl From<PoolError> for DbError {
+    fn from(e: PoolError) -> Self {
+        Self(e.into())
+    }
+}
+
+impl std::fmt::Display for DbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for DbError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
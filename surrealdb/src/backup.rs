@@ -0,0 +1,328 @@
+use std::{
+    fmt,
+    future::Future,
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+    pin::pin,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use deadpool_runtime::Runtime;
+use futures::{Stream, StreamExt};
+
+use crate::{hook, rt::Spawn, shutdown::TaskPool, Error, Pool, Result};
+
+const BACKUP_PREFIX: &str = "backup-";
+const BACKUP_SUFFIX: &str = ".surql";
+const PARTIAL_SUFFIX: &str = ".partial";
+
+/// Storage for backups created by a [`BackupScheduler`].
+///
+/// Backups are identified by their name. Names created by the scheduler
+/// sort chronologically.
+pub trait BackupSink: Send + Sync {
+    /// Stores a backup under the given `name`.
+    ///
+    /// The backup is streamed in chunks as the server exports it, so it
+    /// never has to fit in memory. If `data` yields an error, the backup is
+    /// incomplete and must not be stored.
+    fn store(
+        &self,
+        name: &str,
+        data: impl Stream<Item = io::Result<Vec<u8>>> + Send,
+    ) -> impl Future<Output = io::Result<()>> + Send;
+
+    /// Lists the names of all stored backups.
+    fn list(&self) -> impl Future<Output = io::Result<Vec<String>>> + Send;
+
+    /// Removes the backup with the given `name`.
+    fn remove(&self, name: &str) -> impl Future<Output = io::Result<()>> + Send;
+}
+
+/// [`BackupSink`] storing backups as files in a directory.
+#[derive(Clone, Debug)]
+pub struct FsSink {
+    dir: PathBuf,
+    runtime: Runtime,
+}
+
+impl FsSink {
+    /// Creates a new [`FsSink`] storing backups in `dir`.
+    ///
+    /// File system operations are run on threads where blocking is
+    /// acceptable using the given `runtime`.
+    pub fn new(dir: impl Into<PathBuf>, runtime: Runtime) -> Self {
+        Self {
+            dir: dir.into(),
+            runtime,
+        }
+    }
+
+    async fn blocking<F, R>(&self, f: F) -> io::Result<R>
+    where
+        F: FnOnce() -> io::Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        self.runtime
+            .spawn_blocking(f)
+            .await
+            .map_err(|e| io::Error::other(e.to_string()))?
+    }
+
+    async fn write(
+        &self,
+        path: &Path,
+        data: impl Stream<Item = io::Result<Vec<u8>>> + Send,
+    ) -> io::Result<()> {
+        let path = path.to_owned();
+        let mut file = self.blocking(move || std::fs::File::create(path)).await?;
+        let mut data = pin!(data);
+        while let Some(chunk) = data.next().await {
+            let chunk = chunk?;
+            file = self
+                .blocking(move || {
+                    file.write_all(&chunk)?;
+                    Ok(file)
+                })
+                .await?;
+        }
+        self.blocking(move || file.sync_all()).await
+    }
+}
+
+/// Writes backups into a file with a `.partial` suffix first and renames it
+/// once complete, so [`BackupSink::list()`] never returns incomplete backups.
+impl BackupSink for FsSink {
+    async fn store(
+        &self,
+        name: &str,
+        data: impl Stream<Item = io::Result<Vec<u8>>> + Send,
+    ) -> io::Result<()> {
+        let path = self.dir.join(name);
+        let partial = self.dir.join(format!("{}{}", name, PARTIAL_SUFFIX));
+        if let Err(e) = self.write(&partial, data).await {
+            let _ = self.blocking(move || std::fs::remove_file(partial)).await;
+            return Err(e);
+        }
+        self.blocking(move || std::fs::rename(partial, path)).await
+    }
+
+    async fn list(&self) -> io::Result<Vec<String>> {
+        let dir = self.dir.clone();
+        self.blocking(move || {
+            let mut names = Vec::new();
+            for entry in std::fs::read_dir(dir)? {
+                if let Some(name) = entry?.file_name().to_str() {
+                    if name.starts_with(BACKUP_PREFIX) && name.ends_with(BACKUP_SUFFIX) {
+                        names.push(name.to_string());
+                    }
+                }
+            }
+            Ok(names)
+        })
+        .await
+    }
+
+    async fn remove(&self, name: &str) -> io::Result<()> {
+        let path = self.dir.join(name);
+        self.blocking(move || std::fs::remove_file(path)).await
+    }
+}
+
+/// Background task creating periodic exports of the database.
+///
+/// Use [`BackupScheduler::spawn()`] for running the scheduler on the
+/// [`Executor`] of the pool, or [`BackupScheduler::run()`] with the executor
+/// of your choice:
+///
+/// ```rust,ignore
+/// let sink = FsSink::new("/var/backups/surrealdb", Runtime::Tokio1);
+/// BackupScheduler::new(&pool, sink, Duration::from_secs(3600))
+///     .keep_last(24)
+///     .on_failure(|e| eprintln!("Backup failed: {}", e))
+///     .spawn()?;
+/// ```
+///
+/// Backups run every `interval` by default. Cron expressions aren't parsed
+/// by this crate, but [`BackupScheduler::schedule()`] accepts any function
+/// computing the time until the next backup, e.g. from a cron expression
+/// parsed by a crate of your choice:
+///
+/// ```rust,ignore
+/// let cron = cron::Schedule::from_str("0 0 3 * * *")?;
+/// BackupScheduler::new(&pool, sink, Duration::from_secs(86400))
+///     .schedule(move |now| {
+///         let next = cron.after(&DateTime::<Utc>::from(now)).next().unwrap();
+///         SystemTime::from(next).duration_since(now).unwrap_or_default()
+///     })
+///     .spawn()?;
+/// ```
+///
+/// The scheduler doesn't keep the pool alive, it stops once the pool is
+/// dropped.
+///
+/// [`Executor`]: crate::rt::Executor
+pub struct BackupScheduler<S> {
+    pool: TaskPool,
+    sink: S,
+    interval: Duration,
+    schedule: Option<Box<dyn Fn(SystemTime) -> Duration + Send + Sync>>,
+    keep_last: Option<usize>,
+    on_failure: Option<Box<dyn Fn(&Error) + Send + Sync>>,
+}
+
+impl<S: BackupSink> BackupScheduler<S> {
+    /// Creates a new [`BackupScheduler`] exporting the database of `pool`
+    /// into `sink` every `interval`.
+    pub fn new(pool: &Pool, sink: S, interval: Duration) -> Self {
+        Self {
+            pool: TaskPool::new(pool),
+            sink,
+            interval,
+            schedule: None,
+            keep_last: None,
+            on_failure: None,
+        }
+    }
+
+    /// Keeps only the `n` most recent backups. Older ones are removed after
+    /// every successful backup.
+    pub fn keep_last(mut self, n: usize) -> Self {
+        self.keep_last = Some(n);
+        self
+    }
+
+    /// Computes the time to wait before every backup from the current time
+    /// using `f`, instead of waiting for the fixed interval.
+    ///
+    /// This allows for backups at fixed times, e.g. every night at 3am.
+    pub fn schedule(mut self, f: impl Fn(SystemTime) -> Duration + Send + Sync + 'static) -> Self {
+        self.schedule = Some(Box::new(f));
+        self
+    }
+
+    /// Sets a callback which is invoked whenever a scheduled backup fails.
+    pub fn on_failure(mut self, f: impl Fn(&Error) + Send + Sync + 'static) -> Self {
+        self.on_failure = Some(Box::new(f));
+        self
+    }
+
+    /// Creates a backup right away and applies the retention policy.
+    ///
+    /// Returns the name of the created backup. Names contain the time of
+    /// the backup with nanosecond precision.
+    pub async fn backup_now(&self) -> Result<String> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let name = format!(
+            "{}{:020}-{:09}{}",
+            BACKUP_PREFIX,
+            now.as_secs(),
+            now.subsec_nanos(),
+            BACKUP_SUFFIX
+        );
+        let conn = self.pool.get()?.manager().connect_dedicated().await?;
+        let backup = conn.export(()).await?;
+        // The sink only sees I/O errors, so keep export errors for reporting
+        // them as such.
+        let mut failure = None;
+        let data = backup.map(|chunk| {
+            chunk.map_err(|e| {
+                let error = io::Error::other(e.to_string());
+                failure = Some(e);
+                error
+            })
+        });
+        let stored = self.sink.store(&name, data).await;
+        if let Some(e) = failure {
+            return Err(e.into());
+        }
+        stored?;
+        if let Some(keep_last) = self.keep_last {
+            let mut names = self.sink.list().await?;
+            names.sort();
+            let excess = names.len().saturating_sub(keep_last);
+            for name in &names[..excess] {
+                self.sink.remove(name).await?;
+            }
+        }
+        Ok(name)
+    }
+
+    /// Runs the scheduler until the [`Pool`] is closed or dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoRuntimeSpecified`] if neither an [`Executor`] nor
+    /// a [`Runtime`] was configured for the [`Manager`] of the pool. Failing
+    /// backups are reported to the [`BackupScheduler::on_failure()`]
+    /// callback and don't stop the scheduler.
+    ///
+    /// [`Executor`]: crate::rt::Executor
+    /// [`Manager`]: crate::Manager
+    pub async fn run(self) -> Result<()> {
+        let executor = self
+            .pool
+            .get()?
+            .manager()
+            .background_executor()
+            .ok_or(Error::NoRuntimeSpecified)?;
+        loop {
+            let delay = match &self.schedule {
+                Some(schedule) => schedule(SystemTime::now()),
+                None => self.interval,
+            };
+            if !self.pool.sleep(&*executor, delay).await {
+                break;
+            }
+            if let Err(e) = self.backup_now().await {
+                if let Some(on_failure) = &self.on_failure {
+                    if let Err(e) = hook::catch("on_failure", || on_failure(&e)) {
+                        if let Ok(pool) = self.pool.get() {
+                            pool.manager().errors.record(&e);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S: BackupSink + 'static> BackupScheduler<S> {
+    /// Runs the scheduler as a background task on the [`Executor`] of the
+    /// pool until the [`Pool`] is closed or dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoRuntimeSpecified`] if neither an [`Executor`] nor
+    /// a [`Runtime`] was configured for the [`Manager`] of the pool.
+    ///
+    /// [`Executor`]: crate::rt::Executor
+    /// [`Manager`]: crate::Manager
+    pub fn spawn(self) -> Result<()> {
+        let executor = self
+            .pool
+            .get()?
+            .manager()
+            .background_executor()
+            .ok_or(Error::NoRuntimeSpecified)?;
+        let pool = self.pool.clone();
+        executor.spawn(Box::pin(hook::task(pool, "BackupScheduler", async move {
+            let _ = self.run().await;
+        })));
+        Ok(())
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for BackupScheduler<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BackupScheduler")
+            .field("pool", &self.pool)
+            .field("sink", &self.sink)
+            .field("interval", &self.interval)
+            .field("keep_last", &self.keep_last)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,58 @@
+//! Checks step by step whether a pool can connect to a SurrealDB server.
+//!
+//! ```text
+//! surreal-pool-doctor [CONFIG.json]
+//! ```
+//!
+//! Without a configuration file the configuration is read from the
+//! environment variables of the SurrealDB CLI, see
+//! `Config::from_surreal_env()`.
+
+use std::{env, error::Error, fs, process::ExitCode};
+
+use deadpool_surrealdb::{diagnostics, Config, Runtime};
+
+fn config() -> Result<Config, String> {
+    match env::args().nth(1) {
+        Some(path) => {
+            let json = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+            serde_json::from_str(&json).map_err(|e| format!("{}: {}", path, e))
+        }
+        None => Config::from_surreal_env().map_err(|e| match e {
+            deadpool_surrealdb::Error::InvalidConfig(message) => message,
+            e => e.to_string(),
+        }),
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let config = match config() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    println!(
+        "Connecting to {} (namespace {}, database {})",
+        config.host, config.ns, config.db
+    );
+    let steps = diagnostics::diagnose(&config, Runtime::Tokio1).await;
+    for step in &steps {
+        let elapsed = step.elapsed.as_millis();
+        let Some(e) = &step.error else {
+            println!("[ok]   {:<12} {:>6} ms", step.name, elapsed);
+            continue;
+        };
+        println!("[fail] {:<12} {:>6} ms  {}", step.name, elapsed, e);
+        let mut source = e.source();
+        while let Some(e) = source {
+            println!("       caused by: {}", e);
+            source = e.source();
+        }
+        return ExitCode::FAILURE;
+    }
+    println!("All checks passed");
+    ExitCode::SUCCESS
+}
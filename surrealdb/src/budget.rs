@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Cap on the number of connections opened by several pools together, e.g.
+/// per-tenant or per-purpose pools of the same SurrealDB cluster.
+///
+/// Set it for every [`Manager`] using [`Manager::connection_budget()`].
+/// Creating a connection waits until the budget has room for it, bounded by
+/// the create timeout of the pool. Every connection holds its share of the
+/// budget until it is closed, including idle ones. Set
+/// [`Config::max_idle_time`] so idle connections of one pool don't starve
+/// the others.
+///
+/// All clones of a [`ConnectionBudget`] share the same budget.
+///
+/// ```rust,ignore
+/// let budget = ConnectionBudget::new(50);
+/// let reports = Pool::builder(Manager::from_config(&reports).connection_budget(budget.clone()))
+///     .build()?;
+/// let api = Pool::builder(Manager::from_config(&api).connection_budget(budget))
+///     .build()?;
+/// ```
+///
+/// [`Config::max_idle_time`]: crate::Config::max_idle_time
+/// [`Manager`]: crate::Manager
+/// [`Manager::connection_budget()`]: crate::Manager::connection_budget
+#[derive(Clone, Debug)]
+pub struct ConnectionBudget {
+    semaphore: Arc<Semaphore>,
+    limit: usize,
+}
+
+impl ConnectionBudget {
+    /// Creates a new [`ConnectionBudget`] of `limit` connections.
+    #[must_use]
+    pub fn new(limit: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(limit)),
+            limit,
+        }
+    }
+
+    /// Returns the maximum number of connections.
+    #[must_use]
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Returns the number of connections currently open.
+    #[must_use]
+    pub fn in_use(&self) -> usize {
+        self.limit - self.semaphore.available_permits()
+    }
+
+    /// Waits until the budget has room for another connection.
+    pub(crate) async fn acquire(&self) -> OwnedSemaphorePermit {
+        // The semaphore is never closed.
+        self.semaphore.clone().acquire_owned().await.unwrap()
+    }
+}
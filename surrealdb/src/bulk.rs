@@ -0,0 +1,304 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use futures::stream::{self, StreamExt};
+use serde::{Serialize, Serializer};
+
+use crate::{checkout, retry::RetryBudget, surql, Error, Pool};
+
+/// Number of attempts made for every chunk before it is reported as failed.
+///
+/// Only errors for which [`Error::is_retryable()`] returns `true` are
+/// retried. Chunks of [`PoolExt::bulk_insert()`] are only retried if
+/// checking out a connection failed, as the server may have inserted the
+/// records already once the statement was sent.
+///
+/// [`PoolExt::bulk_insert()`]: crate::PoolExt::bulk_insert
+pub const CHUNK_ATTEMPTS: usize = 3;
+
+/// Time waited before retrying a chunk for the first time. The wait doubles
+/// for every further attempt. Pools without a [`Runtime`] retry right away.
+///
+/// [`Runtime`]: crate::Runtime
+pub const CHUNK_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Summary of a [`PoolExt::bulk_insert()`] run.
+///
+/// [`PoolExt::bulk_insert()`]: crate::PoolExt::bulk_insert
+#[derive(Debug, Default)]
+pub struct BulkReport {
+    /// Number of records inserted successfully
+    pub inserted: usize,
+    /// Number of chunks the input was split into
+    pub chunks: usize,
+    /// Chunks which could not be inserted, even after retrying
+    pub failed: Vec<ChunkFailure>,
+}
+
+impl BulkReport {
+    /// Returns `true` if every chunk was inserted successfully.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// A chunk which could not be inserted.
+#[derive(Debug)]
+pub struct ChunkFailure {
+    /// Index of the chunk
+    pub index: usize,
+    /// Offset of the first record of the chunk within the input
+    pub offset: usize,
+    /// Number of records in the chunk
+    pub len: usize,
+    /// Number of attempts made
+    pub attempts: usize,
+    /// Error of the last attempt
+    pub error: Error,
+}
+
+/// Summary of a [`PoolExt::bulk_upsert()`] run.
+///
+/// [`PoolExt::bulk_upsert()`]: crate::PoolExt::bulk_upsert
+#[derive(Debug, Default)]
+pub struct UpsertReport {
+    /// Number of records upserted successfully
+    pub upserted: usize,
+    /// Number of chunks the input was split into
+    pub chunks: usize,
+    /// Chunks which could not be sent, even after retrying
+    pub failed_chunks: Vec<ChunkFailure>,
+    /// Records which were rejected by the database
+    pub failed_items: Vec<ItemFailure>,
+}
+
+impl UpsertReport {
+    /// Returns `true` if every record was upserted successfully.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.failed_chunks.is_empty() && self.failed_items.is_empty()
+    }
+}
+
+/// A record which could not be upserted.
+#[derive(Debug)]
+pub struct ItemFailure {
+    /// Offset of the record within the input
+    pub offset: usize,
+    /// Id of the record
+    pub id: String,
+    /// Error reported by the database
+    pub error: Error,
+}
+
+/// How existing records are updated by [`PoolExt::bulk_upsert()`].
+///
+/// [`PoolExt::bulk_upsert()`]: crate::PoolExt::bulk_upsert
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UpsertStrategy {
+    /// Merges the given fields into existing records (`UPSERT ... MERGE`)
+    #[default]
+    MergePatch,
+    /// Replaces the content of existing records (`UPSERT ... CONTENT`)
+    Replace,
+}
+
+/// Shared chunk of records which can be bound to a query multiple times
+/// without cloning the records.
+struct Chunk<T>(Arc<[T]>);
+
+impl<T: Serialize> Serialize for Chunk<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+/// Runs `attempt` for a chunk until it succeeds, fails with an error which
+/// isn't retryable or [`CHUNK_ATTEMPTS`] are made.
+///
+/// Returns the number of attempts made along with the result.
+async fn retry_chunk<T, F, Fut>(pool: &Pool, mut attempt: F) -> (usize, Result<T, Error>)
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, Error>>,
+{
+    let runtime = pool.manager().runtime;
+    let mut budget = RetryBudget::new(CHUNK_ATTEMPTS as u32);
+    if runtime.is_some() {
+        budget = budget.backoff(CHUNK_BACKOFF);
+    }
+    let mut attempts = 0;
+    let result = budget
+        .run(runtime, Error::is_retryable, Error::QueryTimeout, || {
+            attempts += 1;
+            attempt()
+        })
+        .await;
+    (attempts, result)
+}
+
+pub(crate) async fn insert<T>(
+    pool: &Pool,
+    table: &str,
+    items: Vec<T>,
+    chunk_size: usize,
+    parallelism: usize,
+) -> BulkReport
+where
+    T: Serialize + Send + Sync + 'static,
+{
+    let chunk_size = chunk_size.max(1);
+    let sql = format!("INSERT INTO {} $items", surql::escape_ident(table));
+    let chunks = split(items, chunk_size);
+
+    let mut report = BulkReport {
+        chunks: chunks.len(),
+        ..BulkReport::default()
+    };
+    let sql = &sql;
+    let mut results = stream::iter(chunks.into_iter().enumerate())
+        .map(|(index, chunk)| async move {
+            let (attempts, result) =
+                retry_chunk(pool, || insert_chunk(pool, sql, Chunk(chunk.clone()))).await;
+            let result = result.and_then(|inserted| inserted);
+            (index, chunk.len(), result.map_err(|e| (attempts, e)))
+        })
+        .buffer_unordered(parallelism.max(1));
+
+    while let Some((index, len, result)) = results.next().await {
+        match result {
+            Ok(()) => report.inserted += len,
+            Err((attempts, error)) => report.failed.push(ChunkFailure {
+                index,
+                offset: index * chunk_size,
+                len,
+                attempts,
+                error,
+            }),
+        }
+    }
+    report.failed.sort_by_key(|f| f.index);
+    report
+}
+
+/// Inserts a chunk, returning errors which occurred after the statement
+/// was sent as `Ok(Err(_))` so they aren't retried.
+async fn insert_chunk<T>(
+    pool: &Pool,
+    sql: &str,
+    chunk: Chunk<T>,
+) -> Result<Result<(), Error>, Error>
+where
+    T: Serialize + Send + Sync + 'static,
+{
+    let conn = checkout::get(pool).await?;
+    let result = match conn.query(sql).bind(("items", chunk)).await {
+        Ok(response) => response.check().map(|_| ()).map_err(Error::from),
+        Err(e) => Err(e),
+    };
+    Ok(result)
+}
+
+pub(crate) async fn upsert<T>(
+    pool: &Pool,
+    table: &str,
+    items: Vec<(String, T)>,
+    strategy: UpsertStrategy,
+    chunk_size: usize,
+    parallelism: usize,
+) -> UpsertReport
+where
+    T: Serialize + Send + Sync + 'static,
+{
+    let chunk_size = chunk_size.max(1);
+    let chunks = split(items, chunk_size);
+
+    let mut report = UpsertReport {
+        chunks: chunks.len(),
+        ..UpsertReport::default()
+    };
+    let mut results = stream::iter(chunks.into_iter().enumerate())
+        .map(|(index, chunk)| async move {
+            let sql = &upsert_sql(chunk.len(), strategy);
+            let (attempts, result) = retry_chunk(pool, || {
+                upsert_chunk(pool, table, sql, Chunk(chunk.clone()))
+            })
+            .await;
+            (index, chunk, result.map_err(|e| (attempts, e)))
+        })
+        .buffer_unordered(parallelism.max(1));
+
+    while let Some((index, chunk, result)) = results.next().await {
+        match result {
+            Ok(mut errors) => {
+                for (i, (id, _)) in chunk.iter().enumerate() {
+                    match errors.remove(&i) {
+                        Some(error) => report.failed_items.push(ItemFailure {
+                            offset: index * chunk_size + i,
+                            id: id.clone(),
+                            error: error.into(),
+                        }),
+                        None => report.upserted += 1,
+                    }
+                }
+            }
+            Err((attempts, error)) => report.failed_chunks.push(ChunkFailure {
+                index,
+                offset: index * chunk_size,
+                len: chunk.len(),
+                attempts,
+                error,
+            }),
+        }
+    }
+    report.failed_chunks.sort_by_key(|f| f.index);
+    report.failed_items.sort_by_key(|f| f.offset);
+    report
+}
+
+/// Creates one `UPSERT` statement for every record of a chunk, so errors can
+/// be reported per record.
+fn upsert_sql(len: usize, strategy: UpsertStrategy) -> String {
+    let clause = match strategy {
+        UpsertStrategy::MergePatch => "MERGE",
+        UpsertStrategy::Replace => "CONTENT",
+    };
+    (0..len)
+        .map(|i| {
+            format!(
+                "UPSERT type::thing($table, $items[{i}][0]) {clause} $items[{i}][1] RETURN NONE;\n"
+            )
+        })
+        .collect()
+}
+
+async fn upsert_chunk<T>(
+    pool: &Pool,
+    table: &str,
+    sql: &str,
+    chunk: Chunk<(String, T)>,
+) -> Result<HashMap<usize, surrealdb::Error>, Error>
+where
+    T: Serialize + Send + Sync + 'static,
+{
+    let conn = checkout::get(pool).await?;
+    let mut response = conn
+        .query(sql)
+        .bind(("table", table.to_string()))
+        .bind(("items", chunk))
+        .await?;
+    Ok(response.take_errors())
+}
+
+fn split<T>(items: Vec<T>, chunk_size: usize) -> Vec<Arc<[T]>> {
+    let mut chunks: Vec<Arc<[T]>> = Vec::with_capacity(items.len().div_ceil(chunk_size));
+    let mut items = items.into_iter();
+    loop {
+        let chunk: Vec<T> = items.by_ref().take(chunk_size).collect();
+        if chunk.is_empty() {
+            break;
+        }
+        chunks.push(chunk.into());
+    }
+    chunks
+}
@@ -0,0 +1,315 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+    future::IntoFuture,
+    marker::PhantomData,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use deadpool::managed::Timeouts;
+use futures::future::BoxFuture;
+use serde::Serialize;
+use surrealdb::opt::QueryResult;
+
+use crate::{
+    checkout,
+    client::{self, Binding},
+    ErrorKind, Pool, Result,
+};
+
+type Erased = Arc<dyn Any + Send + Sync>;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Key {
+    sql: String,
+    bindings: String,
+    result: TypeId,
+}
+
+struct Entry {
+    value: Erased,
+    fetched: Instant,
+}
+
+/// Result of a query run using a [`QueryCache`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cached<R> {
+    /// Result of the query
+    pub value: R,
+    /// Whether the result outlived its TTL and was served because no
+    /// connection could be checked out in time, see
+    /// [`QueryCache::serve_stale()`]
+    pub stale: bool,
+}
+
+impl<R> Cached<R> {
+    /// Returns the result of the query, whether stale or not.
+    pub fn into_inner(self) -> R {
+        self.value
+    }
+}
+
+/// Cache of query results, optionally serving outdated results while the
+/// database is unreachable.
+///
+/// Results are cached for the TTL set using [`QueryCache::ttl()`], keyed by
+/// the text, the bindings and the result type of the query. Queries
+/// containing mutating statements are never cached.
+///
+/// With [`QueryCache::serve_stale()`], expired results are kept and served
+/// flagged as [`Cached::stale`] if no healthy connection can be checked out
+/// within a short deadline, which keeps read paths available during brief
+/// outages.
+///
+/// All clones of a [`QueryCache`] share the cached results.
+///
+/// ```rust,ignore
+/// let cache = QueryCache::new(&pool)
+///     .ttl(Duration::from_secs(10))
+///     .serve_stale(Duration::from_millis(200));
+/// let products = cache.query::<Vec<Product>>("SELECT * FROM product").await?;
+/// if products.stale {
+///     warn!("Serving products from cache");
+/// }
+/// ```
+#[derive(Clone)]
+pub struct QueryCache {
+    pool: Pool,
+    ttl: Duration,
+    capacity: usize,
+    stale_deadline: Option<Duration>,
+    entries: Arc<Mutex<HashMap<Key, Entry>>>,
+}
+
+impl QueryCache {
+    /// Creates a new [`QueryCache`] running its queries using `pool`.
+    #[must_use]
+    pub fn new(pool: &Pool) -> Self {
+        Self {
+            pool: pool.clone(),
+            ttl: Duration::from_secs(30),
+            capacity: 1024,
+            stale_deadline: None,
+            entries: Arc::default(),
+        }
+    }
+
+    /// Sets the time results are served from the cache. Defaults to 30
+    /// seconds.
+    #[must_use]
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets the maximum number of cached results. The oldest result is
+    /// evicted once it is exceeded. Defaults to 1024.
+    #[must_use]
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Serves expired results flagged as stale if no connection can be
+    /// checked out within `deadline` or the connection breaks.
+    ///
+    /// Enforcing the deadline requires a [`Runtime`] to be configured for
+    /// the [`Pool`].
+    ///
+    /// [`Runtime`]: crate::Runtime
+    #[must_use]
+    pub fn serve_stale(mut self, deadline: Duration) -> Self {
+        self.stale_deadline = Some(deadline);
+        self
+    }
+
+    /// Prepares the given `query` returning the result of its first
+    /// statement as `R`.
+    pub fn query<R>(&self, query: impl Into<String>) -> CachedQuery<'_, R> {
+        CachedQuery {
+            cache: self,
+            sql: query.into(),
+            bindings: Vec::new(),
+            result: PhantomData,
+        }
+    }
+
+    /// Returns the number of cached results, including expired ones.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries().len()
+    }
+
+    /// Indicates whether no results are cached.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries().is_empty()
+    }
+
+    /// Removes all cached results, e.g. after writes they depend on.
+    pub fn clear(&self) {
+        self.entries().clear();
+    }
+
+    fn insert(&self, key: Key, value: Erased, fetched: Instant) {
+        let mut entries = self.entries();
+        if entries.len() >= self.capacity && !entries.contains_key(&key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.fetched)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                let _ = entries.remove(&oldest);
+            }
+        }
+        let _ = entries.insert(key, Entry { value, fetched });
+    }
+
+    fn entries(&self) -> MutexGuard<'_, HashMap<Key, Entry>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for QueryCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueryCache")
+            .field("pool", &self.pool)
+            .field("ttl", &self.ttl)
+            .field("capacity", &self.capacity)
+            .field("stale_deadline", &self.stale_deadline)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Query prepared using [`QueryCache::query()`].
+#[must_use = "queries do nothing unless awaited"]
+pub struct CachedQuery<'a, R> {
+    cache: &'a QueryCache,
+    sql: String,
+    bindings: Vec<Binding>,
+    result: PhantomData<fn() -> R>,
+}
+
+impl<R> CachedQuery<'_, R>
+where
+    R: Clone + Send + Sync + 'static,
+    usize: QueryResult<R>,
+{
+    /// Binds parameters to the query.
+    ///
+    /// See [`Query::bind()`] for details.
+    ///
+    /// [`Query::bind()`]: crate::client::Query::bind
+    pub fn bind(mut self, bindings: impl Serialize + Send + Sync + 'static) -> Self {
+        self.bindings.push(Binding(Arc::new(bindings)));
+        self
+    }
+
+    async fn run(self) -> Result<Cached<R>> {
+        let cache = self.cache;
+        let bindings = serde_json::to_string(&self.bindings);
+        let (Ok(bindings), None) = (bindings, client::mutating_statement(&self.sql)) else {
+            let value = fetch(cache, self.sql, self.bindings, None).await?;
+            return Ok(Cached {
+                value,
+                stale: false,
+            });
+        };
+        let key = Key {
+            sql: self.sql.clone(),
+            bindings,
+            result: TypeId::of::<R>(),
+        };
+        let now = cache.pool.manager().clock.now();
+        let cached = cache.entries().get(&key).map(|entry| {
+            let value = entry
+                .value
+                .downcast_ref::<R>()
+                .expect("Entries are keyed by the type of their result")
+                .clone();
+            (
+                value,
+                now.saturating_duration_since(entry.fetched) < cache.ttl,
+            )
+        });
+        let stale = match cached {
+            Some((value, true)) => {
+                return Ok(Cached {
+                    value,
+                    stale: false,
+                })
+            }
+            Some((value, false)) if cache.stale_deadline.is_some() => Some(value),
+            _ => None,
+        };
+        match fetch::<R>(cache, self.sql, self.bindings, cache.stale_deadline).await {
+            Ok(value) => {
+                cache.insert(key, Arc::new(value.clone()), now);
+                Ok(Cached {
+                    value,
+                    stale: false,
+                })
+            }
+            Err(e) => match stale {
+                Some(value) if matches!(e.kind(), ErrorKind::Connection | ErrorKind::Timeout) => {
+                    Ok(Cached { value, stale: true })
+                }
+                _ => Err(e),
+            },
+        }
+    }
+}
+
+/// Runs the query on a connection checked out within `deadline`, if set.
+async fn fetch<R>(
+    cache: &QueryCache,
+    sql: String,
+    bindings: Vec<Binding>,
+    deadline: Option<Duration>,
+) -> Result<R>
+where
+    usize: QueryResult<R>,
+{
+    let pool = &cache.pool;
+    let conn = match deadline {
+        Some(deadline) => {
+            let timeouts = Timeouts {
+                wait: Some(deadline),
+                create: Some(deadline),
+                ..pool.timeouts()
+            };
+            checkout::timeout_get(pool, &timeouts).await?
+        }
+        None => checkout::get(pool).await?,
+    };
+    let mut query = conn.query(sql);
+    for binding in bindings {
+        query = query.bind(binding);
+    }
+    Ok(query.await?.take(0)?)
+}
+
+impl<'a, R> IntoFuture for CachedQuery<'a, R>
+where
+    R: Clone + Send + Sync + 'static,
+    usize: QueryResult<R>,
+{
+    type Output = Result<Cached<R>>;
+    type IntoFuture = BoxFuture<'a, Result<Cached<R>>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.run())
+    }
+}
+
+impl<R> fmt::Debug for CachedQuery<'_, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachedQuery")
+            .field("sql", &self.sql)
+            .field("bindings", &self.bindings.len())
+            .finish()
+    }
+}
@@ -0,0 +1,256 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, PoisonError,
+    },
+    time::Duration,
+};
+
+use deadpool::managed::{self, Metrics, RecycleError, RecycleResult};
+
+use crate::{rt, Error, Runtime};
+
+/// Error returned by a [`ChaosManager`] for an injected failure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InjectedFault(&'static str);
+
+impl fmt::Display for InjectedFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Injected fault: {}", self.0)
+    }
+}
+
+impl std::error::Error for InjectedFault {}
+
+impl From<InjectedFault> for Error {
+    fn from(e: InjectedFault) -> Self {
+        Self::connection("Injected fault", e)
+    }
+}
+
+/// Number of faults injected by a [`ChaosManager`] so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChaosStats {
+    /// Failed attempts of creating a connection
+    pub create_failures: usize,
+    /// Failed attempts of recycling a connection
+    pub recycle_failures: usize,
+    /// Healthy connections discarded instead of being recycled
+    pub drops: usize,
+    /// Delayed attempts of creating or recycling a connection
+    pub delays: usize,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    create_failures: AtomicUsize,
+    recycle_failures: AtomicUsize,
+    drops: AtomicUsize,
+    delays: AtomicUsize,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Delay {
+    rate: f64,
+    duration: Duration,
+}
+
+/// Decorator for a [`managed::Manager`] which injects faults for testing
+/// retry and backoff behavior.
+///
+/// Every fault happens with a configurable probability. The random numbers
+/// are drawn from a generator seeded using [`ChaosManager::seed()`], so runs
+/// with the same seed and the same sequence of calls inject the same faults.
+///
+/// ```rust,ignore
+/// let manager = ChaosManager::new(Manager::from_config(&config))
+///     .seed(42)
+///     .runtime(Runtime::Tokio1)
+///     .create_failure_rate(0.2)
+///     .create_delay(0.5, Duration::from_millis(100))
+///     .drop_rate(0.1);
+/// let pool = deadpool::managed::Pool::builder(manager).max_size(4).build()?;
+/// ```
+pub struct ChaosManager<M> {
+    inner: M,
+    runtime: Option<Runtime>,
+    rng: Mutex<u64>,
+    create_failure_rate: f64,
+    recycle_failure_rate: f64,
+    drop_rate: f64,
+    create_delay: Delay,
+    recycle_delay: Delay,
+    counters: Counters,
+}
+
+impl<M> ChaosManager<M> {
+    /// Wraps the given `manager` without injecting any faults yet.
+    pub fn new(manager: M) -> Self {
+        Self {
+            inner: manager,
+            runtime: None,
+            rng: Mutex::new(0),
+            create_failure_rate: 0.0,
+            recycle_failure_rate: 0.0,
+            drop_rate: 0.0,
+            create_delay: Delay::default(),
+            recycle_delay: Delay::default(),
+            counters: Counters::default(),
+        }
+    }
+
+    /// Seeds the random number generator deciding which faults to inject.
+    #[must_use]
+    pub fn seed(self, seed: u64) -> Self {
+        *self.rng.lock().unwrap_or_else(PoisonError::into_inner) = seed;
+        self
+    }
+
+    /// Sets the [`Runtime`] used for delays.
+    ///
+    /// Without a runtime no delays are injected.
+    #[must_use]
+    pub fn runtime(mut self, runtime: Runtime) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Sets the probability of an attempt of creating a connection failing.
+    #[must_use]
+    pub fn create_failure_rate(mut self, rate: f64) -> Self {
+        self.create_failure_rate = rate;
+        self
+    }
+
+    /// Sets the probability of an attempt of recycling a connection failing.
+    #[must_use]
+    pub fn recycle_failure_rate(mut self, rate: f64) -> Self {
+        self.recycle_failure_rate = rate;
+        self
+    }
+
+    /// Sets the probability of a healthy connection being discarded instead
+    /// of being recycled, as if the server had closed it.
+    #[must_use]
+    pub fn drop_rate(mut self, rate: f64) -> Self {
+        self.drop_rate = rate;
+        self
+    }
+
+    /// Delays attempts of creating a connection by `duration` with the given
+    /// probability.
+    #[must_use]
+    pub fn create_delay(mut self, rate: f64, duration: Duration) -> Self {
+        self.create_delay = Delay { rate, duration };
+        self
+    }
+
+    /// Delays attempts of recycling a connection by `duration` with the
+    /// given probability.
+    #[must_use]
+    pub fn recycle_delay(mut self, rate: f64, duration: Duration) -> Self {
+        self.recycle_delay = Delay { rate, duration };
+        self
+    }
+
+    /// Returns the wrapped manager.
+    pub fn inner(&self) -> &M {
+        &self.inner
+    }
+
+    /// Returns the number of faults injected so far.
+    #[must_use]
+    pub fn stats(&self) -> ChaosStats {
+        ChaosStats {
+            create_failures: self.counters.create_failures.load(Ordering::Relaxed),
+            recycle_failures: self.counters.recycle_failures.load(Ordering::Relaxed),
+            drops: self.counters.drops.load(Ordering::Relaxed),
+            delays: self.counters.delays.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns `true` with the given probability.
+    fn chance(&self, rate: f64) -> bool {
+        if rate <= 0.0 {
+            return false;
+        }
+        // SplitMix64
+        let mut state = self.rng.lock().unwrap_or_else(PoisonError::into_inner);
+        *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        let sample = (z >> 11) as f64 / (1u64 << 53) as f64;
+        sample < rate
+    }
+
+    async fn delay(&self, delay: Delay) {
+        let Some(runtime) = self.runtime else {
+            return;
+        };
+        if self.chance(delay.rate) {
+            let _ = self.counters.delays.fetch_add(1, Ordering::Relaxed);
+            rt::sleep(runtime, delay.duration).await;
+        }
+    }
+}
+
+impl<M> managed::Manager for ChaosManager<M>
+where
+    M: managed::Manager,
+    M::Error: From<InjectedFault>,
+{
+    type Type = M::Type;
+    type Error = M::Error;
+
+    async fn create(&self) -> Result<M::Type, M::Error> {
+        self.delay(self.create_delay).await;
+        if self.chance(self.create_failure_rate) {
+            let _ = self
+                .counters
+                .create_failures
+                .fetch_add(1, Ordering::Relaxed);
+            return Err(InjectedFault("connection refused").into());
+        }
+        self.inner.create().await
+    }
+
+    async fn recycle(&self, obj: &mut M::Type, metrics: &Metrics) -> RecycleResult<M::Error> {
+        self.delay(self.recycle_delay).await;
+        if self.chance(self.recycle_failure_rate) {
+            let _ = self
+                .counters
+                .recycle_failures
+                .fetch_add(1, Ordering::Relaxed);
+            return Err(RecycleError::Backend(
+                InjectedFault("recycle failed").into(),
+            ));
+        }
+        if self.chance(self.drop_rate) {
+            let _ = self.counters.drops.fetch_add(1, Ordering::Relaxed);
+            return Err(RecycleError::message("Connection dropped by chaos manager"));
+        }
+        self.inner.recycle(obj, metrics).await
+    }
+
+    fn detach(&self, obj: &mut M::Type) {
+        self.inner.detach(obj);
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for ChaosManager<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChaosManager")
+            .field("inner", &self.inner)
+            .field("runtime", &self.runtime)
+            .field("create_failure_rate", &self.create_failure_rate)
+            .field("recycle_failure_rate", &self.recycle_failure_rate)
+            .field("drop_rate", &self.drop_rate)
+            .field("create_delay", &self.create_delay)
+            .field("recycle_delay", &self.recycle_delay)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
@@ -0,0 +1,216 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    future::Future,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use deadpool::managed::{Status, TimeoutType, Timeouts};
+
+use crate::{hook, ErrorKind, Object, Pool, PoolError, Result, TimeoutPhase};
+
+/// Failed checkout passed to the hook set using
+/// [`Manager::on_checkout_failure()`].
+///
+/// [`Manager::on_checkout_failure()`]: crate::Manager::on_checkout_failure
+#[derive(Clone, Copy, Debug)]
+pub struct CheckoutFailure {
+    /// Category of the error returned to the caller
+    pub kind: ErrorKind,
+    /// Time spent waiting for a connection
+    pub waited: Duration,
+    /// Status of the pool right after the checkout failed
+    pub status: Status,
+}
+
+/// Connections of a [`Pool`] by state, returned by [`PoolExt::gauges()`].
+///
+/// Unlike [`Status`], where `size` includes both idle and checked out
+/// connections, every connection is counted exactly once.
+///
+/// [`PoolExt::gauges()`]: crate::PoolExt::gauges
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Gauges {
+    /// Maximum number of connections
+    pub max_size: usize,
+    /// Number of connections idle in the pool, ready to be checked out
+    pub idle: usize,
+    /// Number of connections currently checked out
+    pub in_use: usize,
+    /// Number of tasks waiting for a connection
+    pub waiting: usize,
+}
+
+impl From<Status> for Gauges {
+    fn from(status: Status) -> Self {
+        Self {
+            max_size: status.max_size,
+            idle: status.available,
+            in_use: status.size.saturating_sub(status.available),
+            waiting: status.waiting,
+        }
+    }
+}
+
+/// Number of checkouts whose wait time is kept for [`Pressure::wait_p95`].
+pub const WAIT_SAMPLES: usize = 256;
+
+/// Load of a [`Pool`] returned by [`PoolExt::pressure()`], for shedding load
+/// before checkouts time out.
+///
+/// [`PoolExt::pressure()`]: crate::PoolExt::pressure
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pressure {
+    /// Number of tasks waiting for a connection divided by the maximum size
+    /// of the pool
+    ///
+    /// This is 0 if nobody waits and exceeds 1 once more tasks wait than the
+    /// pool can hold connections.
+    pub saturation: f64,
+    /// Share of the maximum size of the pool which is checked out, between 0
+    /// and 1
+    pub utilization: f64,
+    /// 95th percentile of the time the last [`WAIT_SAMPLES`] checkouts
+    /// waited for a connection
+    ///
+    /// Only checkouts made using the helpers of this crate, e.g.
+    /// [`PoolExt::checkout()`], are taken into account.
+    ///
+    /// [`PoolExt::checkout()`]: crate::PoolExt::checkout
+    pub wait_p95: Duration,
+}
+
+/// Wait times of the most recent checkouts of a [`Manager`].
+///
+/// [`Manager`]: crate::Manager
+#[derive(Debug, Default)]
+pub(crate) struct WaitLog(Mutex<VecDeque<Duration>>);
+
+impl WaitLog {
+    fn record(&self, waited: Duration) {
+        let mut waits = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if waits.len() == WAIT_SAMPLES {
+            let _ = waits.pop_front();
+        }
+        waits.push_back(waited);
+    }
+
+    pub(crate) fn p95(&self) -> Duration {
+        let mut waits: Vec<Duration> = {
+            let waits = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+            waits.iter().copied().collect()
+        };
+        if waits.is_empty() {
+            return Duration::ZERO;
+        }
+        waits.sort_unstable();
+        waits[(waits.len() * 95).div_ceil(100) - 1]
+    }
+}
+
+/// Returns the [`Pressure`] of the `pool`.
+pub(crate) fn pressure(pool: &Pool) -> Pressure {
+    let gauges = Gauges::from(pool.status());
+    let max_size = gauges.max_size.max(1) as f64;
+    Pressure {
+        saturation: gauges.waiting as f64 / max_size,
+        utilization: gauges.in_use as f64 / max_size,
+        wait_p95: pool.manager().waits.p95(),
+    }
+}
+
+type HookFn = dyn Fn(&CheckoutFailure) + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct CheckoutHook(pub(crate) Arc<HookFn>);
+
+impl fmt::Debug for CheckoutHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CheckoutHook").finish_non_exhaustive()
+    }
+}
+
+/// Checks out a connection using the default timeouts of the `pool`.
+pub(crate) async fn get(pool: &Pool) -> Result<Object> {
+    timeout_get(pool, &pool.timeouts()).await
+}
+
+/// Checks out a connection, passing failures to the hook of the manager.
+pub(crate) async fn timeout_get(pool: &Pool, timeouts: &Timeouts) -> Result<Object> {
+    let started = pool.manager().clock.now();
+    let result = pool.timeout_get(timeouts).await;
+    finish(pool, started, result)
+}
+
+/// Waits for `gate` before checking out a connection using the default
+/// timeouts of the `pool`.
+///
+/// The wait timeout of the pool bounds waiting for `gate` and the checkout
+/// together if a [`Runtime`] is configured. Expiry is reported like any
+/// other checkout failure as [`Error::Timeout`] in the
+/// [`TimeoutPhase::Wait`].
+///
+/// [`Runtime`]: crate::Runtime
+/// [`Error::Timeout`]: crate::Error::Timeout
+pub(crate) async fn gated_get<T>(
+    pool: &Pool,
+    gate: impl Future<Output = T>,
+) -> Result<(T, Object)> {
+    let manager = pool.manager();
+    let started = manager.clock.now();
+    let timeouts = pool.timeouts();
+    let checkout = async {
+        let passed = gate.await;
+        pool.timeout_get(&timeouts).await.map(|conn| (passed, conn))
+    };
+    let result = match (timeouts.wait, manager.runtime) {
+        (Some(wait), Some(runtime)) => runtime
+            .timeout(wait, checkout)
+            .await
+            .unwrap_or(Err(PoolError::Timeout(TimeoutType::Wait))),
+        _ => checkout.await,
+    };
+    finish(pool, started, result)
+}
+
+/// Records the wait time of a checkout started at `started` and passes its
+/// failure to the hook of the manager.
+fn finish<T>(
+    pool: &Pool,
+    started: Instant,
+    result: std::result::Result<T, PoolError>,
+) -> Result<T> {
+    let manager = pool.manager();
+    manager.waits.record(manager.clock.elapsed(started));
+    match result {
+        Ok(value) => Ok(value),
+        Err(e) => {
+            let e = crate::Error::from(e);
+            // Errors of the manager have been recorded when they occurred.
+            if matches!(e.kind(), ErrorKind::Timeout | ErrorKind::PoolClosed) {
+                manager.errors.record(&e);
+            }
+            match e {
+                crate::Error::Timeout {
+                    phase: TimeoutPhase::Create,
+                } => manager.failures.create(&e),
+                crate::Error::Timeout {
+                    phase: TimeoutPhase::Recycle,
+                } => manager.failures.recycle(&e),
+                _ => {}
+            }
+            if let Some(hook) = &manager.checkout_hook {
+                let failure = CheckoutFailure {
+                    kind: e.kind(),
+                    waited: manager.clock.elapsed(started),
+                    status: pool.status(),
+                };
+                if let Err(e) = hook::catch("on_checkout_failure", || (hook.0)(&failure)) {
+                    manager.errors.record(&e);
+                }
+            }
+            Err(e)
+        }
+    }
+}
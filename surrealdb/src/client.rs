@@ -0,0 +1,981 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    future::IntoFuture,
+    io,
+    ops::Deref,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, OnceLock, PoisonError,
+    },
+    time::Duration,
+};
+
+use deadpool_runtime::Runtime;
+use futures::future::BoxFuture;
+use serde::{de::DeserializeOwned, Serialize, Serializer};
+use surrealdb::{
+    engine::any::Any,
+    method::{Create, Delete, Insert, Update, Upsert},
+    opt::{CreateResource, IntoResource},
+    RecordId, RecordIdKey, Response, Surreal,
+};
+use tokio::sync::OwnedSemaphorePermit;
+
+use crate::{
+    checkout,
+    retry::RetryBudget,
+    surql,
+    trace::{QueryComments, TraceContext},
+    Config, Error, Pool, Result,
+};
+
+/// Statements rejected by read-only connections.
+const MUTATING_STATEMENTS: &[&str] = &[
+    "ALTER", "CREATE", "DEFINE", "DELETE", "INSERT", "REBUILD", "RELATE", "REMOVE", "UPDATE",
+    "UPSERT",
+];
+
+/// Wrapper around [`Surreal<Any>`] which is handed out by the [`Pool`].
+///
+/// It dereferences to [`Surreal<Any>`] so it can be used just like a regular
+/// client. Queries should be run using [`ClientWrapper::query()`] which
+/// shadows [`Surreal::query()`] and adds pool specific behavior like
+/// timeouts on top of it.
+///
+/// [`Pool`]: crate::Pool
+#[derive(Debug)]
+pub struct ClientWrapper {
+    id: u64,
+    generation: u64,
+    endpoint: String,
+    /// Share of the [`ConnectionBudget`], released when dropped
+    ///
+    /// [`ConnectionBudget`]: crate::budget::ConnectionBudget
+    _permit: Option<OwnedSemaphorePermit>,
+    client: Surreal<Any>,
+    runtime: Option<Runtime>,
+    suspect: AtomicBool,
+    read_only: bool,
+    default_timeout: Option<Duration>,
+    comments: Option<QueryComments>,
+    reauth: Option<Arc<Config>>,
+    params: Mutex<Vec<String>>,
+    restores: Mutex<VecDeque<Restore>>,
+    version: OnceLock<String>,
+    counters: Counters,
+    #[cfg(feature = "test-utils")]
+    mock: Option<crate::test_utils::MockManager>,
+}
+
+impl ClientWrapper {
+    pub(crate) fn new(client: Surreal<Any>, runtime: Option<Runtime>) -> Self {
+        Self {
+            id: 0,
+            generation: 0,
+            endpoint: String::new(),
+            _permit: None,
+            client,
+            runtime,
+            suspect: AtomicBool::new(false),
+            read_only: false,
+            default_timeout: None,
+            comments: None,
+            reauth: None,
+            params: Mutex::new(Vec::new()),
+            restores: Mutex::new(VecDeque::new()),
+            version: OnceLock::new(),
+            counters: Counters::default(),
+            #[cfg(feature = "test-utils")]
+            mock: None,
+        }
+    }
+
+    pub(crate) fn with_id(mut self, id: u64) -> Self {
+        self.id = id;
+        self
+    }
+
+    pub(crate) fn with_endpoint(mut self, endpoint: String) -> Self {
+        self.endpoint = endpoint;
+        self
+    }
+
+    pub(crate) fn with_permit(mut self, permit: Option<OwnedSemaphorePermit>) -> Self {
+        self._permit = permit;
+        self
+    }
+
+    pub(crate) fn with_generation(mut self, generation: u64) -> Self {
+        self.generation = generation;
+        self
+    }
+
+    /// Returns the generation of the endpoint this connection was created
+    /// for, which changes with every endpoint switch.
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub(crate) fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub(crate) fn default_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.default_timeout = timeout;
+        self
+    }
+
+    pub(crate) fn count_bytes(mut self, enabled: bool) -> Self {
+        self.counters.count_bytes = enabled;
+        self
+    }
+
+    pub(crate) fn comments(mut self, comments: Option<QueryComments>) -> Self {
+        self.comments = comments;
+        self
+    }
+
+    /// Authenticates again using the credentials of `config` if a query
+    /// fails because the session expired.
+    pub(crate) fn reauth(mut self, config: Option<Arc<Config>>) -> Self {
+        self.reauth = config;
+        self
+    }
+
+    #[cfg(feature = "test-utils")]
+    pub(crate) fn mock(mut self, mock: Option<crate::test_utils::MockManager>) -> Self {
+        self.mock = mock;
+        self
+    }
+
+    /// Sets the session parameter `key` to `value`.
+    ///
+    /// Unlike parameters set using [`Surreal::set()`] it is removed again
+    /// before the connection is handed out by the [`Pool`] the next time.
+    ///
+    /// [`Pool`]: crate::Pool
+    pub async fn set_param(
+        &self,
+        key: impl Into<String>,
+        value: impl Serialize + Send + 'static,
+    ) -> Result<()> {
+        let key = key.into();
+        self.restore_params().await?;
+        self.client.set(key.clone(), value).await?;
+        let mut params = self.params.lock().unwrap_or_else(PoisonError::into_inner);
+        if !params.contains(&key) {
+            params.push(key);
+        }
+        Ok(())
+    }
+
+    /// Sets the session parameter `key` to `value` until the returned
+    /// [`ScopedParam`] is dropped.
+    ///
+    /// Dropping the guard restores the previous value of the parameter, or
+    /// removes it if it wasn't set before. This happens before the next
+    /// query run using [`ClientWrapper::query()`], or once the connection is
+    /// returned to the [`Pool`], so temporary session tweaks never leak into
+    /// other checkouts, even on early returns or panics. Use
+    /// [`ScopedParam::restore()`] to restore the parameter right away.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidQuery`] if `key` isn't a plain parameter
+    /// name consisting of ASCII letters, digits and underscores.
+    ///
+    /// ```rust,ignore
+    /// let _guard = conn.scoped_set("locale", "de").await?;
+    /// let products: Vec<Product> = conn.query("SELECT * FROM product").await?.take(0)?;
+    /// ```
+    ///
+    /// [`Pool`]: crate::Pool
+    pub async fn scoped_set(
+        &self,
+        key: impl Into<String>,
+        value: impl Serialize + Send + 'static,
+    ) -> Result<ScopedParam<'_>> {
+        let key = key.into();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(Error::InvalidQuery(format!(
+                "invalid parameter name: {}",
+                key
+            )));
+        }
+        self.restore_params().await?;
+        // Read the value as a `surrealdb::Value` so record ids, datetimes
+        // and the like are restored with their original type.
+        let mut response = self
+            .client
+            .query(format!("RETURN ${key}; RETURN ${key} != NONE", key = key))
+            .await?;
+        let current: surrealdb::Value = response.take(0)?;
+        let set: Option<bool> = response.take(1)?;
+        let previous = set.unwrap_or(false).then_some(current);
+        self.client.set(key.clone(), value).await?;
+        let mut params = self.params.lock().unwrap_or_else(PoisonError::into_inner);
+        let tracked = params.contains(&key);
+        if !tracked {
+            params.push(key.clone());
+        }
+        Ok(ScopedParam {
+            client: self,
+            restore: Some(Restore {
+                key,
+                previous,
+                tracked,
+            }),
+        })
+    }
+
+    /// Restores parameters of dropped [`ScopedParam`] guards.
+    pub(crate) async fn restore_params(&self) -> Result<()> {
+        loop {
+            let Some(restore) = self.restores().pop_front() else {
+                return Ok(());
+            };
+            let result = match &restore.previous {
+                Some(value) => self.client.set(restore.key.clone(), value.clone()).await,
+                None => self.client.unset(restore.key.clone()).await,
+            };
+            if let Err(e) = result {
+                self.restores().push_front(restore);
+                return Err(e.into());
+            }
+            if !restore.tracked {
+                self.params
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .retain(|key| *key != restore.key);
+            }
+        }
+    }
+
+    fn restores(&self) -> MutexGuard<'_, VecDeque<Restore>> {
+        self.restores.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Removes all parameters set using [`ClientWrapper::set_param()`].
+    pub(crate) async fn clear_params(&self) -> Result<()> {
+        let params =
+            std::mem::take(&mut *self.params.lock().unwrap_or_else(PoisonError::into_inner));
+        for key in params {
+            self.client.unset(key).await?;
+        }
+        Ok(())
+    }
+
+    /// Indicates whether this connection rejects mutating statements.
+    ///
+    /// See [`Config::read_only`] for details.
+    ///
+    /// [`Config::read_only`]: crate::Config::read_only
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Returns the id of this connection.
+    ///
+    /// Ids are assigned in the order connections are created and are unique
+    /// within a pool.
+    #[must_use]
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the endpoint this connection was made to, e.g. one of the
+    /// targets of an SRV record.
+    #[must_use]
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Returns the underlying [`Surreal<Any>`] client.
+    ///
+    /// Statements run directly on the client bypass read-only mode.
+    #[must_use]
+    pub fn client(&self) -> &Surreal<Any> {
+        &self.client
+    }
+
+    /// Fails with [`Error::ReadOnly`] if this connection is read-only.
+    fn check_writable(&self, keyword: &str) -> Result<()> {
+        if self.read_only {
+            return Err(Error::ReadOnly(keyword.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Creates a record, see [`Surreal::create()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ReadOnly`] if this connection is read-only.
+    pub fn create<R>(&self, resource: impl CreateResource<R>) -> Result<Create<'_, Any, R>> {
+        self.check_writable("CREATE")?;
+        Ok(self.client.create(resource))
+    }
+
+    /// Inserts records, see [`Surreal::insert()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ReadOnly`] if this connection is read-only.
+    pub fn insert<O>(&self, resource: impl IntoResource<O>) -> Result<Insert<'_, Any, O>> {
+        self.check_writable("INSERT")?;
+        Ok(self.client.insert(resource))
+    }
+
+    /// Updates or creates records, see [`Surreal::upsert()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ReadOnly`] if this connection is read-only.
+    pub fn upsert<O>(&self, resource: impl IntoResource<O>) -> Result<Upsert<'_, Any, O>> {
+        self.check_writable("UPSERT")?;
+        Ok(self.client.upsert(resource))
+    }
+
+    /// Updates records, see [`Surreal::update()`]. This includes merging
+    /// into and patching records.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ReadOnly`] if this connection is read-only.
+    pub fn update<O>(&self, resource: impl IntoResource<O>) -> Result<Update<'_, Any, O>> {
+        self.check_writable("UPDATE")?;
+        Ok(self.client.update(resource))
+    }
+
+    /// Deletes records, see [`Surreal::delete()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ReadOnly`] if this connection is read-only.
+    pub fn delete<O>(&self, resource: impl IntoResource<O>) -> Result<Delete<'_, Any, O>> {
+        self.check_writable("DELETE")?;
+        Ok(self.client.delete(resource))
+    }
+
+    /// Prepares the given `query` for execution.
+    ///
+    /// The query is limited to [`Config::default_query_timeout`] unless
+    /// [`Query::timeout()`] is called.
+    ///
+    /// [`Config::default_query_timeout`]: crate::Config::default_query_timeout
+    pub fn query(&self, query: impl Into<String>) -> Query<'_> {
+        Query {
+            client: self,
+            sql: query.into(),
+            bindings: Vec::new(),
+            timeout: self.default_timeout,
+            trace: None,
+            tags: Vec::new(),
+        }
+    }
+
+    /// Prepares the given `query` for execution with a `timeout`.
+    ///
+    /// See [`Query::timeout()`] for details.
+    pub fn query_with_timeout(&self, query: impl Into<String>, timeout: Duration) -> Query<'_> {
+        self.query(query).timeout(timeout)
+    }
+
+    /// Merges `patch` into the record `id` of `table` and increments its
+    /// `version` field, but only if the record's version equals
+    /// `expected_version`.
+    ///
+    /// The check and the update happen in a single statement, so concurrent
+    /// updates can't overwrite each other. `patch` must serialize to a map.
+    /// `id` can be any record id key, e.g. a string, number or array.
+    ///
+    /// Returns the updated record.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Conflict`] if the record doesn't exist or its version
+    /// differs.
+    pub async fn update_if_version<T, P>(
+        &self,
+        table: &str,
+        id: impl Into<RecordIdKey>,
+        expected_version: u64,
+        patch: P,
+    ) -> Result<T>
+    where
+        T: DeserializeOwned,
+        P: Serialize + Send + Sync + 'static,
+    {
+        let record = RecordId::from_table_key(table, id);
+        let mut response = self
+            .query(
+                "UPDATE $record MERGE $patch WHERE version = $version RETURN AFTER; \
+                 RETURN <string> record::id($record)",
+            )
+            .bind(("record", record))
+            .bind(("version", expected_version))
+            .bind((
+                "patch",
+                Versioned {
+                    patch,
+                    version: expected_version + 1,
+                },
+            ))
+            .await?;
+        let updated: Option<T> = response.take(0)?;
+        match updated {
+            Some(updated) => Ok(updated),
+            None => {
+                let id: Option<String> = response.take(1)?;
+                Err(Error::Conflict {
+                    table: table.to_string(),
+                    id: id.unwrap_or_default(),
+                    expected_version,
+                })
+            }
+        }
+    }
+
+    /// Checks whether the server is reachable using its cheapest health
+    /// check.
+    pub async fn ping(&self) -> Result<()> {
+        self.client.health().await?;
+        Ok(())
+    }
+
+    /// Returns the version of the server.
+    ///
+    /// The server is only asked once per connection, subsequent calls return
+    /// the cached version.
+    pub async fn server_version(&self) -> Result<String> {
+        if let Some(version) = self.version.get() {
+            return Ok(version.clone());
+        }
+        let version = self.client.version().await?.to_string();
+        Ok(self.version.get_or_init(|| version).clone())
+    }
+
+    /// Marks this connection as suspect so it is discarded instead of being
+    /// recycled once it is returned to the [`Pool`].
+    ///
+    /// [`Pool`]: crate::Pool
+    pub fn mark_suspect(&self) {
+        self.suspect.store(true, Ordering::Relaxed);
+    }
+
+    /// Indicates whether this connection has been marked as suspect.
+    #[must_use]
+    pub fn is_suspect(&self) -> bool {
+        self.suspect.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of queries run on this connection and the bytes
+    /// they transferred.
+    #[must_use]
+    pub fn metrics(&self) -> ConnectionMetrics {
+        self.counters.snapshot()
+    }
+}
+
+/// Queries run on a connection and the bytes they transferred, returned by
+/// [`ClientWrapper::metrics()`].
+///
+/// Only queries run using [`ClientWrapper::query()`] are counted. Bytes are
+/// only counted if [`Config::count_bytes`] or
+/// [`Config::max_transferred_bytes`] is set, and stay 0 otherwise.
+///
+/// The byte counts are rough estimates, not the size of the messages on the
+/// wire, which the client doesn't expose: they are taken from the text of
+/// the queries, their bindings serialized as JSON and the debug
+/// representation of their responses.
+///
+/// [`Config::count_bytes`]: crate::Config::count_bytes
+/// [`Config::max_transferred_bytes`]: crate::Config::max_transferred_bytes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConnectionMetrics {
+    /// Number of queries sent
+    pub queries: u64,
+    /// Number of statements of all responses received
+    pub statements: u64,
+    /// Approximate number of bytes sent
+    pub request_bytes: u64,
+    /// Approximate number of bytes received
+    pub response_bytes: u64,
+}
+
+impl ConnectionMetrics {
+    /// Returns the approximate number of bytes sent and received.
+    #[must_use]
+    pub fn transferred_bytes(&self) -> u64 {
+        self.request_bytes.saturating_add(self.response_bytes)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    /// Whether bytes are counted as well
+    count_bytes: bool,
+    queries: AtomicU64,
+    statements: AtomicU64,
+    request_bytes: AtomicU64,
+    response_bytes: AtomicU64,
+}
+
+impl Counters {
+    fn request(&self, sql: &str, bindings: &[Binding]) {
+        let _ = self.queries.fetch_add(1, Ordering::Relaxed);
+        if !self.count_bytes {
+            return;
+        }
+        // Both are written straight into the counter without allocating.
+        let mut bytes = ByteCount(sql.len() as u64);
+        for binding in bindings {
+            let _ = serde_json::to_writer(&mut bytes, binding);
+        }
+        let _ = self.request_bytes.fetch_add(bytes.0, Ordering::Relaxed);
+    }
+
+    fn response(&self, response: &Response) {
+        let statements = response.num_statements() as u64;
+        let _ = self.statements.fetch_add(statements, Ordering::Relaxed);
+        if !self.count_bytes {
+            return;
+        }
+        let mut bytes = ByteCount(0);
+        let _ = fmt::Write::write_fmt(&mut bytes, format_args!("{:?}", response));
+        let _ = self.response_bytes.fetch_add(bytes.0, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> ConnectionMetrics {
+        ConnectionMetrics {
+            queries: self.queries.load(Ordering::Relaxed),
+            statements: self.statements.load(Ordering::Relaxed),
+            request_bytes: self.request_bytes.load(Ordering::Relaxed),
+            response_bytes: self.response_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Writer counting the bytes written to it.
+struct ByteCount(u64);
+
+impl io::Write for ByteCount {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl fmt::Write for ByteCount {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len() as u64;
+        Ok(())
+    }
+}
+
+impl Deref for ClientWrapper {
+    type Target = Surreal<Any>;
+
+    fn deref(&self) -> &Surreal<Any> {
+        &self.client
+    }
+}
+
+/// Query which is run on a [`ClientWrapper`] once it is awaited.
+#[must_use = "queries do nothing unless awaited"]
+pub struct Query<'a> {
+    client: &'a ClientWrapper,
+    sql: String,
+    bindings: Vec<Binding>,
+    timeout: Option<Duration>,
+    trace: Option<TraceContext>,
+    tags: Vec<(String, String)>,
+}
+
+impl Query<'_> {
+    /// Binds parameters to the query.
+    ///
+    /// This accepts the same `(key, value)` tuples and maps as
+    /// [`surrealdb::method::Query::bind()`].
+    pub fn bind(mut self, bindings: impl Serialize + Send + Sync + 'static) -> Self {
+        self.bindings.push(Binding(Arc::new(bindings)));
+        self
+    }
+
+    /// Sets a deadline for this query.
+    ///
+    /// SurrealDB doesn't offer a way to cancel a running query, so once the
+    /// deadline is exceeded or the query future is dropped before completion
+    /// the connection is marked as suspect. The [`Pool`] discards suspect
+    /// connections instead of recycling them, which closes the session and
+    /// with it any query still running on it.
+    ///
+    /// Enforcing a timeout requires a [`Runtime`] to be configured for the
+    /// [`Manager`].
+    ///
+    /// [`Manager`]: crate::Manager
+    /// [`Pool`]: crate::Pool
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the [`TraceContext`] added to the query text.
+    ///
+    /// This takes precedence over the context provided by the
+    /// [`QueryComments`] of the [`Manager`] and has no effect if none are
+    /// configured.
+    ///
+    /// [`Manager`]: crate::Manager
+    pub fn trace(mut self, context: TraceContext) -> Self {
+        self.trace = Some(context);
+        self
+    }
+
+    /// Adds a tag like `handler` or `tenant` to the query text.
+    ///
+    /// This takes precedence over a tag with the same key of the
+    /// [`QueryComments`] of the [`Manager`] and has no effect if none are
+    /// configured.
+    ///
+    /// [`Manager`]: crate::Manager
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.push((key.into(), value.into()));
+        self
+    }
+
+    async fn run(self) -> Result<Response> {
+        let client = self.client;
+        if client.read_only {
+            reject_mutations(&self.sql)?;
+        }
+        client.restore_params().await?;
+        #[cfg(feature = "test-utils")]
+        if let Some(mock) = &client.mock {
+            if let Some(response) = mock.query(&client.client, &self.sql).await {
+                return response;
+            }
+        }
+        let sql = match &client.comments {
+            Some(comments) => comments.apply(self.sql, self.trace.as_ref(), &self.tags),
+            None => self.sql,
+        };
+        let Some(config) = &client.reauth else {
+            return execute(client, sql, self.bindings, self.timeout).await;
+        };
+        match execute(client, sql.clone(), self.bindings.clone(), self.timeout).await {
+            Err(e) if e.is_session_expired() => {
+                crate::authenticate(config, &client.client).await?;
+                execute(client, sql, self.bindings, self.timeout).await
+            }
+            result => result,
+        }
+    }
+}
+
+async fn execute(
+    client: &ClientWrapper,
+    sql: String,
+    bindings: Vec<Binding>,
+    timeout: Option<Duration>,
+) -> Result<Response> {
+    client.counters.request(&sql, &bindings);
+    let mut query = client.client.query(sql);
+    for binding in bindings {
+        query = query.bind(binding);
+    }
+    let response = match timeout {
+        None => query.await?,
+        Some(timeout) => {
+            let runtime = client.runtime.ok_or(Error::NoRuntimeSpecified)?;
+            let guard = SuspectGuard(Some(client));
+            match runtime.timeout(timeout, query.into_future()).await {
+                Some(result) => {
+                    guard.disarm();
+                    result?
+                }
+                None => return Err(Error::QueryTimeout(timeout)),
+            }
+        }
+    };
+    client.counters.response(&response);
+    Ok(response)
+}
+
+/// Query which is run on a connection checked out from the [`Pool`] once it
+/// is awaited.
+///
+/// If [`Config::retry_on_disconnect`] is set, queries without mutating
+/// statements are retried once on a fresh connection when the first one
+/// turns out to be broken. The broken connection is discarded. Use
+/// [`PoolQuery::retry()`] for retrying queries within a [`RetryBudget`].
+///
+/// [`Config::retry_on_disconnect`]: crate::Config::retry_on_disconnect
+/// [`Pool`]: crate::Pool
+#[must_use = "queries do nothing unless awaited"]
+pub struct PoolQuery<'a> {
+    pool: &'a Pool,
+    sql: String,
+    bindings: Vec<Binding>,
+    timeout: Option<Duration>,
+    retry: Option<RetryBudget>,
+}
+
+impl<'a> PoolQuery<'a> {
+    pub(crate) fn new(pool: &'a Pool, sql: String) -> Self {
+        Self {
+            pool,
+            sql,
+            bindings: Vec::new(),
+            timeout: None,
+            retry: None,
+        }
+    }
+
+    /// Binds parameters to the query.
+    ///
+    /// See [`Query::bind()`] for details.
+    pub fn bind(mut self, bindings: impl Serialize + Send + Sync + 'static) -> Self {
+        self.bindings.push(Binding(Arc::new(bindings)));
+        self
+    }
+
+    pub(crate) fn bindings(mut self, bindings: Vec<Binding>) -> Self {
+        self.bindings.extend(bindings);
+        self
+    }
+
+    /// Sets a deadline for every attempt of this query, overriding
+    /// [`Config::default_query_timeout`].
+    ///
+    /// See [`Query::timeout()`] for details.
+    ///
+    /// [`Config::default_query_timeout`]: crate::Config::default_query_timeout
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Retries the query within the given `budget` if it fails with an
+    /// error for which [`Error::is_retryable()`] returns `true`.
+    ///
+    /// Every attempt checks out a connection of its own, which counts
+    /// against the timeouts of the budget. Queries with mutating statements
+    /// are never retried, but the timeouts of the budget still apply. This
+    /// replaces the retry of [`Config::retry_on_disconnect`].
+    ///
+    /// [`Config::retry_on_disconnect`]: crate::Config::retry_on_disconnect
+    pub fn retry(mut self, budget: RetryBudget) -> Self {
+        self.retry = Some(budget);
+        self
+    }
+
+    async fn run(self) -> Result<Response> {
+        let manager = self.pool.manager();
+        let mutating = mutating_statement(&self.sql).is_some();
+        let (budget, retryable): (_, fn(&Error) -> bool) = match self.retry {
+            Some(budget) if mutating => (budget.once(), Error::is_retryable),
+            Some(budget) => (budget, Error::is_retryable),
+            None if manager.config.retry_on_disconnect && !mutating => {
+                (RetryBudget::new(2), Error::is_connection_lost)
+            }
+            None => (RetryBudget::new(1), Error::is_connection_lost),
+        };
+        let (pool, sql, bindings, timeout) = (self.pool, &self.sql, &self.bindings, self.timeout);
+        let attempt = move || async move {
+            let conn = checkout::get(pool).await?;
+            let query = Query {
+                client: &conn,
+                sql: sql.clone(),
+                bindings: bindings.clone(),
+                timeout: timeout.or(conn.default_timeout),
+                trace: None,
+                tags: Vec::new(),
+            };
+            let result = query.run().await;
+            if matches!(&result, Err(e) if e.is_connection_lost()) {
+                conn.mark_suspect();
+            }
+            result
+        };
+        budget
+            .run(manager.runtime, retryable, Error::QueryTimeout, attempt)
+            .await
+    }
+}
+
+impl<'a> IntoFuture for PoolQuery<'a> {
+    type Output = Result<Response>;
+    type IntoFuture = BoxFuture<'a, Result<Response>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.run())
+    }
+}
+
+impl fmt::Debug for PoolQuery<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoolQuery")
+            .field("sql", &self.sql)
+            .field("bindings", &self.bindings.len())
+            .field("timeout", &self.timeout)
+            .field("retry", &self.retry)
+            .finish()
+    }
+}
+
+/// SurrealQL query whose syntax was validated at compile time, created by
+/// the `surql!` macro of the `macros` feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PreparedQuery(&'static str);
+
+impl PreparedQuery {
+    /// Creates a [`PreparedQuery`] without validating `sql`.
+    ///
+    /// This is used by the `surql!` macro after validating the query.
+    #[doc(hidden)]
+    #[must_use]
+    pub const fn new_unchecked(sql: &'static str) -> Self {
+        Self(sql)
+    }
+
+    /// Returns the SurrealQL of the query.
+    #[must_use]
+    pub const fn sql(&self) -> &'static str {
+        self.0
+    }
+
+    /// Prepares the query for execution on `client`.
+    pub fn on<'a>(&self, client: &'a ClientWrapper) -> Query<'a> {
+        client.query(self.0)
+    }
+
+    /// Prepares the query for execution on a connection checked out from
+    /// `pool` once it is awaited.
+    pub fn on_pool<'a>(&self, pool: &'a Pool) -> PoolQuery<'a> {
+        PoolQuery::new(pool, self.0.to_string())
+    }
+}
+
+impl<'a> IntoFuture for Query<'a> {
+    type Output = Result<Response>;
+    type IntoFuture = BoxFuture<'a, Result<Response>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.run())
+    }
+}
+
+impl fmt::Debug for Query<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Query")
+            .field("sql", &self.sql)
+            .field("bindings", &self.bindings.len())
+            .field("timeout", &self.timeout)
+            .field("trace", &self.trace)
+            .field("tags", &self.tags)
+            .finish()
+    }
+}
+
+/// Fails with [`Error::ReadOnly`] if one of the top level statements of `sql`
+/// is a mutating one.
+///
+/// Mutating statements nested in blocks or subqueries, functions with side
+/// effects and queries run directly on [`Surreal<Any>`] (e.g. using
+/// [`ClientWrapper::client()`]) are not detected, so this is no replacement
+/// for proper permissions on the server.
+fn reject_mutations(sql: &str) -> Result<()> {
+    match mutating_statement(sql) {
+        Some(keyword) => Err(Error::ReadOnly(keyword)),
+        None => Ok(()),
+    }
+}
+
+/// Returns the keyword of the first top level mutating statement of `sql`.
+pub(crate) fn mutating_statement(sql: &str) -> Option<String> {
+    surql::split(sql)
+        .iter()
+        .map(|statement| surql::keyword(statement))
+        .find(|keyword| MUTATING_STATEMENTS.contains(&keyword.as_str()))
+}
+
+/// Patch of [`ClientWrapper::update_if_version()`] including the new version.
+#[derive(Serialize)]
+struct Versioned<P> {
+    #[serde(flatten)]
+    patch: P,
+    version: u64,
+}
+
+/// Type erased parameters which can be bound to a query more than once.
+#[derive(Clone)]
+pub(crate) struct Binding(pub(crate) Arc<dyn erased_serde::Serialize + Send + Sync>);
+
+impl Serialize for Binding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        erased_serde::serialize(&*self.0, serializer)
+    }
+}
+
+/// Guard returned by [`ClientWrapper::scoped_set()`] which restores the
+/// previous value of a session parameter once dropped.
+#[derive(Debug)]
+#[must_use = "the parameter is restored as soon as the guard is dropped"]
+pub struct ScopedParam<'a> {
+    client: &'a ClientWrapper,
+    restore: Option<Restore>,
+}
+
+impl ScopedParam<'_> {
+    /// Restores the previous value of the parameter right away instead of
+    /// deferring it to the next query.
+    pub async fn restore(mut self) -> Result<()> {
+        if let Some(restore) = self.restore.take() {
+            self.client.restores().push_back(restore);
+        }
+        self.client.restore_params().await
+    }
+}
+
+impl Drop for ScopedParam<'_> {
+    fn drop(&mut self) {
+        if let Some(restore) = self.restore.take() {
+            self.client.restores().push_back(restore);
+        }
+    }
+}
+
+/// Session parameter to be restored after its [`ScopedParam`] was dropped.
+#[derive(Debug)]
+struct Restore {
+    key: String,
+    previous: Option<surrealdb::Value>,
+    /// Whether the parameter was set using [`ClientWrapper::set_param()`]
+    /// before and must therefore still be removed on recycle.
+    tracked: bool,
+}
+
+/// Marks a connection as suspect when dropped before being disarmed.
+struct SuspectGuard<'a>(Option<&'a ClientWrapper>);
+
+impl SuspectGuard<'_> {
+    fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for SuspectGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(client) = self.0 {
+            client.mark_suspect();
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use deadpool::managed::Metrics;
+
+/// Source of the current time for time-based behavior like
+/// [`Config::max_idle_time`].
+///
+/// [`SystemClock`] is used by default. Set a different clock using
+/// [`Manager::clock()`], e.g. the `ManualClock` of the `test-utils` feature
+/// for testing idle timeouts without waiting for them.
+///
+/// The clock is used for all decisions this crate bases on time:
+///
+/// - the idle time of connections for [`Config::max_idle_time`],
+/// - the age and idle time reported by [`diagnostics`],
+/// - the expiry of cached query results and server versions,
+/// - the cooldown of the autoscaler,
+/// - the expiry of resolved SRV records,
+/// - the wait times recorded for checkouts and tenants.
+///
+/// Timeouts which are enforced by sleeping, i.e. the wait, create and
+/// recycle timeouts of the pool and query timeouts, are driven by the
+/// [`Runtime`] or [`Executor`] instead, and the creation and recycle times
+/// of connections are taken from the system clock by `deadpool`. Durations
+/// reported for work which actually ran, e.g. diagnostic steps or health
+/// checks, are measured using the system clock as well.
+///
+/// [`Config::max_idle_time`]: crate::Config::max_idle_time
+/// [`Manager::clock()`]: crate::Manager::clock
+/// [`diagnostics`]: crate::diagnostics
+/// [`Runtime`]: crate::Runtime
+/// [`Executor`]: crate::rt::Executor
+pub trait Clock: Send + Sync {
+    /// Returns the current instant.
+    fn now(&self) -> Instant;
+}
+
+/// [`Clock`] returning the time of the operating system.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct SharedClock(pub(crate) Arc<dyn Clock>);
+
+impl SharedClock {
+    /// Returns the current instant.
+    pub(crate) fn now(&self) -> Instant {
+        self.0.now()
+    }
+
+    /// Returns the time elapsed since `earlier`.
+    pub(crate) fn elapsed(&self, earlier: Instant) -> Duration {
+        self.0.now().saturating_duration_since(earlier)
+    }
+
+    /// Returns the time elapsed since the connection of `metrics` was
+    /// created.
+    pub(crate) fn age(&self, metrics: &Metrics) -> Duration {
+        self.0.now().saturating_duration_since(metrics.created)
+    }
+
+    /// Returns the time elapsed since the connection of `metrics` was last
+    /// checked out.
+    pub(crate) fn idle(&self, metrics: &Metrics) -> Duration {
+        let last_used = metrics.recycled.unwrap_or(metrics.created);
+        self.0.now().saturating_duration_since(last_used)
+    }
+}
+
+impl Default for SharedClock {
+    fn default() -> Self {
+        Self(Arc::new(SystemClock))
+    }
+}
+
+impl fmt::Debug for SharedClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SharedClock").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+    future::IntoFuture,
+    marker::PhantomData,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use futures::future::{BoxFuture, FutureExt, Shared};
+use serde::Serialize;
+use surrealdb::opt::QueryResult;
+
+use crate::{
+    client::{self, Binding, PoolQuery},
+    Error, Pool, Result,
+};
+
+type Erased = Arc<dyn Any + Send + Sync>;
+type Flight = Shared<BoxFuture<'static, std::result::Result<Erased, Arc<Error>>>>;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Key {
+    sql: String,
+    bindings: String,
+    result: TypeId,
+}
+
+/// Single-flight layer which runs identical concurrent reads only once.
+///
+/// Queries are identical if their text, their bindings and the type of
+/// their result are. While a query is running, identical queries wait for
+/// its result instead of checking out connections themselves. Once it
+/// finished, the next identical query runs again, so results are never
+/// cached beyond that.
+///
+/// Queries containing mutating statements are never coalesced. Errors are
+/// shared as [`Error::Shared`].
+///
+/// All clones of a [`Coalescer`] share the queries in flight.
+///
+/// ```rust,ignore
+/// let coalescer = Coalescer::new(&pool);
+/// let settings: Vec<Setting> = coalescer
+///     .query("SELECT * FROM setting WHERE app = $app")
+///     .bind(("app", "shop"))
+///     .await?;
+/// ```
+#[derive(Clone)]
+pub struct Coalescer {
+    pool: Pool,
+    flights: Arc<Mutex<HashMap<Key, Flight>>>,
+}
+
+impl Coalescer {
+    /// Creates a new [`Coalescer`] running its queries using `pool`.
+    #[must_use]
+    pub fn new(pool: &Pool) -> Self {
+        Self {
+            pool: pool.clone(),
+            flights: Arc::default(),
+        }
+    }
+
+    /// Prepares the given `query` returning the result of its first
+    /// statement as `R`.
+    pub fn query<R>(&self, query: impl Into<String>) -> CoalescedQuery<'_, R> {
+        CoalescedQuery {
+            coalescer: self,
+            sql: query.into(),
+            bindings: Vec::new(),
+            result: PhantomData,
+        }
+    }
+
+    /// Returns the number of distinct queries currently running.
+    #[must_use]
+    pub fn in_flight(&self) -> usize {
+        self.flights().len()
+    }
+
+    fn flights(&self) -> MutexGuard<'_, HashMap<Key, Flight>> {
+        self.flights.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for Coalescer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Coalescer")
+            .field("pool", &self.pool)
+            .field("in_flight", &self.in_flight())
+            .finish()
+    }
+}
+
+/// Query prepared using [`Coalescer::query()`].
+#[must_use = "queries do nothing unless awaited"]
+pub struct CoalescedQuery<'a, R> {
+    coalescer: &'a Coalescer,
+    sql: String,
+    bindings: Vec<Binding>,
+    result: PhantomData<fn() -> R>,
+}
+
+impl<R> CoalescedQuery<'_, R>
+where
+    R: Clone + Send + Sync + 'static,
+    usize: QueryResult<R>,
+{
+    /// Binds parameters to the query.
+    ///
+    /// See [`Query::bind()`] for details.
+    ///
+    /// [`Query::bind()`]: crate::client::Query::bind
+    pub fn bind(mut self, bindings: impl Serialize + Send + Sync + 'static) -> Self {
+        self.bindings.push(Binding(Arc::new(bindings)));
+        self
+    }
+
+    async fn run(self) -> Result<R> {
+        let pool = self.coalescer.pool.clone();
+        let bindings = serde_json::to_string(&self.bindings);
+        let (Ok(bindings), None) = (bindings, client::mutating_statement(&self.sql)) else {
+            return fetch(pool, self.sql, self.bindings).await;
+        };
+        let key = Key {
+            sql: self.sql.clone(),
+            bindings,
+            result: TypeId::of::<R>(),
+        };
+        let flight = self
+            .coalescer
+            .flights()
+            .entry(key.clone())
+            .or_insert_with(|| {
+                let flights = self.coalescer.flights.clone();
+                async move {
+                    let result = fetch::<R>(pool, self.sql, self.bindings).await;
+                    let _ = flights
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .remove(&key);
+                    result
+                        .map(|value| Arc::new(value) as Erased)
+                        .map_err(Arc::new)
+                }
+                .boxed()
+                .shared()
+            })
+            .clone();
+        match flight.await {
+            Ok(value) => Ok(value
+                .downcast_ref::<R>()
+                .expect("Flights are keyed by the type of their result")
+                .clone()),
+            Err(e) => Err(Error::Shared(e)),
+        }
+    }
+}
+
+async fn fetch<R>(pool: Pool, sql: String, bindings: Vec<Binding>) -> Result<R>
+where
+    usize: QueryResult<R>,
+{
+    let mut response = PoolQuery::new(&pool, sql).bindings(bindings).await?;
+    Ok(response.take(0)?)
+}
+
+impl<'a, R> IntoFuture for CoalescedQuery<'a, R>
+where
+    R: Clone + Send + Sync + 'static,
+    usize: QueryResult<R>,
+{
+    type Output = Result<R>;
+    type IntoFuture = BoxFuture<'a, Result<R>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.run())
+    }
+}
+
+impl<R> fmt::Debug for CoalescedQuery<'_, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CoalescedQuery")
+            .field("sql", &self.sql)
+            .field("bindings", &self.bindings.len())
+            .finish()
+    }
+}
@@ -1,6 +1,13 @@
 use crate::{Manager, Pool};
 use deadpool_runtime::Runtime;
-use std::time::Duration;
+use std::{borrow::Cow, env::VarError, fmt, str::FromStr, time::Duration};
+
+/// Prefix of the environment variables read by [`Config::from_env_partial()`].
+pub const ENV_PREFIX: &str = "SURREALDB__";
+
+/// Scheme of RocksDB hosts in configurations written for older releases of
+/// SurrealDB, which now use `rocksdb://`
+const LEGACY_FILE_SCHEME: &str = "file://";
 
 /// Authentication credentials for SurrealDB
 #[derive(Debug, Clone)]
@@ -35,11 +42,72 @@ pub enum Credentials {
     },
 }
 
+impl Credentials {
+    /// Creates root user credentials
+    pub fn root(user: impl Into<String>, pass: impl Into<String>) -> Self {
+        Self::Root {
+            user: user.into(),
+            pass: pass.into(),
+        }
+    }
+
+    /// Creates namespace-scoped credentials
+    pub fn namespace(
+        user: impl Into<String>,
+        pass: impl Into<String>,
+        ns: impl Into<String>,
+    ) -> Self {
+        Self::Namespace {
+            user: user.into(),
+            pass: pass.into(),
+            ns: ns.into(),
+        }
+    }
+
+    /// Creates database-scoped credentials
+    pub fn database(
+        user: impl Into<String>,
+        pass: impl Into<String>,
+        ns: impl Into<String>,
+        db: impl Into<String>,
+    ) -> Self {
+        Self::Database {
+            user: user.into(),
+            pass: pass.into(),
+            ns: ns.into(),
+            db: db.into(),
+        }
+    }
+
+    /// Returns the kind of these credentials, i.e. `root`, `namespace` or
+    /// `database`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Root { .. } => "root",
+            Self::Namespace { .. } => "namespace",
+            Self::Database { .. } => "database",
+        }
+    }
+
+    fn user(&self) -> &str {
+        match self {
+            Self::Root { user, .. }
+            | Self::Namespace { user, .. }
+            | Self::Database { user, .. } => user,
+        }
+    }
+}
+
 /// Configuration for SurrealDB connection pool
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Config {
     /// Database host URL (e.g. "ws://localhost:8000" or "mem://")
+    ///
+    /// With the `srv` feature the host can refer to a DNS SRV record like
+    /// `srv+ws://_surreal._tcp.example.com`. New connections are then
+    /// distributed across the targets of the record, which is resolved again
+    /// once its TTL expired.
     pub host: String,
     /// Default namespace
     pub ns: String,
@@ -56,6 +124,339 @@ pub struct Config {
     /// Idle timeout in seconds
     #[cfg_attr(feature = "serde", serde(skip))]
     pub idle_timeout: u64,
+    /// Define the namespace and database when creating connections if they
+    /// don't exist yet
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub create_if_missing: bool,
+    /// Reject mutating statements before sending them to the server
+    ///
+    /// This only catches obviously mutating statements (e.g. `CREATE`,
+    /// `UPDATE`, `DELETE`, `DEFINE` or `REMOVE`) run using
+    /// [`ClientWrapper::query()`] and the record methods of
+    /// [`ClientWrapper`] like [`ClientWrapper::create()`]. It is no
+    /// replacement for read-only credentials.
+    ///
+    /// [`ClientWrapper`]: crate::ClientWrapper
+    /// [`ClientWrapper::query()`]: crate::ClientWrapper::query
+    /// [`ClientWrapper::create()`]: crate::ClientWrapper::create
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub read_only: bool,
+    /// Retry queries without mutating statements once on a fresh connection
+    /// if the connection turns out to be broken
+    ///
+    /// This only applies to queries run using [`PoolExt::query()`].
+    ///
+    /// [`PoolExt::query()`]: crate::PoolExt::query
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub retry_on_disconnect: bool,
+    /// Discard connections which haven't been checked out for longer than
+    /// this many seconds instead of reusing them
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_idle_time: Option<u64>,
+    /// Secondary pool for long running queries
+    ///
+    /// See [`PoolExt::analytics()`].
+    ///
+    /// [`PoolExt::analytics()`]: crate::PoolExt::analytics
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub analytics: Option<AnalyticsConfig>,
+    /// Name of the pool included in errors, for telling pools apart when a
+    /// process uses several of them
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub name: Option<String>,
+    /// Address family used for connecting to the host
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub address_family: AddressFamily,
+    /// Authenticate again and retry the query once if a query fails because
+    /// the session or its token expired
+    ///
+    /// This only applies to queries run using [`ClientWrapper::query()`].
+    ///
+    /// [`ClientWrapper::query()`]: crate::ClientWrapper::query
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reauth_on_expiry: bool,
+    /// Timeout in seconds for queries run using [`ClientWrapper::query()`]
+    /// or [`PoolExt::query()`] which don't set a timeout of their own
+    ///
+    /// See [`Query::timeout()`] for how timeouts are enforced. Enforcing
+    /// them needs a [`Runtime`], so [`Config::create_pool()`] rejects this
+    /// setting if none is given.
+    ///
+    /// [`ClientWrapper::query()`]: crate::ClientWrapper::query
+    /// [`PoolExt::query()`]: crate::PoolExt::query
+    /// [`Query::timeout()`]: crate::client::Query::timeout
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub default_query_timeout: Option<u64>,
+    /// Number of connections only handed out to checkouts of the
+    /// [`OperationClass::Write`] and [`OperationClass::Admin`] classes
+    ///
+    /// See [`PoolExt::get_for()`] for details.
+    ///
+    /// [`OperationClass::Write`]: crate::priority::OperationClass::Write
+    /// [`OperationClass::Admin`]: crate::priority::OperationClass::Admin
+    /// [`PoolExt::get_for()`]: crate::PoolExt::get_for
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reserved_for_writes: u32,
+    /// Discard connections which transferred more than this many bytes
+    /// instead of reusing them, bounding the buffers the client keeps per
+    /// connection
+    ///
+    /// Setting it turns on [`Config::count_bytes`]. See
+    /// [`ConnectionMetrics`] for how bytes are counted.
+    ///
+    /// [`ConnectionMetrics`]: crate::client::ConnectionMetrics
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_transferred_bytes: Option<u64>,
+    /// Estimate the bytes transferred by every connection, see
+    /// [`ConnectionMetrics`]
+    ///
+    /// This is off by default, as estimating the size of a response costs
+    /// about as much as formatting it.
+    ///
+    /// [`ConnectionMetrics`]: crate::client::ConnectionMetrics
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub count_bytes: bool,
+}
+
+/// Address family used for connecting to the host
+///
+/// Except for [`AddressFamily::Any`] the host name is resolved by the pool,
+/// which only applies to plain `ws://` and `http://` hosts. TLS hosts are
+/// always connected to using their host name, as the certificate is verified
+/// against it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum AddressFamily {
+    /// Leave resolving the host name to the SurrealDB client
+    #[default]
+    Any,
+    /// Connect using IPv6, falling back to IPv4 if connecting using IPv6
+    /// didn't succeed within 250 milliseconds
+    PreferIpv6,
+    /// Connect using IPv4, falling back to IPv6 if connecting using IPv4
+    /// didn't succeed within 250 milliseconds
+    PreferIpv4,
+    /// Connect using IPv6 only
+    Ipv6Only,
+    /// Connect using IPv4 only
+    Ipv4Only,
+}
+
+/// Configuration of a secondary pool for long running queries
+///
+/// The pool shares the namespace, database and credentials of the primary
+/// pool. It neither inherits [`Config::default_query_timeout`], as its
+/// queries are expected to run longer, nor [`Config::reserved_for_writes`],
+/// as it isn't meant for writes.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct AnalyticsConfig {
+    /// Database host URL, defaults to the host of the primary pool
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub host: Option<String>,
+    /// Maximum number of connections in the pool
+    #[cfg_attr(feature = "serde", serde(default = "default_analytics_max_connections"))]
+    pub max_connections: u32,
+    /// Time to wait for a connection in seconds
+    #[cfg_attr(feature = "serde", serde(default = "default_analytics_wait_timeout"))]
+    pub wait_timeout: u64,
+    /// Timeout in seconds for queries which don't set a timeout of their
+    /// own, replacing [`Config::default_query_timeout`] for this pool
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub query_timeout: Option<u64>,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self {
+            host: None,
+            max_connections: default_analytics_max_connections(),
+            wait_timeout: default_analytics_wait_timeout(),
+            query_timeout: None,
+        }
+    }
+}
+
+/// 64 bit FNV-1a hash of the text written to it.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// Hashes the setting `key` with its `value`.
+    fn field(&mut self, key: &str, value: &(impl Fingerprint + ?Sized)) {
+        key.hash_into(self);
+        value.hash_into(self);
+    }
+}
+
+/// Setting which is part of [`Config::fingerprint()`].
+///
+/// Every value is hashed with an unambiguous encoding of its own, so the
+/// fingerprint doesn't depend on `Debug` output which may change between
+/// releases of Rust or its dependencies.
+trait Fingerprint {
+    fn hash_into(&self, hash: &mut Fnv1a);
+}
+
+impl Fingerprint for str {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        (self.len() as u64).hash_into(hash);
+        hash.write(self.as_bytes());
+    }
+}
+
+impl Fingerprint for String {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        self.as_str().hash_into(hash);
+    }
+}
+
+impl Fingerprint for u64 {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write(&self.to_le_bytes());
+    }
+}
+
+impl Fingerprint for u32 {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        u64::from(*self).hash_into(hash);
+    }
+}
+
+impl Fingerprint for bool {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.write(&[u8::from(*self)]);
+    }
+}
+
+impl<T: Fingerprint> Fingerprint for Option<T> {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        match self {
+            None => hash.write(&[0]),
+            Some(value) => {
+                hash.write(&[1]);
+                value.hash_into(hash);
+            }
+        }
+    }
+}
+
+impl Fingerprint for AddressFamily {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        let name = match self {
+            Self::Any => "any",
+            Self::PreferIpv6 => "prefer_ipv6",
+            Self::PreferIpv4 => "prefer_ipv4",
+            Self::Ipv6Only => "ipv6_only",
+            Self::Ipv4Only => "ipv4_only",
+        };
+        name.hash_into(hash);
+    }
+}
+
+impl Fingerprint for Credentials {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        hash.field("kind", self.kind());
+        match self {
+            Self::Root { user, pass: _ } => hash.field("user", user),
+            Self::Namespace { user, pass: _, ns } => {
+                hash.field("user", user);
+                hash.field("ns", ns);
+            }
+            Self::Database {
+                user,
+                pass: _,
+                ns,
+                db,
+            } => {
+                hash.field("user", user);
+                hash.field("ns", ns);
+                hash.field("db", db);
+            }
+        }
+    }
+}
+
+impl Fingerprint for AnalyticsConfig {
+    fn hash_into(&self, hash: &mut Fnv1a) {
+        let Self {
+            host,
+            max_connections,
+            wait_timeout,
+            query_timeout,
+        } = self;
+        hash.field("host", host);
+        hash.field("max_connections", max_connections);
+        hash.field("wait_timeout", wait_timeout);
+        hash.field("query_timeout", query_timeout);
+    }
+}
+
+/// Returns the `host` using the current names of the engines, translating
+/// legacy `file://` hosts to `rocksdb://`.
+pub(crate) fn engine_host(host: &str) -> Cow<'_, str> {
+    match host.strip_prefix(LEGACY_FILE_SCHEME) {
+        Some(path) => Cow::Owned(format!("rocksdb://{}", path)),
+        None => Cow::Borrowed(host),
+    }
+}
+
+/// Returns whether connections to `host` skip authentication, which local
+/// engines don't support.
+pub(crate) fn skips_auth(host: &str) -> bool {
+    host.starts_with("mem://") || host.starts_with(LEGACY_FILE_SCHEME)
+}
+
+/// Warns about deprecated settings of `config`.
+pub(crate) fn warn_deprecated(config: &Config) {
+    #[cfg(feature = "tracing")]
+    if config.host.starts_with(LEGACY_FILE_SCHEME) {
+        tracing::warn!(
+            host = %config.host,
+            "`file://` hosts are deprecated, use `rocksdb://` instead"
+        );
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = config;
+}
+
+fn check_host(name: &str, host: &str) -> crate::Result<()> {
+    if host.is_empty() {
+        return Err(crate::Error::InvalidConfig(format!("{} is empty", name)));
+    }
+    if let Some(path) = host.strip_prefix(LEGACY_FILE_SCHEME) {
+        if path.is_empty() {
+            return Err(crate::Error::InvalidConfig(format!(
+                "{} `{}` has no path",
+                name, host
+            )));
+        }
+        if path.contains('\0') {
+            return Err(crate::Error::InvalidConfig(format!(
+                "{} `{}` contains a NUL character",
+                name, host
+            )));
+        }
+    }
+    if !host.contains("://") && host != "memory" {
+        return Err(crate::Error::InvalidConfig(format!(
+            "{} `{}` has no scheme like `ws://` or `mem://`",
+            name, host
+        )));
+    }
+    Ok(())
 }
 
 fn default_connect_timeout() -> u64 {
@@ -70,19 +471,36 @@ fn default_max_connections() -> u32 {
     10
 }
 
+fn default_analytics_max_connections() -> u32 {
+    2
+}
+
+fn default_analytics_wait_timeout() -> u64 {
+    300
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             host: String::new(),
             ns: "test".to_string(),
             db: "test".to_string(),
-            creds: Credentials::Root {
-                user: "root".to_string(),
-                pass: "root".to_string(),
-            },
+            creds: Credentials::root("root", "root"),
             connect_timeout: default_connect_timeout(),
             max_connections: default_max_connections(),
             idle_timeout: default_idle_timeout(),
+            create_if_missing: false,
+            read_only: false,
+            retry_on_disconnect: false,
+            max_idle_time: None,
+            analytics: None,
+            name: None,
+            address_family: AddressFamily::Any,
+            reauth_on_expiry: false,
+            default_query_timeout: None,
+            reserved_for_writes: 0,
+            max_transferred_bytes: None,
+            count_bytes: false,
         }
     }
 }
@@ -100,15 +518,158 @@ impl Config {
     /// # Returns
     ///
     /// A new Config instance with default timeouts and pool size
-    pub fn new(host: String, ns: String, db: String, creds: Credentials) -> Self {
+    pub fn new(
+        host: impl Into<String>,
+        ns: impl Into<String>,
+        db: impl Into<String>,
+        creds: Credentials,
+    ) -> Self {
         Self {
-            host,
-            ns,
-            db,
+            host: host.into(),
+            ns: ns.into(),
+            db: db.into(),
             creds,
-            connect_timeout: default_connect_timeout(),
-            max_connections: default_max_connections(),
-            idle_timeout: default_idle_timeout(),
+            ..Self::default()
+        }
+    }
+
+    /// Adjusts this configuration for serverless environments like AWS Lambda
+    ///
+    /// Sandboxes of such environments are frozen between invocations, which
+    /// leaves sockets open that fail once the sandbox is thawed. This profile
+    /// limits the pool to a single connection, discards connections which
+    /// haven't been used for ten seconds and uses short timeouts, so stale
+    /// connections are replaced quickly. Connections are only created when
+    /// they are needed.
+    ///
+    /// Use [`serverless::pool()`] for sharing the pool between invocations.
+    ///
+    /// [`serverless::pool()`]: crate::serverless::pool
+    #[must_use]
+    pub fn serverless(mut self) -> Self {
+        self.max_connections = 1;
+        self.connect_timeout = 3;
+        self.idle_timeout = 2;
+        self.max_idle_time = Some(10);
+        self
+    }
+
+    /// Creates a configuration from the environment variables used by the
+    /// SurrealDB CLI and tools
+    ///
+    /// - `SURREAL_URL` - Database host URL (required)
+    /// - `SURREAL_NS` and `SURREAL_DB` - Namespace and database, both default
+    ///   to `"test"`
+    /// - `SURREAL_USER` and `SURREAL_PASS` - Root credentials, default to
+    ///   `root`/`root`
+    ///
+    /// All other settings use their defaults.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidConfig`] naming the variable if `SURREAL_URL`
+    /// is missing or not a valid host URL, or if any of the variables isn't
+    /// valid unicode.
+    ///
+    /// [`Error::InvalidConfig`]: crate::Error::InvalidConfig
+    pub fn from_surreal_env() -> crate::Result<Self> {
+        let var = |name: &str| match std::env::var(name) {
+            Ok(value) => Ok(Some(value)),
+            Err(VarError::NotPresent) => Ok(None),
+            Err(VarError::NotUnicode(_)) => Err(crate::Error::InvalidConfig(format!(
+                "{} is not valid unicode",
+                name
+            ))),
+        };
+        let host = var("SURREAL_URL")?
+            .ok_or_else(|| crate::Error::InvalidConfig("SURREAL_URL is not set".to_string()))?;
+        check_host("SURREAL_URL", &host)?;
+        let mut config = Self {
+            host,
+            ..Self::default()
+        };
+        if let Some(ns) = var("SURREAL_NS")? {
+            config.ns = ns;
+        }
+        if let Some(db) = var("SURREAL_DB")? {
+            config.db = db;
+        }
+        if let Credentials::Root { user, pass } = &mut config.creds {
+            if let Some(value) = var("SURREAL_USER")? {
+                *user = value;
+            }
+            if let Some(value) = var("SURREAL_PASS")? {
+                *pass = value;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Creates a configuration from the environment variables starting with
+    /// [`ENV_PREFIX`], using the defaults for all settings which aren't set
+    ///
+    /// Variables are named after the fields in upper case, with nested
+    /// fields separated by `__`, e.g. `SURREALDB__MAX_CONNECTIONS` or
+    /// `SURREALDB__CREDS__ROOT__USER`. The analytics pool can't be
+    /// configured this way.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`EnvError`] listing all variables which are set but
+    /// couldn't be parsed, instead of falling back to the defaults.
+    pub fn from_env_partial() -> Result<Self, EnvError> {
+        Self::default().merge_env(ENV_PREFIX)
+    }
+
+    /// Overrides the settings of this configuration with the environment
+    /// variables starting with `prefix`
+    ///
+    /// See [`Config::from_env_partial()`] for how the variables are named.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`EnvError`] listing all variables which are set but
+    /// couldn't be parsed.
+    pub fn merge_env(mut self, prefix: &str) -> Result<Self, EnvError> {
+        let mut env = EnvVars {
+            prefix,
+            invalid: Vec::new(),
+        };
+        env.set("HOST", &mut self.host);
+        env.set("NS", &mut self.ns);
+        env.set("DB", &mut self.db);
+        env.set("CONNECT_TIMEOUT", &mut self.connect_timeout);
+        env.set("MAX_CONNECTIONS", &mut self.max_connections);
+        env.set("IDLE_TIMEOUT", &mut self.idle_timeout);
+        env.set("CREATE_IF_MISSING", &mut self.create_if_missing);
+        env.set("READ_ONLY", &mut self.read_only);
+        env.set("RETRY_ON_DISCONNECT", &mut self.retry_on_disconnect);
+        env.set_some("MAX_IDLE_TIME", &mut self.max_idle_time);
+        env.set_some("NAME", &mut self.name);
+        env.set("REAUTH_ON_EXPIRY", &mut self.reauth_on_expiry);
+        env.set_some("DEFAULT_QUERY_TIMEOUT", &mut self.default_query_timeout);
+        env.set("RESERVED_FOR_WRITES", &mut self.reserved_for_writes);
+        env.set_some("MAX_TRANSFERRED_BYTES", &mut self.max_transferred_bytes);
+        env.set("COUNT_BYTES", &mut self.count_bytes);
+        if let Some(family) = env.get("ADDRESS_FAMILY") {
+            match family.to_ascii_lowercase().replace('_', "").as_str() {
+                "any" => self.address_family = AddressFamily::Any,
+                "preferipv6" => self.address_family = AddressFamily::PreferIpv6,
+                "preferipv4" => self.address_family = AddressFamily::PreferIpv4,
+                "ipv6only" => self.address_family = AddressFamily::Ipv6Only,
+                "ipv4only" => self.address_family = AddressFamily::Ipv4Only,
+                _ => env.invalid("ADDRESS_FAMILY", "unknown address family"),
+            }
+        }
+        if let Some(creds) = env.creds(&self) {
+            self.creds = creds;
+        }
+        if env.invalid.is_empty() {
+            Ok(self)
+        } else {
+            Err(EnvError {
+                invalid: env.invalid,
+            })
         }
     }
 
@@ -127,14 +688,187 @@ impl Config {
         Duration::from_secs(self.idle_timeout)
     }
 
+    /// Returns a fingerprint of all settings except passwords, e.g.
+    /// `9e3f61c0a2d47b18`.
+    ///
+    /// It is a 64 bit FNV-1a hash of every setting which is the same across
+    /// processes and platforms, so tooling can verify that every instance
+    /// of a fleet runs the intended configuration. It is included in the
+    /// [`DebugDump`] and the [`PoolHealth`] of pools and returned by
+    /// [`PoolExt::fingerprint()`] for labelling metrics.
+    ///
+    /// [`DebugDump`]: crate::diagnostics::DebugDump
+    /// [`PoolHealth`]: crate::k8s::PoolHealth
+    /// [`PoolExt::fingerprint()`]: crate::PoolExt::fingerprint
+    #[must_use]
+    pub fn fingerprint(&self) -> String {
+        let Self {
+            host,
+            ns,
+            db,
+            creds,
+            connect_timeout,
+            max_connections,
+            idle_timeout,
+            create_if_missing,
+            read_only,
+            retry_on_disconnect,
+            max_idle_time,
+            analytics,
+            name,
+            address_family,
+            reauth_on_expiry,
+            default_query_timeout,
+            reserved_for_writes,
+            max_transferred_bytes,
+            count_bytes,
+        } = self;
+        let mut hash = Fnv1a::default();
+        hash.field("host", host);
+        hash.field("ns", ns);
+        hash.field("db", db);
+        hash.field("creds", creds);
+        hash.field("connect_timeout", connect_timeout);
+        hash.field("max_connections", max_connections);
+        hash.field("idle_timeout", idle_timeout);
+        hash.field("create_if_missing", create_if_missing);
+        hash.field("read_only", read_only);
+        hash.field("retry_on_disconnect", retry_on_disconnect);
+        hash.field("max_idle_time", max_idle_time);
+        hash.field("analytics", analytics);
+        hash.field("name", name);
+        hash.field("address_family", address_family);
+        hash.field("reauth_on_expiry", reauth_on_expiry);
+        hash.field("default_query_timeout", default_query_timeout);
+        hash.field("reserved_for_writes", reserved_for_writes);
+        hash.field("max_transferred_bytes", max_transferred_bytes);
+        hash.field("count_bytes", count_bytes);
+        format!("{:016x}", hash.0)
+    }
+
+    /// Checks this configuration for settings which can't work together,
+    /// like credentials for another namespace or database than the one used
+    /// by the pool.
+    ///
+    /// Called by [`Config::create_pool()`], so invalid configurations are
+    /// rejected before the first connection is attempted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidConfig`] describing the first problem found.
+    ///
+    /// [`Error::InvalidConfig`]: crate::Error::InvalidConfig
+    pub fn validate(&self) -> crate::Result<()> {
+        let invalid = |message: String| Err(crate::Error::InvalidConfig(message));
+        check_host("host", &self.host)?;
+        if let Some(host) = self.analytics.as_ref().and_then(|a| a.host.as_deref()) {
+            check_host("analytics host", host)?;
+        }
+        if self.ns.is_empty() {
+            return invalid("namespace is empty".to_string());
+        }
+        if self.db.is_empty() {
+            return invalid("database is empty".to_string());
+        }
+        match &self.creds {
+            Credentials::Namespace { ns, .. } if *ns != self.ns => {
+                return invalid(format!(
+                    "namespace credentials are for namespace `{}`, but the pool uses `{}`",
+                    ns, self.ns
+                ));
+            }
+            Credentials::Database { ns, db, .. } if *ns != self.ns || *db != self.db => {
+                return invalid(format!(
+                    "database credentials are for `{}`/`{}`, but the pool uses `{}`/`{}`",
+                    ns, db, self.ns, self.db
+                ));
+            }
+            _ => {}
+        }
+        // Local engines don't authenticate, so their credentials are never
+        // used.
+        if !skips_auth(&self.host) && self.creds.user().is_empty() {
+            return invalid(format!(
+                "{} credentials without a user can't sign in to `{}`",
+                self.creds.kind(),
+                self.host
+            ));
+        }
+        if self.max_connections == 0 {
+            return invalid("max_connections must be at least 1".to_string());
+        }
+        if self.reserved_for_writes >= self.max_connections {
+            return invalid(format!(
+                "reserved_for_writes ({}) must be less than max_connections ({})",
+                self.reserved_for_writes, self.max_connections
+            ));
+        }
+        Ok(())
+    }
+
     /// Creates a new connection pool with the given runtime
+    ///
+    /// Fails with [`Error::InvalidConfig`] if [`Config::validate()`] rejects
+    /// this configuration, or if [`Config::default_query_timeout`] is set
+    /// without a `runtime` to enforce it, which would make every query fail.
+    ///
+    /// [`Error::InvalidConfig`]: crate::Error::InvalidConfig
     pub fn create_pool(&self, runtime: Option<Runtime>) -> crate::Result<Pool> {
-        let mgr = Manager::from_config(self);
+        self.check(runtime)?;
+        let wait_timeout = Duration::from_secs(self.connect_timeout);
+        self.clone().build_pool(runtime, wait_timeout)
+    }
+
+    /// Validates this configuration for a pool using `runtime`.
+    fn check(&self, runtime: Option<Runtime>) -> crate::Result<()> {
+        self.validate()?;
+        if runtime.is_none() && self.default_query_timeout.is_some() {
+            return Err(crate::Error::InvalidConfig(
+                "default_query_timeout requires a runtime".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Builds the pool, moving this configuration into its [`Manager`].
+    fn build_pool(self, runtime: Option<Runtime>, wait_timeout: Duration) -> crate::Result<Pool> {
+        let analytics = match &self.analytics {
+            Some(analytics) => {
+                let config = Config {
+                    host: analytics.host.clone().unwrap_or_else(|| self.host.clone()),
+                    max_connections: analytics.max_connections,
+                    default_query_timeout: analytics.query_timeout,
+                    reserved_for_writes: 0,
+                    analytics: None,
+                    name: self.name.as_ref().map(|name| format!("{}-analytics", name)),
+                    ..self.clone()
+                };
+                config.check(runtime).map_err(|e| match e {
+                    crate::Error::InvalidConfig(message) => {
+                        crate::Error::InvalidConfig(format!("analytics pool: {}", message))
+                    }
+                    e => e,
+                })?;
+                let wait_timeout = Duration::from_secs(analytics.wait_timeout);
+                Some(config.build_pool(runtime, wait_timeout)?)
+            }
+            None => None,
+        };
+        let max_size = self.max_connections as usize;
+        let create_timeout = Duration::from_secs(self.connect_timeout);
+        let recycle_timeout = Duration::from_secs(self.idle_timeout);
+        let mut mgr = Manager::from(self);
+        if let Some(rt) = runtime {
+            mgr = mgr.runtime(rt);
+        }
+        if let Some(analytics) = analytics {
+            mgr = mgr.analytics(analytics);
+        }
         let builder = Pool::builder(mgr)
-            .max_size(self.max_connections as usize)
-            .wait_timeout(Some(Duration::from_secs(self.connect_timeout)))
-            .create_timeout(Some(Duration::from_secs(self.connect_timeout)))
-            .recycle_timeout(Some(Duration::from_secs(self.idle_timeout)));
+            .max_size(max_size)
+            .wait_timeout(Some(wait_timeout))
+            .create_timeout(Some(create_timeout))
+            .recycle_timeout(Some(recycle_timeout));
         match runtime {
             Some(rt) => Ok(builder.runtime(rt).build()?),
             None => Ok(builder.build()?),
@@ -142,6 +876,151 @@ impl Config {
     }
 }
 
+/// Prints the host, namespace, database, pool sizing and the kind of the
+/// credentials, but neither users nor passwords, so the configuration can be
+/// logged safely.
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.name {
+            write!(f, "name={}, ", name)?;
+        }
+        write!(f, "host={}, ns={}, db={}, ", self.host, self.ns, self.db)?;
+        write!(
+            f,
+            "max_connections={}, connect_timeout={}s, idle_timeout={}s, ",
+            self.max_connections, self.connect_timeout, self.idle_timeout
+        )?;
+        write!(f, "credentials={}", self.creds.kind())
+    }
+}
+
+/// Environment variables which are set but couldn't be parsed by
+/// [`Config::from_env_partial()`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnvError {
+    /// Names of the invalid variables along with why they are invalid
+    pub invalid: Vec<(String, String)>,
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Invalid environment variables: ")?;
+        for (i, (name, reason)) in self.invalid.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{} ({})", name, reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for EnvError {}
+
+/// Environment variables read by [`Config::merge_env()`], collecting the
+/// invalid ones.
+struct EnvVars<'a> {
+    prefix: &'a str,
+    invalid: Vec<(String, String)>,
+}
+
+impl EnvVars<'_> {
+    fn get(&mut self, key: &str) -> Option<String> {
+        match std::env::var(format!("{}{}", self.prefix, key)) {
+            Ok(value) => Some(value),
+            Err(VarError::NotPresent) => None,
+            Err(VarError::NotUnicode(_)) => {
+                self.invalid(key, "not valid unicode");
+                None
+            }
+        }
+    }
+
+    fn parse<T>(&mut self, key: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        match self.get(key)?.parse() {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.invalid(key, e);
+                None
+            }
+        }
+    }
+
+    fn set<T>(&mut self, key: &str, target: &mut T)
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        if let Some(value) = self.parse(key) {
+            *target = value;
+        }
+    }
+
+    fn set_some<T>(&mut self, key: &str, target: &mut Option<T>)
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        if let Some(value) = self.parse(key) {
+            *target = Some(value);
+        }
+    }
+
+    /// Reads the credentials of a single kind, keeping the user and
+    /// password of `config` if its credentials are of the same kind.
+    fn creds(&mut self, config: &Config) -> Option<Credentials> {
+        let kinds = ["ROOT", "NAMESPACE", "DATABASE"];
+        let mut set = Vec::new();
+        for kind in kinds {
+            let mut field = |name: &str| self.get(&format!("CREDS__{}__{}", kind, name));
+            let fields = [field("USER"), field("PASS"), field("NS"), field("DB")];
+            if fields.iter().any(Option::is_some) {
+                set.push((kind, fields));
+            }
+        }
+        let (kind, [user, pass, ns, db]) = match set.len() {
+            0 => return None,
+            1 => set.pop()?,
+            _ => {
+                self.invalid("CREDS", "credentials of several kinds are set");
+                return None;
+            }
+        };
+        let (current_user, current_pass) = match (&config.creds, kind) {
+            (Credentials::Root { user, pass }, "ROOT")
+            | (Credentials::Namespace { user, pass, .. }, "NAMESPACE")
+            | (Credentials::Database { user, pass, .. }, "DATABASE") => {
+                (Some(user.clone()), Some(pass.clone()))
+            }
+            _ => (None, None),
+        };
+        let Some(user) = user.or(current_user) else {
+            self.invalid(&format!("CREDS__{}__USER", kind), "not set");
+            return None;
+        };
+        let Some(pass) = pass.or(current_pass) else {
+            self.invalid(&format!("CREDS__{}__PASS", kind), "not set");
+            return None;
+        };
+        let ns = ns.unwrap_or_else(|| config.ns.clone());
+        let db = db.unwrap_or_else(|| config.db.clone());
+        Some(match kind {
+            "ROOT" => Credentials::Root { user, pass },
+            "NAMESPACE" => Credentials::Namespace { user, pass, ns },
+            _ => Credentials::Database { user, pass, ns, db },
+        })
+    }
+
+    fn invalid(&mut self, key: &str, reason: impl fmt::Display) {
+        self.invalid
+            .push((format!("{}{}", self.prefix, key), reason.to_string()));
+    }
+}
+
 /// Builder for SurrealDB configuration
 #[derive(Debug, Default)]
 pub struct ConfigBuilder {
@@ -149,9 +1028,8 @@ pub struct ConfigBuilder {
     ns: Option<String>,
     db: Option<String>,
     creds: Option<Credentials>,
-    connect_timeout: Option<u64>,
-    max_connections: Option<u32>,
-    idle_timeout: Option<u64>,
+    /// Optional settings, starting out with their defaults
+    config: Config,
 }
 
 impl ConfigBuilder {
@@ -186,19 +1064,94 @@ impl ConfigBuilder {
 
     /// Sets the connection timeout in seconds
     pub fn connect_timeout(mut self, timeout: u64) -> Self {
-        self.connect_timeout = Some(timeout);
+        self.config.connect_timeout = timeout;
         self
     }
 
     /// Sets the maximum number of connections in the pool
     pub fn max_connections(mut self, max: u32) -> Self {
-        self.max_connections = Some(max);
+        self.config.max_connections = max;
         self
     }
 
     /// Sets the idle timeout in seconds
     pub fn idle_timeout(mut self, timeout: u64) -> Self {
-        self.idle_timeout = Some(timeout);
+        self.config.idle_timeout = timeout;
+        self
+    }
+
+    /// Defines the namespace and database if they don't exist yet
+    pub fn create_if_missing(mut self, create_if_missing: bool) -> Self {
+        self.config.create_if_missing = create_if_missing;
+        self
+    }
+
+    /// Rejects mutating statements before sending them to the server
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.config.read_only = read_only;
+        self
+    }
+
+    /// Retries read-only queries once on a fresh connection if the
+    /// connection turns out to be broken
+    pub fn retry_on_disconnect(mut self, retry_on_disconnect: bool) -> Self {
+        self.config.retry_on_disconnect = retry_on_disconnect;
+        self
+    }
+
+    /// Sets the time in seconds after which unused connections are discarded
+    pub fn max_idle_time(mut self, max_idle_time: u64) -> Self {
+        self.config.max_idle_time = Some(max_idle_time);
+        self
+    }
+
+    /// Sets the configuration of the secondary pool for long running queries
+    pub fn analytics(mut self, analytics: AnalyticsConfig) -> Self {
+        self.config.analytics = Some(analytics);
+        self
+    }
+
+    /// Sets the name of the pool included in errors
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.config.name = Some(name.into());
+        self
+    }
+
+    /// Sets the address family used for connecting to the host
+    pub fn address_family(mut self, address_family: AddressFamily) -> Self {
+        self.config.address_family = address_family;
+        self
+    }
+
+    /// Sets whether to authenticate again and retry queries failing because
+    /// the session expired
+    pub fn reauth_on_expiry(mut self, reauth_on_expiry: bool) -> Self {
+        self.config.reauth_on_expiry = reauth_on_expiry;
+        self
+    }
+
+    /// Sets the timeout in seconds for queries which don't set one of their
+    /// own
+    pub fn default_query_timeout(mut self, timeout: u64) -> Self {
+        self.config.default_query_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the number of connections reserved for writes
+    pub fn reserved_for_writes(mut self, reserved: u32) -> Self {
+        self.config.reserved_for_writes = reserved;
+        self
+    }
+
+    /// Sets the number of bytes after which connections are discarded
+    pub fn max_transferred_bytes(mut self, max_transferred_bytes: u64) -> Self {
+        self.config.max_transferred_bytes = Some(max_transferred_bytes);
+        self
+    }
+
+    /// Sets whether to estimate the bytes transferred by every connection
+    pub fn count_bytes(mut self, count_bytes: bool) -> Self {
+        self.config.count_bytes = count_bytes;
         self
     }
 
@@ -209,9 +1162,7 @@ impl ConfigBuilder {
             ns: self.ns.ok_or("namespace is required")?,
             db: self.db.ok_or("database is required")?,
             creds: self.creds.ok_or("credentials are required")?,
-            connect_timeout: self.connect_timeout.unwrap_or_else(default_connect_timeout),
-            max_connections: self.max_connections.unwrap_or_else(default_max_connections),
-            idle_timeout: self.idle_timeout.unwrap_or_else(default_idle_timeout),
+            ..self.config
         })
     }
 }
\ No newline at end of file
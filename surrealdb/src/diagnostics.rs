@@ -0,0 +1,526 @@
+use std::{
+    collections::VecDeque,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, PoisonError,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use deadpool_runtime::Runtime;
+use futures::future::{BoxFuture, FutureExt};
+use serde::Serialize;
+
+use crate::{
+    config, health::HealthState, Config, Credentials, Error, ErrorKind, Manager, Pool, Result,
+    TimeoutPhase,
+};
+
+/// Number of errors kept for [`DebugDump::recent_errors`].
+pub const ERROR_LOG_CAPACITY: usize = 32;
+
+/// Number of events kept for [`DebugDump::recent_events`].
+pub const EVENT_LOG_CAPACITY: usize = 64;
+
+/// Report on the state of a [`Pool`] returned by [`PoolExt::debug_dump()`].
+///
+/// It contains no secrets, so it can be serialized as JSON and attached to
+/// incident tickets as is.
+///
+/// [`PoolExt::debug_dump()`]: crate::PoolExt::debug_dump
+#[derive(Clone, Debug, Serialize)]
+pub struct DebugDump {
+    /// Configuration of the pool
+    pub config: ConfigSummary,
+    /// Status of the pool
+    pub status: StatusSummary,
+    /// Connections currently idle in the pool
+    ///
+    /// Connections which are checked out can't be inspected.
+    pub connections: Vec<ConnectionInfo>,
+    /// Most recent errors creating, recycling or checking out connections,
+    /// oldest first
+    pub recent_errors: Vec<RecordedError>,
+    /// Most recent events of the pool, oldest first
+    pub recent_events: Vec<RecordedEvent>,
+    /// Failures creating and recycling connections by reason
+    pub failures: Failures,
+}
+
+/// [`Config`] without passwords.
+#[derive(Clone, Debug, Serialize)]
+pub struct ConfigSummary {
+    /// Name of the pool
+    pub name: Option<String>,
+    /// Database host URL
+    pub host: String,
+    /// Default namespace
+    pub ns: String,
+    /// Default database
+    pub db: String,
+    /// Kind of the credentials, i.e. `root`, `namespace` or `database`
+    pub credentials: &'static str,
+    /// User of the credentials
+    pub user: String,
+    /// Maximum number of connections in the pool
+    pub max_connections: u32,
+    /// Connection timeout in seconds
+    pub connect_timeout: u64,
+    /// Idle timeout in seconds
+    pub idle_timeout: u64,
+    /// Maximum time in seconds a connection may stay unused
+    pub max_idle_time: Option<u64>,
+    /// Whether mutating statements are rejected
+    pub read_only: bool,
+    /// Fingerprint of the configuration, see [`Config::fingerprint()`]
+    pub fingerprint: String,
+}
+
+impl From<&Config> for ConfigSummary {
+    fn from(config: &Config) -> Self {
+        let user = match &config.creds {
+            Credentials::Root { user, .. }
+            | Credentials::Namespace { user, .. }
+            | Credentials::Database { user, .. } => user,
+        };
+        Self {
+            name: config.name.clone(),
+            host: config.host.clone(),
+            ns: config.ns.clone(),
+            db: config.db.clone(),
+            credentials: config.creds.kind(),
+            user: user.clone(),
+            max_connections: config.max_connections,
+            connect_timeout: config.connect_timeout,
+            idle_timeout: config.idle_timeout,
+            max_idle_time: config.max_idle_time,
+            read_only: config.read_only,
+            fingerprint: config.fingerprint(),
+        }
+    }
+}
+
+/// Serializable copy of the [`Status`] of a [`Pool`].
+///
+/// [`Status`]: deadpool::managed::Status
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct StatusSummary {
+    /// Maximum number of connections
+    pub max_size: usize,
+    /// Current number of connections
+    pub size: usize,
+    /// Number of idle connections
+    pub available: usize,
+    /// Number of checked out connections
+    pub in_use: usize,
+    /// Number of tasks waiting for a connection
+    pub waiting: usize,
+}
+
+/// Idle connection of a [`Pool`].
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ConnectionInfo {
+    /// Id of the connection, see [`ClientWrapper::id()`]
+    ///
+    /// [`ClientWrapper::id()`]: crate::ClientWrapper::id
+    pub id: u64,
+    /// Milliseconds since the connection was created
+    pub age_ms: u64,
+    /// Milliseconds since the connection was last checked out
+    pub idle_ms: u64,
+    /// Number of times the connection was recycled
+    pub recycle_count: usize,
+}
+
+/// Error recorded by a [`Pool`].
+#[derive(Clone, Debug, Serialize)]
+pub struct RecordedError {
+    /// Milliseconds since the Unix epoch when the error occurred
+    pub timestamp_ms: u64,
+    /// Category of the error
+    pub kind: ErrorKind,
+    /// Message of the error
+    pub message: String,
+}
+
+/// Ring buffer of the most recent errors of a [`Manager`].
+///
+/// [`Manager`]: crate::Manager
+#[derive(Debug, Default)]
+pub(crate) struct ErrorLog(Mutex<VecDeque<RecordedError>>);
+
+impl ErrorLog {
+    pub(crate) fn record(&self, e: &Error) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut errors = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if errors.len() == ERROR_LOG_CAPACITY {
+            let _ = errors.pop_front();
+        }
+        errors.push_back(RecordedError {
+            timestamp_ms: millis(timestamp),
+            kind: e.kind(),
+            message: e.to_string(),
+        });
+    }
+
+    fn snapshot(&self) -> Vec<RecordedError> {
+        let errors = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        errors.iter().cloned().collect()
+    }
+}
+
+/// Event of a [`Pool`] kept for post-mortem debugging, see
+/// [`PoolExt::recent_events()`].
+///
+/// [`PoolExt::recent_events()`]: crate::PoolExt::recent_events
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PoolEvent {
+    /// A connection was created
+    Created {
+        /// Id of the connection
+        id: u64,
+        /// Endpoint the connection was made to
+        endpoint: String,
+    },
+    /// Creating a connection failed
+    CreateFailed {
+        /// Reason the attempt failed
+        reason: FailureReason,
+    },
+    /// Recycling a connection failed, so it was discarded
+    RecycleFailed {
+        /// Id of the connection
+        id: u64,
+        /// Reason the attempt failed
+        reason: FailureReason,
+    },
+    /// A connection was discarded without asking the server
+    Evicted {
+        /// Id of the connection
+        id: u64,
+        /// Why the connection was discarded, e.g. `Connection idle for too
+        /// long`
+        reason: &'static str,
+    },
+    /// The pool switched to another endpoint
+    Failover {
+        /// Host connections were created for before the switch
+        from: String,
+        /// Host connections are created for after the switch
+        to: String,
+    },
+    /// The [`HealthState`] of the pool changed
+    HealthChanged {
+        /// State before the change
+        from: HealthState,
+        /// State after the change
+        to: HealthState,
+    },
+}
+
+/// Event recorded by a [`Pool`].
+#[derive(Clone, Debug, Serialize)]
+pub struct RecordedEvent {
+    /// Milliseconds since the Unix epoch when the event occurred
+    pub timestamp_ms: u64,
+    /// The event itself
+    #[serde(flatten)]
+    pub event: PoolEvent,
+}
+
+/// Ring buffer of the most recent events of a [`Manager`].
+#[derive(Debug, Default)]
+pub(crate) struct EventLog(Mutex<VecDeque<RecordedEvent>>);
+
+impl EventLog {
+    pub(crate) fn record(&self, event: PoolEvent) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut events = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if events.len() == EVENT_LOG_CAPACITY {
+            let _ = events.pop_front();
+        }
+        events.push_back(RecordedEvent {
+            timestamp_ms: millis(timestamp),
+            event,
+        });
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<RecordedEvent> {
+        let events = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        events.iter().cloned().collect()
+    }
+}
+
+/// Reason an attempt of creating or recycling a connection failed, see
+/// [`Failures`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureReason {
+    /// The server rejected the credentials, e.g. because the session expired
+    Auth,
+    /// The attempt took too long
+    Timeout,
+    /// The server could not be reached or the connection broke
+    Io,
+    /// The server failed for any other reason
+    Server,
+}
+
+impl FailureReason {
+    /// All reasons in the order of the fields of [`FailureCounts`].
+    pub const ALL: [Self; 4] = [Self::Auth, Self::Timeout, Self::Io, Self::Server];
+
+    /// Classifies the error `e` an attempt failed with.
+    #[must_use]
+    pub fn of(e: &Error) -> Self {
+        match e.kind() {
+            ErrorKind::Auth => Self::Auth,
+            ErrorKind::Timeout => Self::Timeout,
+            ErrorKind::Connection | ErrorKind::Io => Self::Io,
+            _ => Self::Server,
+        }
+    }
+
+    /// Returns the name of this reason for use as metric label, e.g.
+    /// `auth`.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Auth => "auth",
+            Self::Timeout => "timeout",
+            Self::Io => "io",
+            Self::Server => "server",
+        }
+    }
+}
+
+/// Number of failed attempts by [`FailureReason`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct FailureCounts {
+    /// Attempts failed with [`FailureReason::Auth`]
+    pub auth: u64,
+    /// Attempts failed with [`FailureReason::Timeout`]
+    pub timeout: u64,
+    /// Attempts failed with [`FailureReason::Io`]
+    pub io: u64,
+    /// Attempts failed with [`FailureReason::Server`]
+    pub server: u64,
+}
+
+impl FailureCounts {
+    /// Returns the number of attempts failed with the given `reason`.
+    #[must_use]
+    pub fn get(&self, reason: FailureReason) -> u64 {
+        match reason {
+            FailureReason::Auth => self.auth,
+            FailureReason::Timeout => self.timeout,
+            FailureReason::Io => self.io,
+            FailureReason::Server => self.server,
+        }
+    }
+
+    /// Returns the number of failed attempts regardless of their reason.
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.auth + self.timeout + self.io + self.server
+    }
+}
+
+/// Failed attempts of creating and recycling connections of a [`Pool`]
+/// since it was created, returned by [`PoolExt::failures()`].
+///
+/// The counters only ever grow, so they can be exported as counters
+/// labelled by operation and reason:
+///
+/// ```rust,ignore
+/// for (operation, reason, count) in pool.failures().labelled() {
+///     let reason = reason.as_str();
+///     counter!("surrealdb_pool_failures_total", "operation" => operation, "reason" => reason)
+///         .absolute(count);
+/// }
+/// ```
+///
+/// Recycling connections which are discarded without asking the server,
+/// e.g. because they were idle for too long, is not a failure.
+///
+/// [`PoolExt::failures()`]: crate::PoolExt::failures
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Failures {
+    /// Failed attempts of creating connections
+    pub create: FailureCounts,
+    /// Failed attempts of recycling connections
+    pub recycle: FailureCounts,
+}
+
+impl Failures {
+    /// Returns the counters as `(operation, reason, count)`, where the
+    /// operation is either `create` or `recycle`.
+    pub fn labelled(&self) -> impl Iterator<Item = (&'static str, FailureReason, u64)> + '_ {
+        [("create", &self.create), ("recycle", &self.recycle)]
+            .into_iter()
+            .flat_map(|(operation, counts)| {
+                FailureReason::ALL.map(|reason| (operation, reason, counts.get(reason)))
+            })
+    }
+}
+
+/// Counters of the failed attempts of a [`Manager`].
+#[derive(Debug, Default)]
+pub(crate) struct FailureCounters {
+    create: [AtomicU64; 4],
+    recycle: [AtomicU64; 4],
+}
+
+impl FailureCounters {
+    pub(crate) fn create(&self, e: &Error) {
+        Self::count(&self.create, e);
+    }
+
+    pub(crate) fn recycle(&self, e: &Error) {
+        Self::count(&self.recycle, e);
+    }
+
+    fn count(counters: &[AtomicU64; 4], e: &Error) {
+        let reason = FailureReason::of(e);
+        let _ = counters[reason as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> Failures {
+        let counts = |counters: &[AtomicU64; 4]| {
+            let [auth, timeout, io, server] =
+                counters.each_ref().map(|c| c.load(Ordering::Relaxed));
+            FailureCounts {
+                auth,
+                timeout,
+                io,
+                server,
+            }
+        };
+        Failures {
+            create: counts(&self.create),
+            recycle: counts(&self.recycle),
+        }
+    }
+}
+
+pub(crate) fn dump(pool: &Pool) -> DebugDump {
+    let manager = pool.manager();
+    let mut connections = Vec::new();
+    let _ = pool.retain(|conn, metrics| {
+        connections.push(ConnectionInfo {
+            id: conn.id(),
+            age_ms: millis(manager.clock.age(&metrics)),
+            idle_ms: millis(manager.clock.idle(&metrics)),
+            recycle_count: metrics.recycle_count,
+        });
+        true
+    });
+    let status = pool.status();
+    DebugDump {
+        config: ConfigSummary::from(manager.config()),
+        status: StatusSummary {
+            max_size: status.max_size,
+            size: status.size,
+            available: status.available,
+            in_use: status.size.saturating_sub(status.available),
+            waiting: status.waiting,
+        },
+        connections,
+        recent_errors: manager.errors.snapshot(),
+        recent_events: manager.events.snapshot(),
+        failures: manager.failures.snapshot(),
+    }
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Step of a [`diagnose()`] run.
+#[derive(Debug)]
+pub struct Step {
+    /// Name of the step, e.g. `connect`
+    pub name: &'static str,
+    /// Time the step took
+    pub elapsed: Duration,
+    /// Error the step failed with, if any
+    pub error: Option<Error>,
+}
+
+/// Connects to the server of `config` step by step: connect, authenticate,
+/// select the namespace and database, and ping.
+///
+/// Every step is limited to [`Config::connect_timeout`]. The steps are run
+/// until one of them fails, which is the last step returned.
+pub async fn diagnose(config: &Config, runtime: Runtime) -> Vec<Step> {
+    let manager = Manager::from_config(config).runtime(runtime);
+    let timeout = config.connect_timeout();
+
+    let started = Instant::now();
+    let db = match limit(runtime, timeout, manager.open()).await {
+        Ok(db) => db,
+        Err(e) => {
+            return vec![Step {
+                name: "connect",
+                elapsed: started.elapsed(),
+                error: Some(e),
+            }]
+        }
+    };
+    let mut steps = vec![Step {
+        name: "connect",
+        elapsed: started.elapsed(),
+        error: None,
+    }];
+
+    let mut checks: Vec<(&'static str, BoxFuture<'_, Result<()>>)> = Vec::new();
+    // Local engines don't support authentication.
+    if !config::skips_auth(&config.host) {
+        checks.push(("authenticate", manager.auth(&db).boxed()));
+    }
+    checks.push((
+        "use ns/db",
+        async {
+            db.use_ns(&config.ns)
+                .use_db(&config.db)
+                .await
+                .map_err(|e| Error::connection("Failed to set ns/db", e))
+        }
+        .boxed(),
+    ));
+    checks.push(("ping", async { Ok(db.health().await?) }.boxed()));
+
+    for (name, check) in checks {
+        let started = Instant::now();
+        let error = limit(runtime, timeout, check).await.err();
+        let failed = error.is_some();
+        steps.push(Step {
+            name,
+            elapsed: started.elapsed(),
+            error,
+        });
+        if failed {
+            break;
+        }
+    }
+    steps
+}
+
+async fn limit<T>(
+    runtime: Runtime,
+    timeout: Duration,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    runtime
+        .timeout(timeout, future)
+        .await
+        .unwrap_or(Err(Error::Timeout {
+            phase: TimeoutPhase::Create,
+        }))
+}
@@ -0,0 +1,60 @@
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::{Error, Pool, Result};
+
+/// Endpoints of a [`Manager`] no connections are created to, see
+/// [`PoolExt::drain_endpoint()`].
+///
+/// [`Manager`]: crate::Manager
+/// [`PoolExt::drain_endpoint()`]: crate::PoolExt::drain_endpoint
+#[derive(Debug, Default)]
+pub(crate) struct Drained(Mutex<Vec<String>>);
+
+impl Drained {
+    /// Indicates whether `endpoint` is drained.
+    pub(crate) fn contains(&self, endpoint: &str) -> bool {
+        self.endpoints().iter().any(|e| same(e, endpoint))
+    }
+
+    /// Fails if `endpoint` is drained.
+    pub(crate) fn check(&self, endpoint: &str) -> Result<()> {
+        if !self.contains(endpoint) {
+            return Ok(());
+        }
+        Err(Error::Connection {
+            message: format!("Endpoint {} is drained", endpoint).into(),
+            context: None,
+            source: None,
+        })
+    }
+
+    pub(crate) fn list(&self) -> Vec<String> {
+        self.endpoints().clone()
+    }
+
+    fn endpoints(&self) -> MutexGuard<'_, Vec<String>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Stops creating connections to `endpoint` and closes the idle ones.
+pub(crate) fn drain(pool: &Pool, endpoint: &str) {
+    let drained = &pool.manager().drained;
+    if !drained.contains(endpoint) {
+        drained.endpoints().push(endpoint.to_string());
+    }
+    let _ = pool.retain(|conn, _| !drained.contains(conn.endpoint()));
+}
+
+/// Allows creating connections to `endpoint` again.
+pub(crate) fn undrain(pool: &Pool, endpoint: &str) {
+    pool.manager()
+        .drained
+        .endpoints()
+        .retain(|e| !same(e, endpoint));
+}
+
+/// Compares endpoints ignoring trailing slashes.
+fn same(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/') == b.trim_end_matches('/')
+}
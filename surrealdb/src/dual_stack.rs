@@ -0,0 +1,89 @@
+use std::{net::SocketAddr, time::Duration};
+
+use deadpool_runtime::Runtime;
+use futures::future::{self, BoxFuture, FutureExt};
+use surrealdb::{engine::any::Any, opt, Surreal};
+
+use crate::{config::AddressFamily, rt, Error, Result};
+
+/// Delay before connecting using the fallback address family, as
+/// recommended by RFC 8305.
+const FALLBACK_DELAY: Duration = Duration::from_millis(250);
+
+/// Connects to `endpoint` using the addresses of the given `family` and the
+/// `engine` configuration, if any.
+///
+/// The host name is resolved by the pool for plain `ws://` and `http://`
+/// endpoints only. TLS endpoints are connected to using their host name, as
+/// the certificate is verified against it.
+pub(crate) async fn connect(
+    endpoint: &str,
+    family: AddressFamily,
+    runtime: Option<Runtime>,
+    engine: Option<&opt::Config>,
+) -> Result<Surreal<Any>> {
+    let Some((scheme, host, port, path)) = split(endpoint).filter(|_| family != AddressFamily::Any)
+    else {
+        return connect_to(endpoint.to_string(), engine).await;
+    };
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| Error::connection("Failed to resolve host", e))?
+        .collect();
+    let (v6, v4): (Vec<_>, Vec<_>) = addrs.into_iter().partition(SocketAddr::is_ipv6);
+    let (primary, fallback) = match family {
+        AddressFamily::Any | AddressFamily::PreferIpv6 => (v6.first(), v4.first()),
+        AddressFamily::PreferIpv4 => (v4.first(), v6.first()),
+        AddressFamily::Ipv6Only => (v6.first(), None),
+        AddressFamily::Ipv4Only => (v4.first(), None),
+    };
+    let url = |addr: &SocketAddr| format!("{}://{}{}", scheme, addr, path);
+    match (primary, fallback) {
+        (Some(primary), Some(fallback)) => {
+            let fallback = url(fallback);
+            let delayed: BoxFuture<'_, Result<Surreal<Any>>> = match runtime {
+                Some(runtime) => async move {
+                    rt::sleep(runtime, FALLBACK_DELAY).await;
+                    connect_to(fallback, engine).await
+                }
+                .boxed(),
+                None => connect_to(fallback, engine).boxed(),
+            };
+            let attempts = [connect_to(url(primary), engine).boxed(), delayed];
+            future::select_ok(attempts).await.map(|(db, _)| db)
+        }
+        (Some(addr), None) | (None, Some(addr)) => connect_to(url(addr), engine).await,
+        (None, None) => Err(Error::Connection {
+            message: format!("No address of the configured family found for {}", host).into(),
+            context: None,
+            source: None,
+        }),
+    }
+}
+
+async fn connect_to(url: String, engine: Option<&opt::Config>) -> Result<Surreal<Any>> {
+    let result = match engine {
+        Some(engine) => surrealdb::engine::any::connect((url, engine.clone())).await,
+        None => surrealdb::engine::any::connect(url).await,
+    };
+    result.map_err(|e| Error::connection("Failed to connect", e))
+}
+
+/// Splits plain `ws://` and `http://` endpoints with a host name into
+/// scheme, host, port and path.
+fn split(endpoint: &str) -> Option<(&str, &str, u16, &str)> {
+    let (scheme, rest) = endpoint.split_once("://")?;
+    if scheme != "ws" && scheme != "http" {
+        return None;
+    }
+    let (authority, path) = rest.find('/').map_or((rest, ""), |i| rest.split_at(i));
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().ok()?),
+        None => (authority, 80),
+    };
+    // IPv6 literals are enclosed in brackets, IPv4 literals parse as such.
+    if host.starts_with('[') || host.parse::<std::net::IpAddr>().is_ok() {
+        return None;
+    }
+    Some((scheme, host, port, path))
+}
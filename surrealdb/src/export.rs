@@ -0,0 +1,47 @@
+use std::{path::PathBuf, pin::pin};
+
+use futures::{io::AsyncWrite, AsyncWriteExt, StreamExt};
+
+use crate::{Pool, Result};
+
+/// Progress of a running export.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExportProgress {
+    /// Number of bytes written so far
+    pub bytes: u64,
+    /// Number of chunks received from the server so far
+    pub chunks: u64,
+}
+
+/// Exports the database into `writer` using a dedicated connection.
+///
+/// The connection is created by the [`Pool`]'s manager but doesn't occupy a
+/// slot of the pool, so exports can't starve regular checkouts.
+pub(crate) async fn export<W, F>(pool: &Pool, writer: W, mut progress: F) -> Result<ExportProgress>
+where
+    W: AsyncWrite + Send,
+    F: FnMut(&ExportProgress) + Send,
+{
+    let conn = pool.manager().connect_dedicated().await?;
+    let backup = conn.export(()).await?;
+    let mut backup = pin!(backup);
+    let mut writer = pin!(writer);
+    let mut state = ExportProgress::default();
+    while let Some(chunk) = backup.next().await {
+        let chunk = chunk?;
+        writer.write_all(&chunk).await?;
+        state.bytes += chunk.len() as u64;
+        state.chunks += 1;
+        progress(&state);
+    }
+    writer.flush().await?;
+    Ok(state)
+}
+
+/// Exports the database into the file at `path` using a dedicated
+/// connection.
+pub(crate) async fn export_to_path(pool: &Pool, path: PathBuf) -> Result<()> {
+    let conn = pool.manager().connect_dedicated().await?;
+    conn.export(path).await?;
+    Ok(())
+}
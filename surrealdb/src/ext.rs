@@ -0,0 +1,512 @@
+use std::{future::Future, path::PathBuf};
+
+use futures::io::{AsyncRead, AsyncWrite};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    bulk::{self, BulkReport, UpsertReport, UpsertStrategy},
+    checkout::{self, Gauges, Pressure},
+    client::PoolQuery,
+    diagnostics::{self, DebugDump, Failures, RecordedEvent},
+    drain,
+    export::{self, ExportProgress},
+    health::{self, HealthState},
+    import::{self, ImportOptions, ImportSummary},
+    migrations::{MigrationReport, Migrator},
+    priority::{self, OperationClass, PriorityObject},
+    scope::{self, Scope},
+    script::{Script, ScriptReport},
+    seed::Seeder,
+    stream::RowStream,
+    switch,
+    tenant::{TenantObject, TenantScope},
+    version, Config, Object, Pool, Result,
+};
+
+/// Extension trait adding SurrealDB specific helpers to [`Pool`].
+pub trait PoolExt {
+    /// Inserts `items` into `table`.
+    ///
+    /// The items are split into chunks of `chunk_size` records which are
+    /// inserted using up to `parallelism` pooled connections at once. Every
+    /// chunk is retried up to [`bulk::CHUNK_ATTEMPTS`] times before it is
+    /// reported as failed in the returned [`BulkReport`].
+    ///
+    /// Chunks are only retried if no connection could be checked out for
+    /// them. Once the `INSERT` was sent, the server may have stored the
+    /// records even if the query failed, e.g. with
+    /// [`Error::QueryTimeout`], and retrying would insert them twice. Such
+    /// chunks are reported as failed after a single attempt.
+    ///
+    /// [`Error::QueryTimeout`]: crate::Error::QueryTimeout
+    fn bulk_insert<T>(
+        &self,
+        table: &str,
+        items: Vec<T>,
+        chunk_size: usize,
+        parallelism: usize,
+    ) -> impl Future<Output = BulkReport> + Send
+    where
+        T: Serialize + Send + Sync + 'static;
+
+    /// Creates or updates the records of `table` with the given ids.
+    ///
+    /// The items are split into chunks like with [`PoolExt::bulk_insert()`].
+    /// Records rejected by the database don't affect the other records of
+    /// their chunk and are reported individually in the returned
+    /// [`UpsertReport`].
+    fn bulk_upsert<T>(
+        &self,
+        table: &str,
+        items: Vec<(String, T)>,
+        strategy: UpsertStrategy,
+        chunk_size: usize,
+        parallelism: usize,
+    ) -> impl Future<Output = UpsertReport> + Send
+    where
+        T: Serialize + Send + Sync + 'static;
+
+    /// Retrieves a connection like [`Pool::get()`], calling the hook set
+    /// using [`Manager::on_checkout_failure()`] if that fails.
+    ///
+    /// [`Pool::get()`]: deadpool::managed::Pool::get
+    /// [`Manager::on_checkout_failure()`]: crate::Manager::on_checkout_failure
+    fn checkout(&self) -> impl Future<Output = Result<Object>> + Send;
+
+    /// Returns the current [`Pressure`] on this pool.
+    fn pressure(&self) -> Pressure;
+
+    /// Returns the number of idle and checked out connections and of
+    /// waiting tasks as separate [`Gauges`].
+    fn gauges(&self) -> Gauges;
+
+    /// Returns the number of failed attempts of creating and recycling
+    /// connections of this pool by reason.
+    fn failures(&self) -> Failures;
+
+    /// Returns the [`Config::fingerprint()`] of this pool, e.g. for a label
+    /// of its metrics.
+    ///
+    /// ```rust,ignore
+    /// gauge!("surrealdb_pool_info", "fingerprint" => pool.fingerprint()).set(1.0);
+    /// ```
+    ///
+    /// [`Config::fingerprint()`]: crate::Config::fingerprint
+    fn fingerprint(&self) -> String;
+
+    /// Returns the current [`HealthState`] of this pool.
+    ///
+    /// See [`HealthThresholds`] for how it is derived.
+    ///
+    /// [`HealthThresholds`]: crate::health::HealthThresholds
+    fn health(&self) -> HealthState;
+
+    /// Warms up connections to the server of `config` in the background for
+    /// switching to it using [`PoolExt::commit_switch()`], e.g. before
+    /// moving the database to another server.
+    ///
+    /// As many connections as the pool currently holds are created using
+    /// the host, credentials, namespace and database of `config`. All other
+    /// settings of the pool stay as they are. Preparing another switch
+    /// replaces the prepared one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoRuntimeSpecified`] if neither an [`Executor`] nor
+    /// a [`Runtime`] was configured for the [`Manager`] of the pool.
+    ///
+    /// [`Error::NoRuntimeSpecified`]: crate::Error::NoRuntimeSpecified
+    /// [`Executor`]: crate::rt::Executor
+    /// [`Runtime`]: crate::Runtime
+    /// [`Manager`]: crate::Manager
+    fn prepare_switch(&self, config: Config) -> Result<()>;
+
+    /// Directs all new checkouts at the server prepared using
+    /// [`PoolExt::prepare_switch()`].
+    ///
+    /// The connections warmed up so far are handed out first. Idle
+    /// connections to the former server are closed right away, checked out
+    /// ones once they are returned. Returns `false` if no switch was
+    /// prepared.
+    fn commit_switch(&self) -> bool;
+
+    /// Directs the creation of new connections at `host` right away, e.g.
+    /// after promoting a replica.
+    ///
+    /// Unlike [`PoolExt::prepare_switch()`] no connections are warmed up and
+    /// all other settings of the current endpoint are kept. A prepared
+    /// switch is dropped. Idle connections to the former host are closed
+    /// right away, checked out ones once they are returned. The function set
+    /// using [`Manager::on_endpoint_switch()`] is called afterwards.
+    ///
+    /// [`Manager::on_endpoint_switch()`]: crate::Manager::on_endpoint_switch
+    fn set_endpoint(&self, host: impl Into<String>);
+
+    /// Stops creating connections to `endpoint`, e.g. one of the targets of
+    /// an SRV record, so its server can be taken down for maintenance.
+    ///
+    /// Idle connections to it are closed right away, checked out ones once
+    /// they are returned. New connections are made to the other endpoints.
+    /// Checkouts fail if all endpoints are drained.
+    fn drain_endpoint(&self, endpoint: &str);
+
+    /// Allows creating connections to an endpoint drained using
+    /// [`PoolExt::drain_endpoint()`] again.
+    fn undrain_endpoint(&self, endpoint: &str);
+
+    /// Returns the endpoints drained using [`PoolExt::drain_endpoint()`].
+    fn drained_endpoints(&self) -> Vec<String>;
+
+    /// Closes this pool and stops its background tasks, e.g. the
+    /// [`Autoscaler`] or the [`ServerStats`] collector, right away.
+    ///
+    /// Unlike [`Pool::close()`], which background tasks only notice once
+    /// they wake up next, this doesn't leave tasks sleeping until their next
+    /// tick. Tasks are stopped at their next await point, so a running
+    /// backup or replay is abandoned. The analytics pool is shut down as
+    /// well.
+    ///
+    /// [`Autoscaler`]: crate::autoscale::Autoscaler
+    /// [`ServerStats`]: crate::stats::ServerStats
+    /// [`Pool::close()`]: deadpool::managed::Pool::close
+    fn shutdown(&self);
+
+    /// Closes all idle connections right away, e.g. after a failover of the
+    /// server or before scaling it down.
+    ///
+    /// Checked out connections are not affected. Returns the number of
+    /// closed connections.
+    fn close_idle(&self) -> usize;
+
+    /// Returns the version of the server.
+    ///
+    /// The server is asked once and the version is cached by the pool. It
+    /// is refreshed every ten minutes and after [`PoolExt::commit_switch()`],
+    /// so upgrades of the server are noticed.
+    fn server_version(&self) -> impl Future<Output = Result<String>> + Send;
+
+    /// Returns a [`DebugDump`] of the configuration, status, idle
+    /// connections, recent errors and recent events of this pool.
+    fn debug_dump(&self) -> DebugDump;
+
+    /// Returns the most recent events of this pool, oldest first, e.g.
+    /// created and evicted connections or failovers.
+    ///
+    /// Up to [`EVENT_LOG_CAPACITY`] events are kept, so transient incidents
+    /// can be investigated after the fact.
+    ///
+    /// [`EVENT_LOG_CAPACITY`]: crate::diagnostics::EVENT_LOG_CAPACITY
+    fn recent_events(&self) -> Vec<RecordedEvent>;
+
+    /// Prepares the given `query` for execution on a connection checked out
+    /// once it is awaited.
+    ///
+    /// See [`PoolQuery`] for retries on broken connections.
+    fn query(&self, query: impl Into<String>) -> PoolQuery<'_>;
+
+    /// Returns the secondary pool for long running queries configured using
+    /// [`Config::analytics`], or this pool if there is none.
+    ///
+    /// [`Config::analytics`]: crate::Config::analytics
+    fn analytics(&self) -> &Pool;
+
+    /// Runs the given `SELECT` query and returns a [`RowStream`] of its
+    /// deserialized rows.
+    ///
+    /// The rows are fetched in pages of `page_size` rows, so the query must
+    /// not contain `LIMIT` or `START` clauses itself and should contain an
+    /// `ORDER BY` clause for a stable order.
+    fn query_stream<T>(&self, query: impl Into<String>, page_size: usize) -> RowStream<T>
+    where
+        T: DeserializeOwned + Send + 'static;
+
+    /// Exports the database as SurrealQL into `writer`.
+    ///
+    /// The export runs on a dedicated connection which is created using the
+    /// configured credentials but doesn't occupy a slot of the pool.
+    fn export<W>(&self, writer: W) -> impl Future<Output = Result<ExportProgress>> + Send
+    where
+        W: AsyncWrite + Send;
+
+    /// Same as [`PoolExt::export()`] but invokes `progress` after every chunk
+    /// written to `writer`.
+    fn export_with_progress<W, F>(
+        &self,
+        writer: W,
+        progress: F,
+    ) -> impl Future<Output = Result<ExportProgress>> + Send
+    where
+        W: AsyncWrite + Send,
+        F: FnMut(&ExportProgress) + Send;
+
+    /// Exports the database as SurrealQL into the file at `path`.
+    ///
+    /// See [`PoolExt::export()`] for details.
+    fn export_to_path(&self, path: impl Into<PathBuf>) -> impl Future<Output = Result<()>> + Send;
+
+    /// Imports the SurrealQL script read from `reader`.
+    ///
+    /// The script is split into single statements which are sent to the
+    /// server in chunks as configured by `options`. Just like
+    /// [`PoolExt::export()`] this uses a dedicated connection which doesn't
+    /// occupy a slot of the pool. The import stops at the first failing
+    /// chunk.
+    fn import<R>(
+        &self,
+        reader: R,
+        options: ImportOptions,
+    ) -> impl Future<Output = Result<ImportSummary>> + Send
+    where
+        R: AsyncRead + Send;
+
+    /// Applies all pending migrations of the given [`Migrator`].
+    ///
+    /// Use the methods of [`Migrator`] for reverting migrations.
+    fn migrate(&self, migrator: &Migrator) -> impl Future<Output = Result<MigrationReport>> + Send;
+
+    /// Defines the configured namespace and database if they don't exist.
+    ///
+    /// This requires root credentials, or namespace credentials if the
+    /// namespace exists already. Set [`Config::create_if_missing`] for
+    /// doing this whenever a new connection is created instead.
+    ///
+    /// [`Config::create_if_missing`]: crate::Config::create_if_missing
+    fn ensure_ns_db(&self) -> impl Future<Output = Result<()>> + Send;
+
+    /// Loads all fixtures of the given [`Seeder`].
+    fn seed(&self, seeder: &Seeder) -> impl Future<Output = Result<()>> + Send;
+
+    /// Runs the given SurrealQL [`Script`] statement by statement on a
+    /// single pooled connection.
+    ///
+    /// See [`Script::run()`] for details.
+    fn run_script(&self, script: &Script) -> impl Future<Output = Result<ScriptReport>> + Send;
+
+    /// Retrieves a connection with the session parameter `$tenant_id` set to
+    /// `tenant`.
+    ///
+    /// See [`TenantScope`] for details and for using a different parameter.
+    fn get_for_tenant(
+        &self,
+        tenant: impl Into<String>,
+    ) -> impl Future<Output = Result<TenantObject>> + Send;
+
+    /// Runs `f`, which can spawn queries into the given [`Scope`] to run them
+    /// concurrently on distinct pooled connections.
+    ///
+    /// At most as many queries as the pool holds connections run at once.
+    /// The returned future completes once `f` and all queries it spawned
+    /// completed. The first error of `f` or of any query, including errors of
+    /// single statements, fails the scope and cancels everything still
+    /// running.
+    ///
+    /// ```rust,ignore
+    /// let (users, orders) = pool
+    ///     .scope(|s| async move {
+    ///         let users = s.spawn_query("SELECT * FROM user");
+    ///         let orders = s.spawn_query("SELECT * FROM order");
+    ///         Ok((users.await?.take::<Vec<User>>(0)?, orders.await?.take::<Vec<Order>>(0)?))
+    ///     })
+    ///     .await?;
+    /// ```
+    fn scope<F, Fut, T>(&self, f: F) -> impl Future<Output = Result<T>> + Send
+    where
+        F: FnOnce(Scope) -> Fut + Send,
+        Fut: Future<Output = Result<T>> + Send,
+        T: Send;
+
+    /// Retrieves a connection for operations of the given `class`.
+    ///
+    /// [`Config::reserved_for_writes`] connections are reserved for
+    /// [`OperationClass::Write`] and [`OperationClass::Admin`] checkouts, so
+    /// heavy read traffic can't starve critical write paths. Checkouts for
+    /// [`OperationClass::Read`] wait while all other connections are checked
+    /// out for reads. At least one connection is always left for reads.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Timeout`] if waiting for a read slot and the
+    /// connection takes longer than the wait timeout of the pool.
+    ///
+    /// [`Error::Timeout`]: crate::Error::Timeout
+    fn get_for(&self, class: OperationClass)
+        -> impl Future<Output = Result<PriorityObject>> + Send;
+}
+
+impl PoolExt for Pool {
+    async fn bulk_insert<T>(
+        &self,
+        table: &str,
+        items: Vec<T>,
+        chunk_size: usize,
+        parallelism: usize,
+    ) -> BulkReport
+    where
+        T: Serialize + Send + Sync + 'static,
+    {
+        bulk::insert(self, table, items, chunk_size, parallelism).await
+    }
+
+    async fn bulk_upsert<T>(
+        &self,
+        table: &str,
+        items: Vec<(String, T)>,
+        strategy: UpsertStrategy,
+        chunk_size: usize,
+        parallelism: usize,
+    ) -> UpsertReport
+    where
+        T: Serialize + Send + Sync + 'static,
+    {
+        bulk::upsert(self, table, items, strategy, chunk_size, parallelism).await
+    }
+
+    async fn checkout(&self) -> Result<Object> {
+        checkout::get(self).await
+    }
+
+    fn pressure(&self) -> Pressure {
+        checkout::pressure(self)
+    }
+
+    fn gauges(&self) -> Gauges {
+        Gauges::from(self.status())
+    }
+
+    fn failures(&self) -> Failures {
+        self.manager().failures.snapshot()
+    }
+
+    fn fingerprint(&self) -> String {
+        self.manager().config().fingerprint()
+    }
+
+    fn health(&self) -> HealthState {
+        health::evaluate(self.manager())
+    }
+
+    fn prepare_switch(&self, config: Config) -> Result<()> {
+        switch::prepare(self, config)
+    }
+
+    fn commit_switch(&self) -> bool {
+        switch::commit(self)
+    }
+
+    fn set_endpoint(&self, host: impl Into<String>) {
+        switch::set_endpoint(self, host.into());
+    }
+
+    fn drain_endpoint(&self, endpoint: &str) {
+        drain::drain(self, endpoint);
+    }
+
+    fn undrain_endpoint(&self, endpoint: &str) {
+        drain::undrain(self, endpoint);
+    }
+
+    fn drained_endpoints(&self) -> Vec<String> {
+        self.manager().drained.list()
+    }
+
+    fn shutdown(&self) {
+        let analytics = self.analytics();
+        if !std::ptr::eq(analytics, self) {
+            analytics.shutdown();
+        }
+        self.manager().shutdown.trigger();
+        self.close();
+    }
+
+    fn close_idle(&self) -> usize {
+        self.retain(|_, _| false).removed.len()
+    }
+
+    async fn server_version(&self) -> Result<String> {
+        version::server_version(self).await
+    }
+
+    fn recent_events(&self) -> Vec<RecordedEvent> {
+        self.manager().events.snapshot()
+    }
+
+    fn debug_dump(&self) -> DebugDump {
+        diagnostics::dump(self)
+    }
+
+    fn query(&self, query: impl Into<String>) -> PoolQuery<'_> {
+        PoolQuery::new(self, query.into())
+    }
+
+    fn analytics(&self) -> &Pool {
+        self.manager().analytics.as_ref().unwrap_or(self)
+    }
+
+    fn query_stream<T>(&self, query: impl Into<String>, page_size: usize) -> RowStream<T>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        RowStream::new(self.clone(), query.into(), page_size)
+    }
+
+    async fn export<W>(&self, writer: W) -> Result<ExportProgress>
+    where
+        W: AsyncWrite + Send,
+    {
+        export::export(self, writer, |_| {}).await
+    }
+
+    async fn export_with_progress<W, F>(&self, writer: W, progress: F) -> Result<ExportProgress>
+    where
+        W: AsyncWrite + Send,
+        F: FnMut(&ExportProgress) + Send,
+    {
+        export::export(self, writer, progress).await
+    }
+
+    async fn export_to_path(&self, path: impl Into<PathBuf>) -> Result<()> {
+        export::export_to_path(self, path.into()).await
+    }
+
+    async fn import<R>(&self, reader: R, options: ImportOptions) -> Result<ImportSummary>
+    where
+        R: AsyncRead + Send,
+    {
+        import::import(self, reader, options).await
+    }
+
+    async fn migrate(&self, migrator: &Migrator) -> Result<MigrationReport> {
+        migrator.up(self).await
+    }
+
+    async fn ensure_ns_db(&self) -> Result<()> {
+        let conn = checkout::get(self).await?;
+        self.manager().provision(conn.client()).await
+    }
+
+    async fn seed(&self, seeder: &Seeder) -> Result<()> {
+        seeder.run(self).await
+    }
+
+    async fn run_script(&self, script: &Script) -> Result<ScriptReport> {
+        script.run(self).await
+    }
+
+    async fn get_for_tenant(&self, tenant: impl Into<String>) -> Result<TenantObject> {
+        let tenant = tenant.into();
+        TenantScope::new(self).get(tenant).await
+    }
+
+    async fn scope<F, Fut, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(Scope) -> Fut + Send,
+        Fut: Future<Output = Result<T>> + Send,
+        T: Send,
+    {
+        scope::run(self, f).await
+    }
+
+    async fn get_for(&self, class: OperationClass) -> Result<PriorityObject> {
+        priority::get(self, class).await
+    }
+}
@@ -0,0 +1,132 @@
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+use deadpool::managed::Status;
+use futures::future::{BoxFuture, FutureExt};
+use tokio::sync::OwnedSemaphorePermit;
+
+use crate::{
+    checkout,
+    tenant::{TenantObject, TenantPool},
+    Object, Pool, Result,
+};
+
+/// Object-safe facade of a pool, so application code can accept
+/// `Arc<dyn SurrealPool>` instead of a concrete [`Pool`].
+///
+/// It is implemented by:
+///
+/// - [`Pool`], including the pools created by the `MockManager` of the
+///   `test-utils` feature and the analytics pool returned by
+///   [`PoolExt::analytics()`],
+/// - [`TenantPool`], handing out connections scoped to a single tenant.
+///
+/// Implement it for wrappers of your own, e.g. pools routing to replicas, to
+/// swap them in.
+///
+/// ```rust,ignore
+/// struct Service {
+///     db: Arc<dyn SurrealPool>,
+/// }
+///
+/// let service = Service { db: Arc::new(pool) };
+/// service.db.ping().await?;
+/// ```
+///
+/// [`PoolExt::analytics()`]: crate::PoolExt::analytics
+pub trait SurrealPool: Send + Sync {
+    /// Retrieves a connection, see [`PoolExt::checkout()`].
+    ///
+    /// [`PoolExt::checkout()`]: crate::PoolExt::checkout
+    fn get(&self) -> BoxFuture<'_, Result<PoolObject>>;
+
+    /// Checks whether the server is reachable using a pooled connection.
+    fn ping(&self) -> BoxFuture<'_, Result<()>>;
+
+    /// Returns the current status of the underlying [`Pool`].
+    fn status(&self) -> Status;
+}
+
+/// Connection retrieved using [`SurrealPool::get()`].
+///
+/// It dereferences to the pooled [`Object`] and releases everything the
+/// implementation holds for the checkout once dropped, e.g. the slot of a
+/// tenant's budget.
+pub struct PoolObject {
+    conn: Object,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl PoolObject {
+    pub(crate) fn new(conn: Object, permit: Option<OwnedSemaphorePermit>) -> Self {
+        Self {
+            conn,
+            _permit: permit,
+        }
+    }
+}
+
+impl From<Object> for PoolObject {
+    fn from(conn: Object) -> Self {
+        Self::new(conn, None)
+    }
+}
+
+impl Deref for PoolObject {
+    type Target = Object;
+
+    fn deref(&self) -> &Object {
+        &self.conn
+    }
+}
+
+impl DerefMut for PoolObject {
+    fn deref_mut(&mut self) -> &mut Object {
+        &mut self.conn
+    }
+}
+
+impl fmt::Debug for PoolObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoolObject")
+            .field("conn", &self.conn)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SurrealPool for Pool {
+    fn get(&self) -> BoxFuture<'_, Result<PoolObject>> {
+        async move { Ok(checkout::get(self).await?.into()) }.boxed()
+    }
+
+    fn ping(&self) -> BoxFuture<'_, Result<()>> {
+        async move { checkout::get(self).await?.ping().await }.boxed()
+    }
+
+    fn status(&self) -> Status {
+        Pool::status(self)
+    }
+}
+
+impl SurrealPool for TenantPool {
+    fn get(&self) -> BoxFuture<'_, Result<PoolObject>> {
+        async move { Ok(TenantPool::get(self).await?.into()) }.boxed()
+    }
+
+    fn ping(&self) -> BoxFuture<'_, Result<()>> {
+        async move { TenantPool::get(self).await?.ping().await }.boxed()
+    }
+
+    fn status(&self) -> Status {
+        self.pool().status()
+    }
+}
+
+impl From<TenantObject> for PoolObject {
+    fn from(object: TenantObject) -> Self {
+        let (conn, permit) = object.into_parts();
+        Self::new(conn, permit)
+    }
+}
@@ -0,0 +1,445 @@
+use std::{
+    fmt, io,
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use ciborium::Value;
+use futures::{SinkExt, StreamExt};
+use serde::Serialize;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::watch,
+    task::JoinHandle,
+};
+use tokio_tungstenite::tungstenite::{
+    handshake::server::{ErrorResponse, Request, Response},
+    http::header::SEC_WEBSOCKET_PROTOCOL,
+    Message,
+};
+
+use crate::{Config, Credentials};
+
+/// Version reported to clients, which check it when connecting.
+const VERSION: &str = "surrealdb-2.1.4";
+
+/// Prefix of the tokens returned when signing in.
+const TOKEN_PREFIX: &str = "fake-token-";
+
+#[derive(Clone)]
+enum FakeResponse {
+    Value(Value),
+    Error(String),
+}
+
+struct State {
+    users: Vec<(String, String)>,
+    responses: Vec<(String, FakeResponse)>,
+    latency: Duration,
+    reject_auth: bool,
+    refuse_connections: bool,
+    session_epoch: u64,
+    connections: usize,
+    requests: Vec<String>,
+    queries: Vec<String>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            users: vec![("root".to_string(), "root".to_string())],
+            responses: Vec::new(),
+            latency: Duration::ZERO,
+            reject_auth: false,
+            refuse_connections: false,
+            session_epoch: 0,
+            connections: 0,
+            requests: Vec::new(),
+            queries: Vec::new(),
+        }
+    }
+}
+
+/// State of a single websocket connection.
+#[derive(Debug, Default)]
+struct Session {
+    /// Epoch of the sessions the connection signed in during
+    signed_in: Option<u64>,
+}
+
+/// In-process server speaking a minimal subset of the SurrealDB websocket
+/// RPC protocol, for testing reconnects, authentication failures and
+/// timeouts without running SurrealDB.
+///
+/// The methods `ping`, `version`, `signin`, `authenticate`, `invalidate`,
+/// `use`, `let`, `unset` and `query` are supported. Only root users are
+/// known, `root`/`root` by default. Queries return an empty result unless a
+/// response has been scripted using [`FakeServer::respond()`]. Every query
+/// returns a single result, so scripts with several statements are not
+/// supported.
+///
+/// The server listens on `127.0.0.1` and stops when dropped, closing all
+/// connections.
+///
+/// ```rust,ignore
+/// let server = FakeServer::start().await?;
+/// server.respond("SELECT * FROM user", vec![User { name: "alice".into() }]);
+/// let pool = server.config("test", "test").create_pool(Some(Runtime::Tokio1))?;
+///
+/// let users: Vec<User> = pool.get().await?.query("SELECT * FROM user").await?.take(0)?;
+/// server.disconnect_all();
+/// ```
+pub struct FakeServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    disconnect: watch::Sender<u64>,
+    accept: JoinHandle<()>,
+}
+
+impl FakeServer {
+    /// Starts a new [`FakeServer`] on a free port.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if binding the port fails.
+    pub async fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State::default()));
+        let (disconnect, receiver) = watch::channel(0);
+        let accept = tokio::spawn({
+            let state = state.clone();
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    {
+                        let mut state = lock(&state);
+                        // Dropping the stream resets the connection.
+                        if state.refuse_connections {
+                            continue;
+                        }
+                        state.connections += 1;
+                    }
+                    let _ = tokio::spawn(serve(stream, state.clone(), receiver.clone()));
+                }
+            }
+        });
+        Ok(Self {
+            addr,
+            state,
+            disconnect,
+            accept,
+        })
+    }
+
+    /// Returns the address the server listens on.
+    #[must_use]
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns the URL of the server, e.g. `ws://127.0.0.1:41234`.
+    #[must_use]
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    /// Returns a [`Config`] for connecting to the server as `root`/`root`
+    /// using the namespace `ns` and the database `db`.
+    #[must_use]
+    pub fn config(&self, ns: impl Into<String>, db: impl Into<String>) -> Config {
+        Config::new(self.url(), ns, db, Credentials::root("root", "root"))
+    }
+
+    /// Adds a root user which may sign in.
+    pub fn user(&self, user: impl Into<String>, pass: impl Into<String>) {
+        self.state().users.push((user.into(), pass.into()));
+    }
+
+    /// Makes the query `sql` return `value` as its result.
+    ///
+    /// Queries are matched by their text with whitespace normalized.
+    /// Responses scripted later take precedence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` can't be serialized.
+    pub fn respond(&self, sql: impl Into<String>, value: impl Serialize) {
+        let value = Value::serialized(&value).expect("Failed to serialize the response");
+        self.state()
+            .responses
+            .push((normalize(&sql.into()), FakeResponse::Value(value)));
+    }
+
+    /// Makes the query `sql` fail with the given error `message`.
+    pub fn respond_error(&self, sql: impl Into<String>, message: impl Into<String>) {
+        let response = FakeResponse::Error(message.into());
+        self.state()
+            .responses
+            .push((normalize(&sql.into()), response));
+    }
+
+    /// Delays every response by `latency`, e.g. for testing timeouts.
+    pub fn latency(&self, latency: Duration) {
+        self.state().latency = latency;
+    }
+
+    /// Makes signing in and authenticating using a token fail.
+    pub fn reject_auth(&self, reject: bool) {
+        self.state().reject_auth = reject;
+    }
+
+    /// Makes the server reset new connections right after accepting them,
+    /// as if it was down.
+    pub fn refuse_connections(&self, refuse: bool) {
+        self.state().refuse_connections = refuse;
+    }
+
+    /// Closes all open connections.
+    pub fn disconnect_all(&self) {
+        self.disconnect.send_modify(|generation| *generation += 1);
+    }
+
+    /// Expires the sessions of all connections signed in so far. Their
+    /// queries fail until they sign in again.
+    pub fn expire_sessions(&self) {
+        self.state().session_epoch += 1;
+    }
+
+    /// Returns the number of connections accepted so far.
+    #[must_use]
+    pub fn connections(&self) -> usize {
+        self.state().connections
+    }
+
+    /// Returns the methods of all requests received so far.
+    #[must_use]
+    pub fn requests(&self) -> Vec<String> {
+        self.state().requests.clone()
+    }
+
+    /// Returns the text of all queries received so far.
+    #[must_use]
+    pub fn queries(&self) -> Vec<String> {
+        self.state().queries.clone()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        lock(&self.state)
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        // Dropping `disconnect` closes the open connections.
+        self.accept.abort();
+    }
+}
+
+impl fmt::Debug for FakeServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state();
+        f.debug_struct("FakeServer")
+            .field("addr", &self.addr)
+            .field("responses", &state.responses.len())
+            .field("latency", &state.latency)
+            .field("reject_auth", &state.reject_auth)
+            .field("refuse_connections", &state.refuse_connections)
+            .field("connections", &state.connections)
+            .finish_non_exhaustive()
+    }
+}
+
+async fn serve(stream: TcpStream, state: Arc<Mutex<State>>, mut disconnect: watch::Receiver<u64>) {
+    // Only disconnects requested after accepting the connection apply.
+    let _ = disconnect.borrow_and_update();
+    let Ok(mut ws) = tokio_tungstenite::accept_hdr_async(stream, echo_protocol).await else {
+        return;
+    };
+    let mut session = Session::default();
+    loop {
+        let message = tokio::select! {
+            message = ws.next() => message,
+            _ = disconnect.changed() => break,
+        };
+        let data = match message {
+            Some(Ok(Message::Binary(data))) => data,
+            // Pings are answered by tungstenite.
+            Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+            Some(Ok(_)) => continue,
+        };
+        let Ok(request) = ciborium::from_reader::<Value, _>(data.as_slice()) else {
+            break;
+        };
+        let (response, latency) = {
+            let mut state = lock(&state);
+            (handle(&mut state, &mut session, &request), state.latency)
+        };
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+        let mut buf = Vec::new();
+        if ciborium::into_writer(&response, &mut buf).is_err()
+            || ws.send(Message::Binary(buf)).await.is_err()
+        {
+            break;
+        }
+    }
+    let _ = ws.close(None).await;
+}
+
+/// Accepts the subprotocol requested by the client, which is the format of
+/// the messages.
+fn echo_protocol(request: &Request, mut response: Response) -> Result<Response, ErrorResponse> {
+    if let Some(protocol) = request.headers().get(SEC_WEBSOCKET_PROTOCOL) {
+        let _ = response
+            .headers_mut()
+            .insert(SEC_WEBSOCKET_PROTOCOL, protocol.clone());
+    }
+    Ok(response)
+}
+
+fn handle(state: &mut State, session: &mut Session, request: &Value) -> Value {
+    let id = field(request, "id").cloned().unwrap_or(Value::Null);
+    let method = field(request, "method").and_then(text).unwrap_or_default();
+    let params = match field(request, "params").map(untag) {
+        Some(Value::Array(params)) => params.as_slice(),
+        _ => &[],
+    };
+    state.requests.push(method.to_string());
+
+    let result = match method {
+        "ping" | "let" | "unset" | "use" => Ok(Value::Null),
+        "version" => Ok(Value::Text(VERSION.to_string())),
+        "signin" => {
+            let creds = params.first();
+            let user = creds.and_then(|c| field(c, "user")).and_then(text);
+            let pass = creds.and_then(|c| field(c, "pass")).and_then(text);
+            let known = state
+                .users
+                .iter()
+                .any(|(u, p)| Some(u.as_str()) == user && Some(p.as_str()) == pass);
+            if known && !state.reject_auth {
+                session.signed_in = Some(state.session_epoch);
+                Ok(Value::Text(format!(
+                    "{}{}",
+                    TOKEN_PREFIX, state.session_epoch
+                )))
+            } else {
+                Err(auth_failed())
+            }
+        }
+        "authenticate" => {
+            let epoch = params
+                .first()
+                .and_then(text)
+                .and_then(|token| token.strip_prefix(TOKEN_PREFIX))
+                .and_then(|epoch| epoch.parse().ok());
+            match epoch {
+                Some(epoch) if !state.reject_auth => {
+                    session.signed_in = Some(epoch);
+                    Ok(Value::Null)
+                }
+                _ => Err(auth_failed()),
+            }
+        }
+        "invalidate" => {
+            session.signed_in = None;
+            Ok(Value::Null)
+        }
+        "query" => {
+            let sql = params.first().and_then(text).unwrap_or_default();
+            state.queries.push(sql.to_string());
+            match session.signed_in {
+                Some(epoch) if epoch == state.session_epoch => {
+                    let sql = normalize(sql);
+                    let response = state
+                        .responses
+                        .iter()
+                        .rev()
+                        .find(|(s, _)| *s == sql)
+                        .map(|(_, response)| response.clone());
+                    Ok(Value::Array(vec![match response {
+                        Some(FakeResponse::Value(value)) => query_result("OK", value),
+                        Some(FakeResponse::Error(message)) => {
+                            query_result("ERR", Value::Text(message))
+                        }
+                        None => query_result("OK", Value::Array(Vec::new())),
+                    }]))
+                }
+                Some(_) => Err((-32000, "The session has expired".to_string())),
+                None => Err((-32000, "Not enough permissions".to_string())),
+            }
+        }
+        _ => Err((-32601, "Method not found".to_string())),
+    };
+
+    let (key, value) = match result {
+        Ok(result) => ("result", result),
+        Err((code, message)) => (
+            "error",
+            map(vec![
+                ("code", Value::Integer(code.into())),
+                ("message", Value::Text(message)),
+            ]),
+        ),
+    };
+    map(vec![("id", id), (key, value)])
+}
+
+fn auth_failed() -> (i64, String) {
+    (
+        -32000,
+        "There was a problem with authentication".to_string(),
+    )
+}
+
+fn query_result(status: &str, result: Value) -> Value {
+    map(vec![
+        ("status", Value::Text(status.to_string())),
+        ("time", Value::Text("0ns".to_string())),
+        ("result", result),
+    ])
+}
+
+fn map(entries: Vec<(&str, Value)>) -> Value {
+    Value::Map(
+        entries
+            .into_iter()
+            .map(|(key, value)| (Value::Text(key.to_string()), value))
+            .collect(),
+    )
+}
+
+/// Strips the tags SurrealDB uses for its own types, e.g. `NONE`.
+fn untag(value: &Value) -> &Value {
+    match value {
+        Value::Tag(_, value) => untag(value),
+        value => value,
+    }
+}
+
+fn field<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    match untag(value) {
+        Value::Map(entries) => entries
+            .iter()
+            .find(|(k, _)| text(k) == Some(key))
+            .map(|(_, v)| v),
+        _ => None,
+    }
+}
+
+fn text(value: &Value) -> Option<&str> {
+    match untag(value) {
+        Value::Text(text) => Some(text),
+        _ => None,
+    }
+}
+
+fn normalize(sql: &str) -> String {
+    sql.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,81 @@
+use std::{collections::HashMap, fmt, marker::PhantomData, sync::Arc};
+
+use async_graphql::dataloader::Loader;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{checkout, Error, Pool};
+
+/// Fetches the records by their ids, adding the id as `__key`.
+const LOAD: &str = "SELECT *, record::id(id) AS __key \
+    FROM (SELECT VALUE type::thing($table, $this) FROM $keys)";
+
+#[derive(Deserialize)]
+struct Row<T> {
+    __key: String,
+    #[serde(flatten)]
+    value: T,
+}
+
+/// [`Loader`] fetching records of a single table by their ids.
+///
+/// All keys requested by the resolvers of a query are batched into a single
+/// query using a single pooled connection:
+///
+/// ```rust,ignore
+/// let loader = DataLoader::new(SurrealLoader::<User>::new(&pool, "user"), tokio::spawn);
+/// let user = loader.load_one("tobie".to_string()).await?;
+/// ```
+///
+/// Keys are the ids of the records without the table name. Ids which don't
+/// exist are missing from the result.
+pub struct SurrealLoader<T> {
+    pool: Pool,
+    table: String,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T> SurrealLoader<T> {
+    /// Creates a new [`SurrealLoader`] for records of `table`.
+    pub fn new(pool: &Pool, table: impl Into<String>) -> Self {
+        Self {
+            pool: pool.clone(),
+            table: table.into(),
+            _value: PhantomData,
+        }
+    }
+}
+
+impl<T> Loader<String> for SurrealLoader<T>
+where
+    T: DeserializeOwned + Send + Sync + Clone + 'static,
+{
+    type Value = T;
+    type Error = Arc<Error>;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, T>, Arc<Error>> {
+        let result: Result<Vec<Row<T>>, Error> = async {
+            let conn = checkout::get(&self.pool).await?;
+            Ok(conn
+                .query(LOAD)
+                .bind(("table", self.table.clone()))
+                .bind(("keys", keys.to_vec()))
+                .await?
+                .take(0)?)
+        }
+        .await;
+        Ok(result
+            .map_err(Arc::new)?
+            .into_iter()
+            .map(|row| (row.__key, row.value))
+            .collect())
+    }
+}
+
+impl<T> fmt::Debug for SurrealLoader<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SurrealLoader")
+            .field("pool", &self.pool)
+            .field("table", &self.table)
+            .finish()
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use serde::Serialize;
+
+use crate::{diagnostics::PoolEvent, hook, Manager};
+
+/// Health of a pool, see [`PoolExt::health()`].
+///
+/// [`PoolExt::health()`]: crate::PoolExt::health
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    /// Connections are created and recycled reliably and quickly
+    #[default]
+    Healthy,
+    /// Some attempts of creating or recycling connections fail or
+    /// checkouts wait noticeably
+    Degraded,
+    /// Most attempts of creating or recycling connections fail or checkouts
+    /// wait for very long
+    Unhealthy,
+}
+
+/// Thresholds of the [`HealthState`] of a pool, set using
+/// [`Manager::health_thresholds()`].
+///
+/// The failure rate is the share of failed attempts of creating and
+/// recycling connections among the most recent [`window`] attempts. Wait
+/// times are the [`Pressure::wait_p95`] of the pool.
+///
+/// Reaching the failure rate or the wait time of a threshold enters its
+/// state right away. Leaving the state again requires both values to fall
+/// below the [`recovery`] share of the threshold, so the state doesn't flap
+/// around the thresholds.
+///
+/// [`Manager::health_thresholds()`]: crate::Manager::health_thresholds
+/// [`Pressure::wait_p95`]: crate::checkout::Pressure::wait_p95
+/// [`window`]: HealthThresholds::window
+/// [`recovery`]: HealthThresholds::recovery
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HealthThresholds {
+    /// Number of the most recent attempts the failure rate is computed from
+    pub window: usize,
+    /// Failure rate from which the pool is [`HealthState::Degraded`]
+    pub degraded_failure_rate: f64,
+    /// Failure rate from which the pool is [`HealthState::Unhealthy`]
+    pub unhealthy_failure_rate: f64,
+    /// Wait time from which the pool is [`HealthState::Degraded`]
+    pub degraded_wait: Duration,
+    /// Wait time from which the pool is [`HealthState::Unhealthy`]
+    pub unhealthy_wait: Duration,
+    /// Share of a threshold both values have to fall below for leaving its
+    /// state again
+    pub recovery: f64,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            window: 32,
+            degraded_failure_rate: 0.1,
+            unhealthy_failure_rate: 0.5,
+            degraded_wait: Duration::from_millis(100),
+            unhealthy_wait: Duration::from_secs(1),
+            recovery: 0.5,
+        }
+    }
+}
+
+impl HealthThresholds {
+    fn state(&self, failure_rate: f64, wait: Duration, current: HealthState) -> HealthState {
+        let exceeds = |state, rate: f64, threshold: Duration| {
+            let share = if current >= state { self.recovery } else { 1.0 };
+            failure_rate >= rate * share || wait >= threshold.mul_f64(share)
+        };
+        if exceeds(
+            HealthState::Unhealthy,
+            self.unhealthy_failure_rate,
+            self.unhealthy_wait,
+        ) {
+            HealthState::Unhealthy
+        } else if exceeds(
+            HealthState::Degraded,
+            self.degraded_failure_rate,
+            self.degraded_wait,
+        ) {
+            HealthState::Degraded
+        } else {
+            HealthState::Healthy
+        }
+    }
+}
+
+/// Change of the [`HealthState`] of a pool, passed to the function set
+/// using [`Manager::on_health_change()`].
+///
+/// [`Manager::on_health_change()`]: crate::Manager::on_health_change
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HealthTransition {
+    /// State before the transition
+    pub from: HealthState,
+    /// State after the transition
+    pub to: HealthState,
+    /// Failure rate causing the transition
+    pub failure_rate: f64,
+    /// Wait time causing the transition
+    pub wait_p95: Duration,
+}
+
+type HookFn = dyn Fn(&HealthTransition) + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct HealthHook(pub(crate) Arc<HookFn>);
+
+impl fmt::Debug for HealthHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("HealthHook").finish_non_exhaustive()
+    }
+}
+
+/// Recent outcomes of creating and recycling connections of a [`Manager`]
+/// and its current [`HealthState`].
+#[derive(Debug, Default)]
+pub(crate) struct Health(Mutex<Tracker>);
+
+impl Health {
+    fn lock(&self) -> MutexGuard<'_, Tracker> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Tracker {
+    state: HealthState,
+    outcomes: VecDeque<bool>,
+}
+
+/// Records whether an attempt of creating or recycling a connection
+/// succeeded.
+pub(crate) fn record(manager: &Manager, success: bool) {
+    {
+        let mut tracker = manager.health.lock();
+        while tracker.outcomes.len() >= manager.health_thresholds.window.max(1) {
+            let _ = tracker.outcomes.pop_front();
+        }
+        tracker.outcomes.push_back(success);
+    }
+    let _ = evaluate(manager);
+}
+
+/// Updates the [`HealthState`] of the `manager`, calling its hook if it
+/// changed.
+pub(crate) fn evaluate(manager: &Manager) -> HealthState {
+    let wait_p95 = manager.waits.p95();
+    let transition = {
+        let mut tracker = manager.health.lock();
+        let failures = tracker.outcomes.iter().filter(|success| !**success).count();
+        let failure_rate = failures as f64 / tracker.outcomes.len().max(1) as f64;
+        let from = tracker.state;
+        tracker.state = manager
+            .health_thresholds
+            .state(failure_rate, wait_p95, from);
+        HealthTransition {
+            from,
+            to: tracker.state,
+            failure_rate,
+            wait_p95,
+        }
+    };
+    if transition.from != transition.to {
+        manager.events.record(PoolEvent::HealthChanged {
+            from: transition.from,
+            to: transition.to,
+        });
+        if let Some(hook) = &manager.health_hook {
+            if let Err(e) = hook::catch("on_health_change", || (hook.0)(&transition)) {
+                manager.errors.record(&e);
+            }
+        }
+    }
+    transition.to
+}
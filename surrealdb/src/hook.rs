@@ -0,0 +1,38 @@
+use std::{
+    any::Any,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+};
+
+use futures::FutureExt;
+
+use crate::{shutdown::TaskPool, Error, Result};
+
+/// Calls the hook `name` provided by the application, returning a panic as
+/// [`Error::Panicked`] instead of unwinding into the pool.
+pub(crate) fn catch<R>(name: &'static str, f: impl FnOnce() -> R) -> Result<R> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| panicked(name, payload))
+}
+
+/// Runs the background task `name` of `pool` until it completes or the pool
+/// is shut down or dropped, recording a panic in the error log of its
+/// manager instead of losing the task silently.
+pub(crate) async fn task(pool: TaskPool, name: &'static str, future: impl Future<Output = ()>) {
+    let future = AssertUnwindSafe(future).catch_unwind();
+    if let Some(Err(payload)) = pool.until(future).await {
+        if let Ok(pool) = pool.get() {
+            pool.manager().errors.record(&panicked(name, payload));
+        }
+    }
+}
+
+fn panicked(name: &'static str, payload: Box<dyn Any + Send>) -> Error {
+    let message = match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "unknown cause".to_string(),
+        },
+    };
+    Error::Panicked { name, message }
+}
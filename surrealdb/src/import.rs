@@ -0,0 +1,149 @@
+use std::{io, pin::pin};
+
+use futures::{io::AsyncRead, AsyncReadExt};
+
+use crate::{surql, ClientWrapper, Pool, Result};
+
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Options for [`PoolExt::import()`].
+///
+/// [`PoolExt::import()`]: crate::PoolExt::import
+#[derive(Clone, Copy, Debug)]
+pub struct ImportOptions {
+    /// Number of statements sent to the server in a single query
+    pub statements_per_chunk: usize,
+    /// Wrap every chunk in a transaction
+    ///
+    /// Chunks containing transaction statements of their own (as created by
+    /// SurrealDB exports) are never wrapped. Chunks are never split inside
+    /// of such a transaction.
+    pub transactional: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            statements_per_chunk: 100,
+            transactional: false,
+        }
+    }
+}
+
+/// Summary of a successful [`PoolExt::import()`] run.
+///
+/// [`PoolExt::import()`]: crate::PoolExt::import
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Number of statements executed
+    pub statements: usize,
+    /// Number of queries sent to the server
+    pub chunks: usize,
+}
+
+struct Chunker<'a> {
+    conn: &'a ClientWrapper,
+    options: ImportOptions,
+    statements: Vec<String>,
+    in_transaction: bool,
+    summary: ImportSummary,
+}
+
+impl Chunker<'_> {
+    async fn push(&mut self, statement: String) -> Result<()> {
+        match surql::keyword(&statement).as_str() {
+            "BEGIN" => self.in_transaction = true,
+            "COMMIT" | "CANCEL" => self.in_transaction = false,
+            _ => {}
+        }
+        self.statements.push(statement);
+        if !self.in_transaction && self.statements.len() >= self.options.statements_per_chunk {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        if self.statements.is_empty() {
+            return Ok(());
+        }
+        let wrap = self.options.transactional
+            && !self
+                .statements
+                .iter()
+                .any(|s| matches!(surql::keyword(s).as_str(), "BEGIN" | "COMMIT" | "CANCEL"));
+        let mut sql = String::new();
+        if wrap {
+            sql.push_str("BEGIN TRANSACTION;\n");
+        }
+        for statement in &self.statements {
+            sql.push_str(statement);
+            sql.push_str(";\n");
+        }
+        if wrap {
+            sql.push_str("COMMIT TRANSACTION;\n");
+        }
+        let _ = self.conn.query(sql).await?.check()?;
+        self.summary.statements += self.statements.len();
+        self.summary.chunks += 1;
+        self.statements.clear();
+        Ok(())
+    }
+}
+
+/// Imports the SurrealQL script read from `reader` using a dedicated
+/// connection.
+pub(crate) async fn import<R>(
+    pool: &Pool,
+    reader: R,
+    options: ImportOptions,
+) -> Result<ImportSummary>
+where
+    R: AsyncRead + Send,
+{
+    let conn = pool.manager().connect_dedicated().await?;
+    let mut chunker = Chunker {
+        conn: &conn,
+        options: ImportOptions {
+            statements_per_chunk: options.statements_per_chunk.max(1),
+            ..options
+        },
+        statements: Vec::new(),
+        in_transaction: false,
+        summary: ImportSummary::default(),
+    };
+    let mut splitter = surql::Splitter::default();
+    let mut reader = pin!(reader);
+    let mut buf = vec![0; READ_BUFFER_SIZE];
+    let mut bytes = Vec::new();
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        bytes.extend_from_slice(&buf[..n]);
+        // Multi-byte characters may be split between two reads.
+        let valid = match std::str::from_utf8(&bytes) {
+            Ok(text) => text.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e).into()),
+        };
+        let statements = splitter.push(std::str::from_utf8(&bytes[..valid]).unwrap_or_default());
+        let _ = bytes.drain(..valid);
+        for statement in statements {
+            chunker.push(statement).await?;
+        }
+    }
+    if !bytes.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "stream did not contain valid UTF-8",
+        )
+        .into());
+    }
+    if let Some(statement) = splitter.finish() {
+        chunker.push(statement).await?;
+    }
+    chunker.flush().await?;
+    Ok(chunker.summary)
+}
@@ -0,0 +1,277 @@
+use std::{
+    sync::{atomic::Ordering, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use deadpool::managed::Timeouts;
+use futures::future;
+use serde::Serialize;
+
+use crate::{Error, Pool, Result};
+
+/// Outcome of a probe.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProbeResult {
+    /// Whether the probe passes, taking the thresholds into account
+    pub ok: bool,
+    /// Number of consecutive failed checks
+    pub consecutive_failures: u32,
+    /// Error of the last check if it failed
+    pub error: Option<String>,
+}
+
+#[derive(Debug)]
+struct ProbeState {
+    ok: bool,
+    failures: u32,
+    successes: u32,
+}
+
+impl ProbeState {
+    fn new(ok: bool) -> Self {
+        Self {
+            ok,
+            failures: 0,
+            successes: 0,
+        }
+    }
+}
+
+/// Readiness and liveness probes for Kubernetes.
+///
+/// Both probes only change their outcome after a number of consecutive
+/// checks with the opposite result, so they don't flap during brief
+/// hiccups of the database:
+///
+/// - The readiness probe checks out a connection and pings the server
+///   within the configured timeout. It starts out failing.
+/// - The liveness probe fails if the pool is closed or if requests are
+///   waiting for connections while no connection was handed out since the
+///   previous liveness check, i.e. checkouts make no progress. It starts
+///   out passing. A closed pool fails the probe right away. A saturated pool
+///   whose connections keep being handed out passes, as restarting it
+///   wouldn't help; it fails the readiness probe instead once checkouts
+///   time out.
+///
+/// ```rust,ignore
+/// let probes = Arc::new(Probes::new(&pool).failure_threshold(3));
+/// let app = Router::new()
+///     .route("/readyz", get(|| async move { status(probes.readiness().await.ok) }));
+/// ```
+#[derive(Debug)]
+pub struct Probes {
+    pool: Pool,
+    timeout: Duration,
+    failure_threshold: u32,
+    success_threshold: u32,
+    readiness: Mutex<ProbeState>,
+    liveness: Mutex<ProbeState>,
+    /// Connections handed out by the pool as of the previous liveness check
+    checkouts: Mutex<Option<u64>>,
+}
+
+impl Probes {
+    /// Creates new [`Probes`] for the given `pool` with a timeout of 500ms,
+    /// a failure threshold of 3 and a success threshold of 1.
+    pub fn new(pool: &Pool) -> Self {
+        Self {
+            pool: pool.clone(),
+            timeout: Duration::from_millis(500),
+            failure_threshold: 3,
+            success_threshold: 1,
+            readiness: Mutex::new(ProbeState::new(false)),
+            liveness: Mutex::new(ProbeState::new(true)),
+            checkouts: Mutex::new(None),
+        }
+    }
+
+    /// Sets the time the readiness check may take for checking out a
+    /// connection and pinging the server.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the number of consecutive failed checks after which a probe
+    /// fails.
+    #[must_use]
+    pub fn failure_threshold(mut self, threshold: u32) -> Self {
+        self.failure_threshold = threshold.max(1);
+        self
+    }
+
+    /// Sets the number of consecutive successful checks after which a
+    /// failing probe passes again.
+    #[must_use]
+    pub fn success_threshold(mut self, threshold: u32) -> Self {
+        self.success_threshold = threshold.max(1);
+        self
+    }
+
+    /// Evaluates the readiness probe.
+    ///
+    /// Every check fails if no [`Runtime`] was configured for the pool.
+    ///
+    /// [`Runtime`]: crate::Runtime
+    pub async fn readiness(&self) -> ProbeResult {
+        let check = self.check_readiness().await;
+        self.evaluate(&self.readiness, check.map_err(|e| e.to_string()), false)
+    }
+
+    /// Evaluates the liveness probe.
+    ///
+    /// Progress of checkouts is measured between two calls, so call it at
+    /// the interval of the probe only.
+    #[must_use]
+    pub fn liveness(&self) -> ProbeResult {
+        if self.pool.is_closed() {
+            return self.evaluate(&self.liveness, Err("Pool is closed".to_string()), true);
+        }
+        let status = self.pool.status();
+        let checkouts = self.pool.manager().checkouts.load(Ordering::Relaxed);
+        let previous = self
+            .checkouts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .replace(checkouts);
+        let check = if status.waiting > 0 && previous == Some(checkouts) {
+            Err(format!(
+                "{} requests waiting for connections, none handed out since the last check",
+                status.waiting
+            ))
+        } else {
+            Ok(())
+        };
+        self.evaluate(&self.liveness, check, false)
+    }
+
+    async fn check_readiness(&self) -> Result<()> {
+        ping(&self.pool, self.timeout).await
+    }
+
+    fn evaluate(
+        &self,
+        state: &Mutex<ProbeState>,
+        check: std::result::Result<(), String>,
+        immediate: bool,
+    ) -> ProbeResult {
+        let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+        match &check {
+            Ok(()) => {
+                state.failures = 0;
+                state.successes += 1;
+                if state.successes >= self.success_threshold {
+                    state.ok = true;
+                }
+            }
+            Err(_) => {
+                state.successes = 0;
+                state.failures += 1;
+                if immediate || state.failures >= self.failure_threshold {
+                    state.ok = false;
+                }
+            }
+        }
+        ProbeResult {
+            ok: state.ok,
+            consecutive_failures: state.failures,
+            error: check.err(),
+        }
+    }
+}
+
+/// Checks out a connection of `pool` and pings the server within `timeout`.
+async fn ping(pool: &Pool, timeout: Duration) -> Result<()> {
+    let runtime = pool.manager().runtime.ok_or(Error::NoRuntimeSpecified)?;
+    let check = async {
+        let timeouts = Timeouts {
+            wait: Some(timeout),
+            ..pool.timeouts()
+        };
+        let conn = pool.timeout_get(&timeouts).await?;
+        conn.ping().await
+    };
+    runtime
+        .timeout(timeout, check)
+        .await
+        .unwrap_or(Err(Error::QueryTimeout(timeout)))
+}
+
+/// Health of a single pool checked by [`check_all()`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PoolHealth {
+    /// Name the pool was passed to [`check_all()`] with
+    pub name: String,
+    /// Fingerprint of the configuration of the pool, see
+    /// [`Config::fingerprint()`]
+    ///
+    /// [`Config::fingerprint()`]: crate::Config::fingerprint
+    pub fingerprint: String,
+    /// Whether a connection was checked out and the server answered in time
+    pub ok: bool,
+    /// Milliseconds the check took
+    pub elapsed_ms: u64,
+    /// Error of the check if it failed
+    pub error: Option<String>,
+}
+
+/// Health of several pools returned by [`check_all()`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct HealthReport {
+    /// Health of every pool, in the order the pools were passed
+    pub pools: Vec<PoolHealth>,
+}
+
+impl HealthReport {
+    /// Returns whether all pools are healthy.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.pools.iter().all(|pool| pool.ok)
+    }
+
+    /// Returns the pools which are not healthy.
+    pub fn failed(&self) -> impl Iterator<Item = &PoolHealth> {
+        self.pools.iter().filter(|pool| !pool.ok)
+    }
+}
+
+/// Checks all `pools` concurrently for a single readiness endpoint of an
+/// application using several pools, e.g. one per tenant.
+///
+/// Every pool checks out a connection and pings its server like the
+/// readiness probe of [`Probes`], all within the same `deadline`. Pools
+/// without a [`Runtime`] fail their check.
+///
+/// ```rust,ignore
+/// let report = check_all([("main", &main), ("analytics", &analytics)], deadline).await;
+/// let status = if report.is_ok() { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+/// (status, Json(report))
+/// ```
+///
+/// [`Runtime`]: crate::Runtime
+pub async fn check_all<'a, N>(
+    pools: impl IntoIterator<Item = (N, &'a Pool)>,
+    deadline: Duration,
+) -> HealthReport
+where
+    N: Into<String>,
+{
+    let checks = pools.into_iter().map(|(name, pool)| {
+        let name = name.into();
+        async move {
+            let started = Instant::now();
+            let result = ping(pool, deadline).await;
+            PoolHealth {
+                name,
+                fingerprint: pool.manager().config().fingerprint(),
+                ok: result.is_ok(),
+                elapsed_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+                error: result.err().map(|e| e.to_string()),
+            }
+        }
+    });
+    HealthReport {
+        pools: future::join_all(checks).await,
+    }
+}
@@ -15,6 +15,7 @@
 //!         user: String::new(),
 //!         pass: String::new(),
 //!     },
+//!     ..Default::default()
 //! };
 //!
 //! let pool = config.create_pool(Some(deadpool_surrealdb::Runtime::Tokio1)).unwrap();
@@ -62,6 +63,7 @@
 //!         user: String::new(),
 //!         pass: String::new(),
 //!     },
+//!     ..Default::default()
 //! };
 //! ```
 
@@ -88,11 +90,146 @@
 )]
 #![allow(clippy::uninlined_format_args)]
 
+/// Integration with `actix-web`.
+#[cfg(feature = "actix")]
+#[cfg_attr(docsrs, doc(cfg(feature = "actix")))]
+pub mod actix;
+/// Integration with `axum`.
+#[cfg(feature = "axum")]
+#[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+pub mod axum;
+/// Adaptive sizing of pools.
+pub mod autoscale;
+/// Scheduled backups of the database.
+pub mod backup;
+/// Connection limits shared by several pools.
+pub mod budget;
+/// Helpers for inserting large batches of records.
+pub mod bulk;
+/// Caching of query results.
+pub mod cache;
+/// Fault injection for testing retry and backoff behavior.
+#[cfg(feature = "test-utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-utils")))]
+pub mod chaos;
+/// Hooks observing connection checkouts.
+pub mod checkout;
+/// Source of the current time.
+pub mod clock;
+/// Client wrapper handed out by the pool.
+pub mod client;
+/// Coalescing of identical concurrent reads.
+pub mod coalesce;
 /// Configuration types for the SurrealDB connection pool.
 pub mod config;
+/// Diagnostics of pools and connections.
+pub mod diagnostics;
+mod drain;
+mod dual_stack;
+/// Database exports through the pool.
+pub mod export;
+mod ext;
+mod facade;
+/// In-process fake of the SurrealDB websocket RPC for protocol-level tests.
+#[cfg(feature = "fake-server")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fake-server")))]
+pub mod fake_server;
+/// Integration with `async-graphql`.
+#[cfg(feature = "graphql")]
+#[cfg_attr(docsrs, doc(cfg(feature = "graphql")))]
+pub mod graphql;
+/// Health of pools derived from recent failures and wait times.
+pub mod health;
+mod hook;
+/// Database imports through the pool.
+pub mod import;
+/// Kubernetes readiness and liveness probes, also across several pools.
+pub mod k8s;
+/// Versioned schema migrations.
+pub mod migrations;
+/// Buffering of writes while the database is unreachable.
+pub mod offline;
+/// Cursor based pagination of query results.
+pub mod paginate;
+/// Several statements sent as a single query.
+pub mod pipeline;
+/// Prioritization of checkouts by the class of their operations.
+pub mod priority;
+/// Integration with `rocket`.
+#[cfg(feature = "rocket")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rocket")))]
+pub mod rocket;
+/// Typed repositories for records of a single table.
+pub mod repo;
+/// Connections shared by all data-access calls handling a request.
+pub mod request;
+/// Budgets bounding retries of creating connections and of queries.
+pub mod retry;
+/// Executors for background tasks.
+pub mod rt;
+/// Declarative schema management.
+pub mod schema;
+/// Queries running concurrently within a scope.
+pub mod scope;
+/// Running of SurrealQL scripts statement by statement.
+pub mod script;
+/// Loading of fixture data.
+pub mod seed;
+/// Helpers for serverless environments.
+pub mod serverless;
+/// Integration with `tower-sessions`.
+#[cfg(feature = "sessions")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sessions")))]
+pub mod sessions;
+mod shutdown;
+/// Integration with Shuttle.
+#[cfg(feature = "shuttle")]
+#[cfg_attr(docsrs, doc(cfg(feature = "shuttle")))]
+pub mod shuttle;
+#[cfg(feature = "srv")]
+mod srv;
+/// Collection of server statistics.
+pub mod stats;
+/// Streaming of query results.
+pub mod stream;
+mod surql;
+/// Types of the `surrealdb` crate used in signatures of this crate, so
+/// they can be named without depending on a matching version of it.
+pub mod surreal;
+/// Integration with the `surrealdb-migrations` crate.
+#[cfg(feature = "surrealdb-migrations")]
+#[cfg_attr(docsrs, doc(cfg(feature = "surrealdb-migrations")))]
+pub mod surrealdb_migrations;
+/// Switching pools over to another endpoint.
+pub mod switch;
+/// Connections scoped to a tenant.
+pub mod tenant;
+/// Utilities for testing code which uses a pool.
+#[cfg(feature = "test-utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-utils")))]
+pub mod test_utils;
+/// Integration with testcontainers.
+#[cfg(feature = "testcontainers")]
+#[cfg_attr(docsrs, doc(cfg(feature = "testcontainers")))]
+pub mod testcontainers;
+/// Integration with `tower`.
+#[cfg(feature = "tower")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+pub mod tower;
+/// Trace context propagation using query comments.
+pub mod trace;
+mod version;
 
 use deadpool::managed;
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 use surrealdb::{
     engine::any::Any,
     opt::auth,
@@ -100,6 +237,9 @@ use surrealdb::{
 };
 use deadpool::managed::RecycleError;
 
+use crate::diagnostics::{FailureReason, PoolEvent};
+use crate::trace::QueryComments;
+
 deadpool::managed_reexports!(
     "surrealdb",
     Manager,
@@ -107,27 +247,475 @@ deadpool::managed_reexports!(
     Error,
     std::convert::Infallible
 );
-pub use self::{config::Config, config::Credentials};
+pub use self::{
+    client::ClientWrapper, config::AddressFamily, config::AnalyticsConfig, config::Config,
+    config::Credentials, config::EnvError,
+    ext::PoolExt, facade::PoolObject, facade::SurrealPool,
+};
 pub use deadpool_runtime::Runtime;
+/// Validates the syntax of a SurrealQL query at compile time.
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+pub use deadpool_surrealdb_macros::surql;
 
 /// Error type for SurrealDB pool operations
+///
+/// New variants may be added in minor releases. Use [`Error::kind()`] to
+/// handle errors by category.
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum Error {
     /// SurrealDB error
     #[error("SurrealDB error: {0}")]
     Surreal(#[from] surrealdb::Error),
     
     /// Connection error
-    #[error("Connection error: {0}")]
-    Connection(String),
+    #[error("Connection error: {message}{}", in_context(.context))]
+    Connection {
+        /// Description of what failed
+        message: Cow<'static, str>,
+        /// Pool and connection the error occurred on, if known
+        context: Option<Box<ErrorContext>>,
+        /// Underlying error, if any
+        #[source]
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    },
     
     /// Authentication error
-    #[error("Authentication error: {0}")]
-    Auth(String),
+    #[error("Authentication error: {message}{}", in_context(.context))]
+    Auth {
+        /// Description of what failed
+        message: Cow<'static, str>,
+        /// Pool and connection the error occurred on, if known
+        context: Option<Box<ErrorContext>>,
+        /// Error reported by the server
+        #[source]
+        source: surrealdb::Error,
+    },
 
     /// Build error
     #[error("Build error: {0}")]
     Build(#[from] managed::BuildError),
+
+    /// Pool error not covered by a more specific variant
+    #[error("Pool error: {0}")]
+    Pool(#[source] Box<PoolError>),
+
+    /// Timeout while checking out a connection from the pool
+    #[error("Timed out {phase} a connection")]
+    Timeout {
+        /// Phase of the checkout which timed out
+        phase: TimeoutPhase,
+    },
+
+    /// The pool has been closed
+    #[error("Pool is closed")]
+    PoolClosed,
+
+    /// Invalid pagination cursor
+    #[error("Invalid cursor: {0}")]
+    InvalidCursor(String),
+
+    /// Query exceeded its deadline
+    #[error("Query timed out after {0:?}")]
+    QueryTimeout(Duration),
+
+    /// No runtime was specified for enforcing timeouts
+    #[error("No runtime specified")]
+    NoRuntimeSpecified,
+
+    /// I/O error
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Migration failed
+    #[error("Migration {version} failed: {source}")]
+    Migration {
+        /// Version of the failed migration
+        version: u64,
+        /// Error which caused the migration to fail
+        #[source]
+        source: Box<Error>,
+    },
+
+    /// Migration can't be reverted
+    #[error("Migration {0} has no down script")]
+    MissingDownScript(u64),
+
+    /// Mutating statement rejected by a read-only connection
+    #[error("{0} statements are not allowed in read-only mode")]
+    ReadOnly(String),
+
+    /// Optimistic locking failed
+    #[error("Version conflict updating {table}:{id}, expected version {expected_version}")]
+    Conflict {
+        /// Table of the record
+        table: String,
+        /// Id of the record
+        id: String,
+        /// Version the record was expected to have
+        expected_version: u64,
+    },
+
+    /// Configuration rejected by [`Config::validate()`] or read from invalid
+    /// environment variables by [`Config::from_surreal_env()`]
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    /// Unsupported or malformed `DEFINE` statement
+    #[error("Invalid definition: {0}")]
+    InvalidDefinition(String),
+
+    /// Query which can't be used for the requested operation, e.g. a
+    /// paginated query without an `ORDER BY` clause
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+
+    /// Value couldn't be converted to or from JSON, e.g. a record passed to
+    /// [`SurrealRecord::create()`] or a persisted write of an
+    /// [`OfflineBuffer`]
+    ///
+    /// [`SurrealRecord::create()`]: repo::SurrealRecord::create
+    /// [`OfflineBuffer`]: offline::OfflineBuffer
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    /// Queue of an [`OfflineBuffer`] is full
+    ///
+    /// [`OfflineBuffer`]: offline::OfflineBuffer
+    #[error("Offline buffer is full ({0} writes)")]
+    BufferFull(usize),
+
+    /// Hook or background task provided by the application panicked
+    ///
+    /// The panic is caught so it can't take down the pool. Panicking hooks
+    /// are treated as if they weren't set for the failed call.
+    #[error("{name} panicked: {message}")]
+    Panicked {
+        /// Name of the hook or task, e.g. `on_resize`
+        name: &'static str,
+        /// Message of the panic
+        message: String,
+    },
+
+    /// Error of a query whose result was shared with other callers, see
+    /// [`Coalescer`]
+    ///
+    /// [`Coalescer`]: coalesce::Coalescer
+    #[error(transparent)]
+    Shared(Arc<Error>),
+
+    /// Query of a [`Scope`] was canceled as its scope failed or finished
+    ///
+    /// [`Scope`]: scope::Scope
+    #[error("Query was canceled")]
+    Canceled,
+
+    /// Error reported by the `surrealdb-migrations` crate
+    #[cfg(feature = "surrealdb-migrations")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "surrealdb-migrations")))]
+    #[error("surrealdb-migrations error: {0}")]
+    SurrealMigrations(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    /// Docker container could not be started
+    #[cfg(feature = "testcontainers")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "testcontainers")))]
+    #[error("Container error: {0}")]
+    Container(#[source] Box<::testcontainers::TestcontainersError>),
+}
+
+/// Pool and connection an [`Error`] occurred on.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// Name of the pool, see [`Config::name`]
+    pub pool: Option<String>,
+    /// Host the pool connects to
+    pub endpoint: String,
+    /// Id of the connection, see [`ClientWrapper::id()`]
+    pub connection: Option<u64>,
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(pool) = &self.pool {
+            write!(f, "pool {}, ", pool)?;
+        }
+        write!(f, "endpoint {}", self.endpoint)?;
+        if let Some(connection) = self.connection {
+            write!(f, ", connection #{}", connection)?;
+        }
+        Ok(())
+    }
+}
+
+fn in_context(context: &Option<Box<ErrorContext>>) -> String {
+    context
+        .as_ref()
+        .map(|context| format!(" ({})", context))
+        .unwrap_or_default()
+}
+
+/// Category of an [`Error`], returned by [`Error::kind()`].
+///
+/// Unlike the variants of [`Error`] the kinds are stable: new variants are
+/// assigned one of the existing kinds where possible.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The server could not be reached or the connection broke
+    Connection,
+    /// The server rejected the credentials
+    Auth,
+    /// Checking out a connection or running a query took too long
+    Timeout,
+    /// The pool has been closed
+    PoolClosed,
+    /// The server failed to execute a query
+    Query,
+    /// Optimistic locking failed
+    Conflict,
+    /// Mutating statement rejected by a read-only connection
+    ReadOnly,
+    /// Input such as a pagination cursor, a definition or a query is invalid,
+    /// or a value can't be serialized
+    InvalidInput,
+    /// The pool or manager is misconfigured
+    Config,
+    /// Applying or reverting a migration failed
+    Migration,
+    /// I/O error
+    Io,
+    /// Any other error
+    Other,
+}
+
+/// Phase of a checkout which timed out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TimeoutPhase {
+    /// Waiting for a free slot in the pool
+    Wait,
+    /// Creating a new connection
+    Create,
+    /// Recycling an idle connection
+    Recycle,
+}
+
+impl fmt::Display for TimeoutPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Wait => "waiting for",
+            Self::Create => "creating",
+            Self::Recycle => "recycling",
+        })
+    }
+}
+
+impl From<managed::TimeoutType> for TimeoutPhase {
+    fn from(t: managed::TimeoutType) -> Self {
+        match t {
+            managed::TimeoutType::Wait => Self::Wait,
+            managed::TimeoutType::Create => Self::Create,
+            managed::TimeoutType::Recycle => Self::Recycle,
+        }
+    }
+}
+
+impl Error {
+    /// Creates an [`Error::Connection`] caused by `source`.
+    pub(crate) fn connection(
+        message: impl Into<Cow<'static, str>>,
+        source: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        Self::Connection {
+            message: message.into(),
+            context: None,
+            source: Some(source.into()),
+        }
+    }
+
+    /// Creates an [`Error::Auth`] caused by `source`.
+    fn auth(message: &'static str, source: surrealdb::Error) -> Self {
+        Self::Auth {
+            message: message.into(),
+            context: None,
+            source,
+        }
+    }
+
+    /// Returns the category of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self.backend() {
+            Self::Auth { .. } => ErrorKind::Auth,
+            e if e.is_auth() => ErrorKind::Auth,
+            Self::Connection { .. } => ErrorKind::Connection,
+            e if e.is_connection_lost() => ErrorKind::Connection,
+            Self::Surreal(_) => ErrorKind::Query,
+            Self::Timeout { .. } | Self::QueryTimeout(_) => ErrorKind::Timeout,
+            Self::PoolClosed => ErrorKind::PoolClosed,
+            Self::Conflict { .. } => ErrorKind::Conflict,
+            Self::ReadOnly(_) => ErrorKind::ReadOnly,
+            Self::InvalidCursor(_)
+            | Self::InvalidDefinition(_)
+            | Self::InvalidQuery(_)
+            | Self::Serialization(_) => ErrorKind::InvalidInput,
+            Self::Build(_) | Self::NoRuntimeSpecified | Self::InvalidConfig(_) => ErrorKind::Config,
+            Self::Migration { .. } | Self::MissingDownScript(_) => ErrorKind::Migration,
+            #[cfg(feature = "surrealdb-migrations")]
+            Self::SurrealMigrations(_) => ErrorKind::Migration,
+            Self::Io(_) => ErrorKind::Io,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// Returns the pool and connection this error occurred on, if known.
+    #[must_use]
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self.backend() {
+            Self::Connection { context, .. } | Self::Auth { context, .. } => context.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Attaches `context` to connection and authentication errors.
+    pub(crate) fn with_context(mut self, context: ErrorContext) -> Self {
+        if let Self::Connection { context: c, .. } | Self::Auth { context: c, .. } = &mut self {
+            *c = Some(Box::new(context));
+        }
+        self
+    }
+
+    /// Indicates whether the failed operation might succeed when retried
+    /// as is.
+    ///
+    /// This is the case for timeouts and connection failures, but not for
+    /// errors caused by the query itself, optimistic locking conflicts,
+    /// failed authentication or a closed pool.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self.backend() {
+            Self::Timeout { .. } | Self::QueryTimeout(_) | Self::Connection { .. } => true,
+            e => e.is_connection_lost(),
+        }
+    }
+
+    /// Indicates whether an optimistic locking update failed, see
+    /// [`ClientWrapper::update_if_version()`].
+    ///
+    /// Sending the same update again fails the same way. Re-read the record
+    /// and retry the update with its current version instead.
+    #[must_use]
+    pub fn is_conflict(&self) -> bool {
+        matches!(self.backend(), Self::Conflict { .. })
+    }
+
+    /// Indicates whether the server rejected the credentials.
+    #[must_use]
+    pub fn is_auth(&self) -> bool {
+        use surrealdb::error::Db;
+
+        matches!(
+            self.backend(),
+            Self::Auth { .. } | Self::Surreal(surrealdb::Error::Db(Db::InvalidAuth))
+        )
+    }
+
+    /// Indicates whether an established connection to the server broke, as
+    /// opposed to the query itself failing.
+    #[must_use]
+    pub fn is_connection_lost(&self) -> bool {
+        use surrealdb::error::Api;
+
+        matches!(
+            self.backend(),
+            Self::Surreal(surrealdb::Error::Api(
+                Api::Ws(_) | Api::Http(_) | Api::ConnectionUninitialised
+            ))
+        )
+    }
+
+    /// Indicates whether the session of the connection or its token expired,
+    /// so the connection needs to authenticate again.
+    #[must_use]
+    pub fn is_session_expired(&self) -> bool {
+        use surrealdb::error::{Api, Db};
+
+        match self.backend() {
+            Self::Surreal(surrealdb::Error::Db(Db::ExpiredSession | Db::ExpiredToken)) => true,
+            // Remote engines report errors of the server as text.
+            Self::Surreal(surrealdb::Error::Api(Api::Query(message))) => {
+                message.contains("session has expired") || message.contains("token has expired")
+            }
+            _ => false,
+        }
+    }
+
+    /// Indicates whether the server refused a query because the session
+    /// lacks the permissions for it, e.g. because it was invalidated.
+    #[must_use]
+    pub fn is_permission_denied(&self) -> bool {
+        use surrealdb::error::{Api, Db};
+
+        match self.backend() {
+            Self::Surreal(surrealdb::Error::Db(Db::IamError(_))) => true,
+            // Remote engines report errors of the server as text.
+            Self::Surreal(surrealdb::Error::Api(Api::Query(message))) => {
+                message.contains("Not enough permissions")
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the HTTP status code a web handler should respond with.
+    ///
+    /// - `503 Service Unavailable` if no connection could be checked out or
+    ///   the connection to the server broke
+    /// - `504 Gateway Timeout` if a query exceeded its timeout
+    /// - `409 Conflict` for optimistic locking conflicts and duplicate records
+    /// - `400 Bad Request` for invalid pagination cursors, which are supplied
+    ///   by the client
+    /// - `500 Internal Server Error` for everything else, including failed
+    ///   authentication and invalid queries
+    #[must_use]
+    pub fn http_status(&self) -> u16 {
+        use surrealdb::error::Db;
+
+        match self.backend() {
+            Self::Timeout { .. } | Self::PoolClosed | Self::Connection { .. } => 503,
+            Self::QueryTimeout(_) => 504,
+            Self::Conflict { .. } | Self::Surreal(surrealdb::Error::Db(Db::RecordExists { .. })) => {
+                409
+            }
+            Self::InvalidCursor(_) => 400,
+            e if e.is_connection_lost() => 503,
+            _ => 500,
+        }
+    }
+
+    /// Returns the error of the [`Manager`] if this error wraps one.
+    fn backend(&self) -> &Self {
+        match self {
+            Self::Pool(e) => match &**e {
+                PoolError::PostCreateHook(managed::HookError::Backend(e)) => e.backend(),
+                _ => self,
+            },
+            Self::Shared(e) => e.backend(),
+            _ => self,
+        }
+    }
+}
+
+impl From<PoolError> for Error {
+    fn from(e: PoolError) -> Self {
+        match e {
+            PoolError::Backend(e) => e,
+            PoolError::Timeout(t) => Self::Timeout { phase: t.into() },
+            PoolError::Closed => Self::PoolClosed,
+            PoolError::NoRuntimeSpecified => Self::NoRuntimeSpecified,
+            e => Self::Pool(Box::new(e)),
+        }
+    }
 }
 
 /// Result type for SurrealDB pool operations
@@ -136,103 +724,584 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// Manager for creating and recycling SurrealDB connections.
 #[derive(Debug)]
 pub struct Manager {
-    config: Config,
+    config: Arc<Config>,
+    runtime: Option<Runtime>,
+    comments: Option<QueryComments>,
+    analytics: Option<Pool>,
+    executor: Option<rt::SharedExecutor>,
+    engine: Option<surrealdb::opt::Config>,
+    clock: clock::SharedClock,
+    checkout_hook: Option<checkout::CheckoutHook>,
+    switch_hook: Option<switch::SwitchHook>,
+    health: health::Health,
+    health_thresholds: health::HealthThresholds,
+    health_hook: Option<health::HealthHook>,
+    errors: diagnostics::ErrorLog,
+    events: diagnostics::EventLog,
+    failures: diagnostics::FailureCounters,
+    waits: checkout::WaitLog,
+    switch: switch::Switch,
+    drained: Arc<drain::Drained>,
+    budget: Option<budget::ConnectionBudget>,
+    create_retry: Option<retry::RetryBudget>,
+    reservation: Arc<priority::Reservation>,
+    version: version::VersionCache,
+    shutdown: Arc<shutdown::Shutdown>,
+    next_id: AtomicU64,
+    /// Number of connections handed out by `create` or `recycle`, telling
+    /// whether checkouts make progress
+    checkouts: AtomicU64,
+    #[cfg(feature = "srv")]
+    srv: Option<srv::SrvResolver>,
+    #[cfg(feature = "test-utils")]
+    mock: Option<test_utils::MockManager>,
 }
 
 impl Manager {
-    /// Creates a new Manager using the given Config.
+    /// Creates a new Manager using a copy of the given Config.
+    ///
+    /// Use `Manager::from(config)` for a Config which isn't needed
+    /// afterwards, which doesn't copy it.
     #[must_use]
     pub fn from_config(config: &Config) -> Self {
+        Self::from_shared_config(Arc::new(config.clone()))
+    }
+
+    /// Creates a new Manager using the given Config without copying it.
+    #[must_use]
+    pub fn from_shared_config(config: Arc<Config>) -> Self {
+        config::warn_deprecated(&config);
         Self {
-            config: config.clone(),
+            config,
+            runtime: None,
+            comments: None,
+            analytics: None,
+            executor: None,
+            engine: None,
+            clock: clock::SharedClock::default(),
+            checkout_hook: None,
+            switch_hook: None,
+            health: health::Health::default(),
+            health_thresholds: health::HealthThresholds::default(),
+            health_hook: None,
+            errors: diagnostics::ErrorLog::default(),
+            events: diagnostics::EventLog::default(),
+            failures: diagnostics::FailureCounters::default(),
+            waits: checkout::WaitLog::default(),
+            switch: switch::Switch::default(),
+            drained: Arc::default(),
+            budget: None,
+            create_retry: None,
+            reservation: Arc::default(),
+            version: version::VersionCache::default(),
+            shutdown: Arc::default(),
+            next_id: AtomicU64::new(1),
+            checkouts: AtomicU64::new(0),
+            #[cfg(feature = "srv")]
+            srv: srv::SrvResolver::parse(&config.host),
+            #[cfg(feature = "test-utils")]
+            mock: None,
         }
     }
 
-    /// Authenticate the connection using configured credentials
-    async fn auth(&self, db: &Surreal<Any>) -> Result<()> {
-        match &self.config.creds {
-            Credentials::Root { user, pass } => {
-                let _jwt = db.signin(auth::Root {
-                    username: user,
-                    password: pass,
-                })
-                .await
-                .map_err(|e| Error::Auth(format!("Root auth failed: {}", e)))?;
-            }
-            Credentials::Namespace { user, pass, ns } => {
-                let _jwt = db.signin(auth::Namespace {
-                    username: user,
-                    password: pass,
-                    namespace: ns,
-                })
-                .await
-                .map_err(|e| Error::Auth(format!("Namespace auth failed: {}", e)))?;
-            }
-            Credentials::Database {
-                user,
-                pass,
-                ns,
-                db: database,
-            } => {
-                let _jwt = db.signin(auth::Database {
-                    username: user,
-                    password: pass,
-                    namespace: ns,
-                    database,
-                })
-                .await
-                .map_err(|e| Error::Auth(format!("Database auth failed: {}", e)))?;
-            }
+    /// Returns the [`Config`] this manager creates connections with.
+    ///
+    /// The manager of a [`Pool`] is returned by [`Pool::manager()`].
+    ///
+    /// [`Pool::manager()`]: managed::Pool::manager
+    #[must_use]
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Returns the [`Config`] this manager creates connections with as a
+    /// shared reference, e.g. for creating further managers.
+    #[must_use]
+    pub fn shared_config(&self) -> Arc<Config> {
+        self.config.clone()
+    }
+
+    /// Sets the [`Runtime`] used by connections for enforcing query timeouts.
+    #[must_use]
+    pub fn runtime(mut self, runtime: Runtime) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Prepends the given [`QueryComments`] to all queries run using
+    /// [`ClientWrapper::query()`].
+    #[must_use]
+    pub fn query_comments(mut self, comments: QueryComments) -> Self {
+        self.comments = Some(comments);
+        self
+    }
+
+    /// Sets the configuration of the SurrealDB engine, e.g. for tuning
+    /// timeouts or capabilities of a client which was set up elsewhere.
+    ///
+    /// It is used for every connection created by this manager.
+    #[must_use]
+    pub fn engine_config(mut self, config: surrealdb::opt::Config) -> Self {
+        self.engine = Some(config);
+        self
+    }
+
+    /// Sets the [`Clock`] used for time-based behavior like
+    /// [`Config::max_idle_time`].
+    ///
+    /// See [`Clock`] for which behavior is affected.
+    ///
+    /// [`Clock`]: clock::Clock
+    #[must_use]
+    pub fn clock(mut self, clock: impl clock::Clock + 'static) -> Self {
+        self.clock = clock::SharedClock(Arc::new(clock));
+        self
+    }
+
+    /// Sets the [`Executor`] running background tasks like the
+    /// [`BackupScheduler`].
+    ///
+    /// Defaults to the [`Runtime`] set using [`Manager::runtime()`].
+    ///
+    /// [`BackupScheduler`]: backup::BackupScheduler
+    /// [`Executor`]: rt::Executor
+    #[must_use]
+    pub fn executor(mut self, executor: impl rt::Executor + 'static) -> Self {
+        self.executor = Some(rt::SharedExecutor(Arc::new(executor)));
+        self
+    }
+
+    /// Sets a [`ConnectionBudget`] capping the connections of this manager
+    /// together with those of other managers sharing the budget.
+    ///
+    /// [`ConnectionBudget`]: budget::ConnectionBudget
+    #[must_use]
+    pub fn connection_budget(mut self, budget: budget::ConnectionBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Retries creating connections within the given [`RetryBudget`] if it
+    /// fails with an error for which [`Error::is_retryable()`] returns
+    /// `true`.
+    ///
+    /// The create timeout of the pool still applies to all attempts
+    /// together.
+    ///
+    /// [`RetryBudget`]: retry::RetryBudget
+    #[must_use]
+    pub fn create_retry(mut self, budget: retry::RetryBudget) -> Self {
+        self.create_retry = Some(budget);
+        self
+    }
+
+    /// Sets a function called whenever checking out a connection fails, e.g.
+    /// for alerting.
+    ///
+    /// It is called by [`PoolExt::checkout()`] and by all helpers of this
+    /// crate checking out connections, but not by [`Pool::get()`] itself.
+    /// The function runs on the task which failed to check out a connection,
+    /// so it should return quickly.
+    ///
+    /// [`Pool::get()`]: managed::Pool::get
+    #[must_use]
+    pub fn on_checkout_failure(
+        mut self,
+        f: impl Fn(&checkout::CheckoutFailure) + Send + Sync + 'static,
+    ) -> Self {
+        self.checkout_hook = Some(checkout::CheckoutHook(Arc::new(f)));
+        self
+    }
+
+    /// Sets a function called whenever the pool is switched to another
+    /// endpoint using [`PoolExt::commit_switch()`] or
+    /// [`PoolExt::set_endpoint()`].
+    #[must_use]
+    pub fn on_endpoint_switch(
+        mut self,
+        f: impl Fn(&switch::EndpointSwitch) + Send + Sync + 'static,
+    ) -> Self {
+        self.switch_hook = Some(switch::SwitchHook(Arc::new(f)));
+        self
+    }
+
+    /// Sets the thresholds of the [`HealthState`] of the pool.
+    ///
+    /// [`HealthState`]: health::HealthState
+    #[must_use]
+    pub fn health_thresholds(mut self, thresholds: health::HealthThresholds) -> Self {
+        self.health_thresholds = thresholds;
+        self
+    }
+
+    /// Sets a function called whenever the [`HealthState`] of the pool
+    /// changes, e.g. for alerting.
+    ///
+    /// The function runs on the task whose checkout caused the change, so it
+    /// should return quickly.
+    ///
+    /// [`HealthState`]: health::HealthState
+    #[must_use]
+    pub fn on_health_change(
+        mut self,
+        f: impl Fn(&health::HealthTransition) + Send + Sync + 'static,
+    ) -> Self {
+        self.health_hook = Some(health::HealthHook(Arc::new(f)));
+        self
+    }
+
+    /// Returns the [`rt::Executor`] for background tasks, if any.
+    pub(crate) fn background_executor(&self) -> Option<Arc<dyn rt::Executor>> {
+        match (&self.executor, self.runtime) {
+            (Some(executor), _) => Some(executor.0.clone()),
+            (None, Some(runtime)) => Some(Arc::new(runtime)),
+            (None, None) => None,
+        }
+    }
+
+    pub(crate) fn analytics(mut self, pool: Pool) -> Self {
+        self.analytics = Some(pool);
+        self
+    }
+
+    #[cfg(feature = "test-utils")]
+    pub(crate) fn mock(mut self, mock: test_utils::MockManager) -> Self {
+        self.mock = Some(mock);
+        self
+    }
+
+    /// Returns a manager creating connections using `config` just like this
+    /// one does, for switching to another endpoint.
+    fn retarget(&self, config: Arc<Config>) -> Self {
+        let mut manager = Self::from_shared_config(config);
+        manager.runtime = self.runtime;
+        manager.comments = self.comments.clone();
+        manager.engine = self.engine.clone();
+        manager.drained = self.drained.clone();
+        manager.budget = self.budget.clone();
+        #[cfg(feature = "test-utils")]
+        {
+            manager.mock = self.mock.clone();
+        }
+        manager
+    }
+
+    fn error_context(&self, connection: Option<u64>) -> ErrorContext {
+        ErrorContext {
+            pool: self.config.name.clone(),
+            endpoint: self.config.host.clone(),
+            connection,
+        }
+    }
+
+    /// Returns the endpoint the next connection should be made to.
+    async fn endpoint(&self) -> Result<Cow<'_, str>> {
+        #[cfg(feature = "srv")]
+        if let Some(srv) = &self.srv {
+            let accept = |endpoint: &str| !self.drained.contains(endpoint);
+            return srv.endpoint(&self.clock, accept).await.map(Cow::Owned);
+        }
+        self.drained.check(&self.config.host)?;
+        Ok(config::engine_host(&self.config.host))
+    }
+
+    /// Opens a connection without authenticating it.
+    pub(crate) async fn open(&self) -> Result<Surreal<Any>> {
+        let endpoint = self.endpoint().await?;
+        self.open_at(&endpoint).await
+    }
+
+    async fn open_at(&self, endpoint: &str) -> Result<Surreal<Any>> {
+        let family = self.config.address_family;
+        dual_stack::connect(endpoint, family, self.runtime, self.engine.as_ref()).await
+    }
+
+    async fn connect(&self, id: u64) -> Result<ClientWrapper> {
+        #[cfg(feature = "test-utils")]
+        if let Some(mock) = &self.mock {
+            mock.record_create()?;
+        }
+
+        let permit = match &self.budget {
+            Some(budget) => Some(budget.acquire().await),
+            None => None,
+        };
+        Ok(self.establish(id).await?.with_permit(permit))
+    }
+
+    /// Opens a dedicated connection outside of the pool, e.g. for exports,
+    /// imports and migrations.
+    ///
+    /// Unlike [`managed::Manager::create()`], this doesn't record events,
+    /// errors or the health of the pool, takes no permit of a shared
+    /// [`ConnectionBudget`] and never takes a connection warmed up for
+    /// switching endpoints. The connection is made to the endpoint the pool
+    /// currently uses.
+    ///
+    /// [`ConnectionBudget`]: budget::ConnectionBudget
+    pub(crate) async fn connect_dedicated(&self) -> Result<ClientWrapper> {
+        let (_, target) = self.switch.current();
+        let manager = target.as_deref().unwrap_or(self);
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        manager.establish(id).await
+    }
+
+    /// Opens and sets up a connection without any bookkeeping.
+    async fn establish(&self, id: u64) -> Result<ClientWrapper> {
+        // Connect to database
+        let endpoint = self.endpoint().await?;
+        let db = self.open_at(&endpoint).await?;
+            
+        // Skip authentication for local engines
+        if !config::skips_auth(&self.config.host) {
+            // Authenticate
+            self.auth(&db).await?;
+        }
+
+        if self.config.create_if_missing {
+            self.provision(&db).await?;
         }
         
         // Set namespace and database
         db.use_ns(&self.config.ns)
             .use_db(&self.config.db)
             .await
-            .map_err(|e| Error::Connection(format!("Failed to set ns/db: {}", e)))?;
+            .map_err(|e| Error::connection("Failed to set ns/db", e))?;
             
+        let reauth = self.config.reauth_on_expiry && !config::skips_auth(&self.config.host);
+        let conn = ClientWrapper::new(db, self.runtime)
+            .with_id(id)
+            .with_endpoint(endpoint.into_owned())
+            .reauth(reauth.then(|| self.config.clone()))
+            .read_only(self.config.read_only)
+            .default_timeout(self.config.default_query_timeout.map(Duration::from_secs))
+            .count_bytes(self.config.count_bytes || self.config.max_transferred_bytes.is_some())
+            .comments(self.comments.clone());
+        #[cfg(feature = "test-utils")]
+        let conn = conn.mock(self.mock.clone());
+        Ok(conn)
+    }
+
+    /// Authenticate the connection using configured credentials
+    pub(crate) async fn auth(&self, db: &Surreal<Any>) -> Result<()> {
+        authenticate(&self.config, db).await
+    }
+
+    /// Defines the configured namespace and database if they don't exist.
+    ///
+    /// Root credentials are required for defining the namespace. With
+    /// namespace credentials only the database is defined.
+    pub(crate) async fn provision(&self, db: &Surreal<Any>) -> Result<()> {
+        let ns = surql::escape_ident(&self.config.ns);
+        let database = surql::escape_ident(&self.config.db);
+        let sql = match &self.config.creds {
+            Credentials::Database { .. } => return Ok(()),
+            Credentials::Namespace { .. } => format!(
+                "USE NS {}; DEFINE DATABASE IF NOT EXISTS {}; USE DB {};",
+                ns, database, database
+            ),
+            Credentials::Root { .. } => format!(
+                "DEFINE NAMESPACE IF NOT EXISTS {}; USE NS {}; DEFINE DATABASE IF NOT EXISTS {}; USE DB {};",
+                ns, ns, database, database
+            ),
+        };
+        let _ = db.query(sql).await?.check()?;
         Ok(())
     }
 }
 
+impl Drop for Manager {
+    fn drop(&mut self) {
+        // Stops background tasks which only hold a weak reference to the
+        // pool of this manager.
+        self.shutdown.trigger();
+    }
+}
+
+impl From<Config> for Manager {
+    fn from(config: Config) -> Self {
+        Self::from_shared_config(Arc::new(config))
+    }
+}
+
+impl From<Arc<Config>> for Manager {
+    fn from(config: Arc<Config>) -> Self {
+        Self::from_shared_config(config)
+    }
+}
+
+/// Authenticates `db` using the credentials of `config` and selects its
+/// namespace and database.
+pub(crate) async fn authenticate(config: &Config, db: &Surreal<Any>) -> Result<()> {
+    match &config.creds {
+        Credentials::Root { user, pass } => {
+            let _jwt = db.signin(auth::Root {
+                username: user,
+                password: pass,
+            })
+            .await
+            .map_err(|e| Error::auth("Root auth failed", e))?;
+        }
+        Credentials::Namespace { user, pass, ns } => {
+            let _jwt = db.signin(auth::Namespace {
+                username: user,
+                password: pass,
+                namespace: ns,
+            })
+            .await
+            .map_err(|e| Error::auth("Namespace auth failed", e))?;
+        }
+        Credentials::Database {
+            user,
+            pass,
+            ns,
+            db: database,
+        } => {
+            let _jwt = db.signin(auth::Database {
+                username: user,
+                password: pass,
+                namespace: ns,
+                database,
+            })
+            .await
+            .map_err(|e| Error::auth("Database auth failed", e))?;
+        }
+    }
+
+    // Set namespace and database
+    db.use_ns(&config.ns)
+        .use_db(&config.db)
+        .await
+        .map_err(|e| Error::connection("Failed to set ns/db", e))?;
+
+    Ok(())
+}
+
 impl managed::Manager for Manager {
-    type Type = Surreal<Any>;
+    type Type = ClientWrapper;
     type Error = Error;
 
     async fn create(&self) -> Result<Self::Type> {
-        // Connect to database
-        let db = surrealdb::engine::any::connect(&self.config.host)
-            .await
-            .map_err(|e| Error::Connection(format!("Failed to connect: {}", e)))?;
-            
-        // Skip authentication for memory database
-        if !self.config.host.starts_with("mem://") {
-            // Authenticate
-            self.auth(&db).await?;
+        if let Some(conn) = self.switch.take_ready() {
+            let _ = self.checkouts.fetch_add(1, Ordering::Relaxed);
+            return Ok(conn);
+        }
+        let (generation, target) = self.switch.current();
+        let manager = target.as_deref().unwrap_or(self);
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let result = match &self.create_retry {
+            Some(budget) => {
+                let timed_out = |_| Error::Timeout {
+                    phase: TimeoutPhase::Create,
+                };
+                let attempt = || manager.connect(id);
+                budget
+                    .run(self.runtime, Error::is_retryable, timed_out, attempt)
+                    .await
+            }
+            None => manager.connect(id).await,
+        };
+        health::record(self, result.is_ok());
+        match result {
+            Ok(conn) => {
+                let _ = self.checkouts.fetch_add(1, Ordering::Relaxed);
+                self.events.record(PoolEvent::Created {
+                    id,
+                    endpoint: conn.endpoint().to_string(),
+                });
+                Ok(conn.with_generation(generation))
+            }
+            Err(e) => {
+                let e = e.with_context(manager.error_context(Some(id)));
+                self.errors.record(&e);
+                self.failures.create(&e);
+                self.events.record(PoolEvent::CreateFailed {
+                    reason: FailureReason::of(&e),
+                });
+                Err(e)
+            }
         }
-        
-        // Set namespace and database
-        db.use_ns(&self.config.ns)
-            .use_db(&self.config.db)
-            .await
-            .map_err(|e| Error::Connection(format!("Failed to set ns/db: {}", e)))?;
-            
-        Ok(db)
     }
 
     async fn recycle(
         &self,
         conn: &mut Self::Type,
-        _: &managed::Metrics,
+        metrics: &managed::Metrics,
     ) -> managed::RecycleResult<Self::Error> {
-        // Skip authentication for memory database
-        if !self.config.host.starts_with("mem://") {
-            // Check connection health
-            self.auth(conn)
+        #[cfg(feature = "test-utils")]
+        if let Some(mock) = &self.mock {
+            mock.record_recycle().map_err(|e| RecycleError::Message(Cow::Owned(e)))?;
+        }
+
+        let evict = |reason: &'static str| {
+            self.events.record(PoolEvent::Evicted {
+                id: conn.id(),
+                reason,
+            });
+            RecycleError::message(reason)
+        };
+
+        if conn.is_suspect() {
+            return Err(evict("Connection marked as suspect"));
+        }
+
+        let (generation, target) = self.switch.current();
+        if conn.generation() != generation {
+            return Err(evict("Endpoint switched"));
+        }
+        let manager = target.as_deref().unwrap_or(self);
+
+        if self.drained.contains(conn.endpoint()) {
+            return Err(evict("Endpoint drained"));
+        }
+
+        if let Some(max_idle_time) = self.config.max_idle_time {
+            if self.clock.idle(metrics) > Duration::from_secs(max_idle_time) {
+                return Err(evict("Connection idle for too long"));
+            }
+        }
+
+        if let Some(max_transferred_bytes) = self.config.max_transferred_bytes {
+            if conn.metrics().transferred_bytes() > max_transferred_bytes {
+                return Err(evict("Connection transferred too many bytes"));
+            }
+        }
+
+        let backend = |e: Error| {
+            let e = e.with_context(manager.error_context(Some(conn.id())));
+            self.errors.record(&e);
+            self.failures.recycle(&e);
+            self.events.record(PoolEvent::RecycleFailed {
+                id: conn.id(),
+                reason: FailureReason::of(&e),
+            });
+            health::record(self, false);
+            RecycleError::Backend(e)
+        };
+        conn.restore_params().await.map_err(backend)?;
+        conn.clear_params().await.map_err(backend)?;
+
+        // Skip authentication for local engines
+        if !config::skips_auth(&manager.config.host) {
+            // Check connection health, signing in again only if the session
+            // lost its authentication, e.g. because it was invalidated.
+            let probe = conn
+                .client()
+                .query("INFO FOR DB")
                 .await
-                .map_err(|e| RecycleError::Message(Cow::Owned(format!("Connection check failed: {}", e))))?;
+                .and_then(surrealdb::Response::check)
+                .map_err(Error::from);
+            match probe {
+                Ok(_) => {}
+                Err(e) if e.is_permission_denied() || e.is_session_expired() => {
+                    manager.auth(conn.client()).await.map_err(backend)?;
+                }
+                Err(e) => return Err(backend(e)),
+            }
         }
-            
+
+        health::record(self, true);
+        let _ = self.checkouts.fetch_add(1, Ordering::Relaxed);
         Ok(())
     }
 }
@@ -0,0 +1,238 @@
+use std::borrow::Cow;
+
+use crate::{surql, ClientWrapper, Error, Pool, Result};
+
+/// Default name of the table tracking applied migrations.
+pub const DEFAULT_TABLE: &str = "_migrations";
+
+const RECORD_APPLIED: &str = "CREATE type::thing($table, $version) \
+    SET version = $version, name = $name, applied_at = time::now()";
+const RECORD_REVERTED: &str = "DELETE type::thing($table, $version)";
+
+/// Creates a [`Migration`] from SurrealQL files embedded into the binary.
+///
+/// The paths are resolved relative to the file containing the macro call,
+/// just like [`include_str!`].
+///
+/// ```rust,ignore
+/// let migrator = Migrator::new([
+///     deadpool_surrealdb::migration!(1, "create users", "migrations/001_users.up.surql"),
+///     deadpool_surrealdb::migration!(
+///         2,
+///         "add email index",
+///         "migrations/002_email.up.surql",
+///         "migrations/002_email.down.surql"
+///     ),
+/// ]);
+/// ```
+#[macro_export]
+macro_rules! migration {
+    ($version:expr, $name:expr, $up:expr $(,)?) => {
+        $crate::migrations::Migration::new($version, $name, include_str!($up))
+    };
+    ($version:expr, $name:expr, $up:expr, $down:expr $(,)?) => {
+        $crate::migrations::Migration::new($version, $name, include_str!($up))
+            .with_down(include_str!($down))
+    };
+}
+
+/// Single versioned migration.
+#[derive(Clone, Debug)]
+pub struct Migration {
+    version: u64,
+    name: Cow<'static, str>,
+    up: Cow<'static, str>,
+    down: Option<Cow<'static, str>>,
+}
+
+impl Migration {
+    /// Creates a new [`Migration`] applying the `up` script.
+    pub fn new(
+        version: u64,
+        name: impl Into<Cow<'static, str>>,
+        up: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        Self {
+            version,
+            name: name.into(),
+            up: up.into(),
+            down: None,
+        }
+    }
+
+    /// Sets the script reverting this migration.
+    #[must_use]
+    pub fn with_down(mut self, down: impl Into<Cow<'static, str>>) -> Self {
+        self.down = Some(down.into());
+        self
+    }
+
+    /// Returns the version of this migration.
+    #[must_use]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the name of this migration.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Outcome of a [`Migrator`] run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// Versions applied (or which would have been applied in a dry run)
+    pub applied: Vec<u64>,
+    /// Versions reverted (or which would have been reverted in a dry run)
+    pub reverted: Vec<u64>,
+    /// Whether this was a dry run
+    pub dry_run: bool,
+}
+
+/// Applies and reverts [`Migration`]s.
+///
+/// Applied versions are tracked in a table ([`DEFAULT_TABLE`] by default).
+/// Every migration is executed in its own transaction together with the
+/// update of that table, using a dedicated connection which doesn't occupy a
+/// slot of the pool.
+#[derive(Clone, Debug)]
+pub struct Migrator {
+    migrations: Vec<Migration>,
+    table: String,
+    dry_run: bool,
+}
+
+impl Migrator {
+    /// Creates a new [`Migrator`] for the given `migrations`.
+    pub fn new(migrations: impl IntoIterator<Item = Migration>) -> Self {
+        let mut migrations: Vec<_> = migrations.into_iter().collect();
+        migrations.sort_by_key(|m| m.version);
+        Self {
+            migrations,
+            table: DEFAULT_TABLE.to_string(),
+            dry_run: false,
+        }
+    }
+
+    /// Sets the table used for tracking applied migrations.
+    #[must_use]
+    pub fn table(mut self, table: impl Into<String>) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// Only reports what would be done without executing any migration.
+    #[must_use]
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Returns the versions which have been applied already.
+    pub async fn applied(&self, pool: &Pool) -> Result<Vec<u64>> {
+        let conn = pool.manager().connect_dedicated().await?;
+        self.applied_versions(&conn).await
+    }
+
+    /// Applies all pending migrations.
+    pub async fn up(&self, pool: &Pool) -> Result<MigrationReport> {
+        self.up_to(pool, u64::MAX).await
+    }
+
+    /// Applies all pending migrations up to and including `target`.
+    pub async fn up_to(&self, pool: &Pool, target: u64) -> Result<MigrationReport> {
+        let conn = pool.manager().connect_dedicated().await?;
+        let applied = self.applied_versions(&conn).await?;
+        let mut report = MigrationReport {
+            dry_run: self.dry_run,
+            ..MigrationReport::default()
+        };
+        for migration in &self.migrations {
+            if migration.version > target || applied.contains(&migration.version) {
+                continue;
+            }
+            if !self.dry_run {
+                self.execute(&conn, migration, &migration.up, RECORD_APPLIED)
+                    .await?;
+            }
+            report.applied.push(migration.version);
+        }
+        Ok(report)
+    }
+
+    /// Reverts all applied migrations newer than `target`, newest first.
+    ///
+    /// # Errors
+    ///
+    /// Fails without reverting anything if one of the migrations to revert
+    /// has no down script.
+    pub async fn down_to(&self, pool: &Pool, target: u64) -> Result<MigrationReport> {
+        let conn = pool.manager().connect_dedicated().await?;
+        let applied = self.applied_versions(&conn).await?;
+        let pending: Vec<_> = self
+            .migrations
+            .iter()
+            .rev()
+            .filter(|m| m.version > target && applied.contains(&m.version))
+            .collect();
+        if let Some(m) = pending.iter().find(|m| m.down.is_none()) {
+            return Err(Error::MissingDownScript(m.version));
+        }
+        let mut report = MigrationReport {
+            dry_run: self.dry_run,
+            ..MigrationReport::default()
+        };
+        for migration in pending {
+            if !self.dry_run {
+                let down = migration.down.as_deref().unwrap_or_default();
+                self.execute(&conn, migration, down, RECORD_REVERTED)
+                    .await?;
+            }
+            report.reverted.push(migration.version);
+        }
+        Ok(report)
+    }
+
+    async fn applied_versions(&self, conn: &ClientWrapper) -> Result<Vec<u64>> {
+        let mut versions: Vec<u64> = conn
+            .query(format!(
+                "SELECT VALUE version FROM {}",
+                surql::escape_ident(&self.table)
+            ))
+            .await?
+            .take(0)?;
+        versions.sort_unstable();
+        Ok(versions)
+    }
+
+    async fn execute(
+        &self,
+        conn: &ClientWrapper,
+        migration: &Migration,
+        script: &str,
+        record: &str,
+    ) -> Result<()> {
+        let sql = format!(
+            "BEGIN TRANSACTION;\n{};\n{};\nCOMMIT TRANSACTION;",
+            script.trim_end().trim_end_matches(';'),
+            record
+        );
+        let result: Result<()> = async {
+            let _ = conn
+                .query(sql)
+                .bind(("table", self.table.clone()))
+                .bind(("version", migration.version))
+                .bind(("name", migration.name.to_string()))
+                .await?
+                .check()?;
+            Ok(())
+        }
+        .await;
+        result.map_err(|e| Error::Migration {
+            version: migration.version,
+            source: Box::new(e),
+        })
+    }
+}
@@ -0,0 +1,473 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    fs::{self, OpenOptions},
+    io::{self, Write as _},
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{checkout, hook, rt::Spawn, shutdown::TaskPool, Error, ErrorKind, Pool, Result};
+
+/// Write queued by an [`OfflineBuffer`].
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct BufferedWrite {
+    /// SurrealQL of the write
+    pub sql: String,
+    /// Parameters bound to the write, `null` if there are none
+    pub bindings: serde_json::Value,
+}
+
+/// Result of [`OfflineBuffer::write()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WriteOutcome {
+    /// The write was executed right away
+    Written,
+    /// The database is unreachable, the write was queued for replaying it
+    /// later
+    Buffered,
+}
+
+/// Decision of the hook set using [`OfflineBuffer::on_conflict()`] about a
+/// replayed write the server rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictAction {
+    /// Drop the write and continue replaying
+    Skip,
+    /// Keep the write at the head of the queue and stop replaying, so it is
+    /// retried on the next replay
+    Stop,
+}
+
+type ConflictFn = dyn Fn(&BufferedWrite, &Error) -> ConflictAction + Send + Sync;
+
+/// Line of the file an [`OfflineBuffer`] is persisted in.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum LogEntry {
+    /// Write appended to the queue
+    Write(BufferedWrite),
+    /// Number of writes removed from the head of the queue
+    Done { done: usize },
+}
+
+/// State shared by the clones of an [`OfflineBuffer`].
+#[derive(Default)]
+struct Shared {
+    queue: Mutex<VecDeque<BufferedWrite>>,
+    /// Held exclusively by replays and shared by direct writes, so a replay
+    /// can't drain the queue while a write decides whether to queue itself.
+    replaying: tokio::sync::RwLock<()>,
+    /// Held while changing the queue and its file, so the lines of the file
+    /// are in the order of the queue.
+    log: tokio::sync::Mutex<()>,
+}
+
+/// Queue of writes made while the database is unreachable, which are
+/// replayed in order once connections can be created again.
+///
+/// Writes are executed right away while the database is reachable. If no
+/// connection can be checked out or the connection breaks, the write is
+/// queued instead. Later writes are queued as well until the queue has been
+/// replayed, so the order of the writes is kept. Writes may be executed
+/// twice if the connection breaks after the server received them, so only
+/// buffer idempotent writes like `UPSERT` with explicit record ids.
+///
+/// The queue holds up to `capacity` writes and is lost when the process
+/// exits unless it is persisted using [`OfflineBuffer::persist()`]. Clones
+/// share the queue. The buffer doesn't keep the pool alive: once the pool is
+/// dropped, writes fail with [`Error::PoolClosed`] and replaying stops.
+///
+/// ```rust,ignore
+/// let buffer = OfflineBuffer::new(&pool, 10_000).persist("/var/lib/app/writes.jsonl")?;
+/// buffer.clone().spawn()?;
+/// buffer
+///     .write("UPSERT type::thing('reading', $id) CONTENT $reading")
+///     .bind(("id", reading.id))
+///     .bind(("reading", reading))
+///     .await?;
+/// ```
+#[derive(Clone)]
+pub struct OfflineBuffer {
+    pool: TaskPool,
+    capacity: usize,
+    interval: Duration,
+    path: Option<PathBuf>,
+    on_conflict: Option<Arc<ConflictFn>>,
+    shared: Arc<Shared>,
+}
+
+impl OfflineBuffer {
+    /// Creates a new [`OfflineBuffer`] queueing up to `capacity` writes for
+    /// `pool`.
+    pub fn new(pool: &Pool, capacity: usize) -> Self {
+        Self {
+            pool: TaskPool::new(pool),
+            capacity,
+            interval: Duration::from_secs(5),
+            path: None,
+            on_conflict: None,
+            shared: Arc::default(),
+        }
+    }
+
+    /// Persists the queue as JSON lines in the file at `path`, loading the
+    /// writes queued there by a previous process.
+    ///
+    /// The file is a log: queued writes are appended to it and replayed
+    /// writes are marked as done, and it is emptied once the queue has been
+    /// replayed completely. A line cut off by a crash while appending it is
+    /// ignored. The file is written on threads where blocking is acceptable
+    /// if a [`Runtime`] is configured for the [`Manager`] of the pool.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file exists but can't be read or parsed.
+    ///
+    /// [`Runtime`]: crate::Runtime
+    /// [`Manager`]: crate::Manager
+    pub fn persist(mut self, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut queue = VecDeque::new();
+        let mut lines = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .peekable();
+        while let Some(line) = lines.next() {
+            let entry = match serde_json::from_str(line) {
+                Ok(entry) => entry,
+                // Only the last line can be incomplete.
+                Err(_) if lines.peek().is_none() && !text.ends_with('\n') => break,
+                Err(e) => return Err(e.into()),
+            };
+            match entry {
+                LogEntry::Write(write) => queue.push_back(write),
+                LogEntry::Done { done } => {
+                    let done = done.min(queue.len());
+                    let _ = queue.drain(..done);
+                }
+            }
+        }
+        self.path = Some(path);
+        self.queue().extend(queue);
+        Ok(self)
+    }
+
+    /// Sets the time between two replays when spawned. Defaults to five
+    /// seconds.
+    #[must_use]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets a hook deciding what happens to a replayed write the server
+    /// rejected, e.g. because of a conflicting record.
+    ///
+    /// Without a hook, rejected writes are skipped.
+    #[must_use]
+    pub fn on_conflict(
+        mut self,
+        f: impl Fn(&BufferedWrite, &Error) -> ConflictAction + Send + Sync + 'static,
+    ) -> Self {
+        self.on_conflict = Some(Arc::new(f));
+        self
+    }
+
+    /// Prepares the write `sql`, which is executed or queued once awaited.
+    pub fn write(&self, sql: impl Into<String>) -> BufferedQuery<'_> {
+        BufferedQuery {
+            buffer: self,
+            sql: sql.into(),
+            bindings: serde_json::Map::new(),
+            error: None,
+        }
+    }
+
+    /// Returns the number of queued writes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.queue().len()
+    }
+
+    /// Indicates whether no writes are queued.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.queue().is_empty()
+    }
+
+    /// Returns the queued writes, oldest first.
+    #[must_use]
+    pub fn pending(&self) -> Vec<BufferedWrite> {
+        self.queue().iter().cloned().collect()
+    }
+
+    /// Replays the queued writes in order.
+    ///
+    /// Replaying stops once the database turns out to be unreachable again
+    /// or the hook set using [`OfflineBuffer::on_conflict()`] returns
+    /// [`ConflictAction::Stop`]. Returns the number of writes executed.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the queue can't be persisted.
+    pub async fn replay(&self) -> Result<usize> {
+        let _replaying = self.shared.replaying.write().await;
+        let mut executed = 0;
+        loop {
+            let Some(write) = self.queue().front().cloned() else {
+                break;
+            };
+            match self.execute(&write).await {
+                Ok(()) => executed += 1,
+                Err(e) if unreachable(&e) => break,
+                Err(e) => {
+                    let action = match &self.on_conflict {
+                        Some(f) => {
+                            hook::catch("on_conflict", || f(&write, &e)).unwrap_or_else(|e| {
+                                if let Ok(pool) = self.pool.get() {
+                                    pool.manager().errors.record(&e);
+                                }
+                                ConflictAction::Skip
+                            })
+                        }
+                        None => ConflictAction::Skip,
+                    };
+                    if action == ConflictAction::Stop {
+                        break;
+                    }
+                }
+            }
+            self.pop_front().await?;
+        }
+        Ok(executed)
+    }
+
+    /// Replays the queue every `interval` until the [`Pool`] is closed or
+    /// dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoRuntimeSpecified`] if neither an [`Executor`] nor
+    /// a [`Runtime`] was configured for the [`Manager`] of the pool.
+    /// Failing replays are recorded in the [`DebugDump`] of the pool and
+    /// don't stop replaying.
+    ///
+    /// [`Executor`]: crate::rt::Executor
+    /// [`Runtime`]: crate::Runtime
+    /// [`Manager`]: crate::Manager
+    /// [`DebugDump`]: crate::diagnostics::DebugDump
+    pub async fn run(self) -> Result<()> {
+        let executor = self
+            .pool
+            .get()?
+            .manager()
+            .background_executor()
+            .ok_or(Error::NoRuntimeSpecified)?;
+        while self.pool.sleep(&*executor, self.interval).await {
+            if !self.is_empty() {
+                if let Err(e) = self.replay().await {
+                    if let Ok(pool) = self.pool.get() {
+                        pool.manager().errors.record(&e);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Replays the queue every `interval` as a background task on the
+    /// [`Executor`] of the pool until the [`Pool`] is closed or dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoRuntimeSpecified`] if neither an [`Executor`] nor
+    /// a [`Runtime`] was configured for the [`Manager`] of the pool.
+    ///
+    /// [`Executor`]: crate::rt::Executor
+    /// [`Runtime`]: crate::Runtime
+    /// [`Manager`]: crate::Manager
+    pub fn spawn(self) -> Result<()> {
+        let pool = self.pool.clone();
+        let executor = pool
+            .get()?
+            .manager()
+            .background_executor()
+            .ok_or(Error::NoRuntimeSpecified)?;
+        executor.spawn(Box::pin(hook::task(pool, "OfflineBuffer", async move {
+            let _ = self.run().await;
+        })));
+        Ok(())
+    }
+
+    async fn execute(&self, write: &BufferedWrite) -> Result<()> {
+        let conn = checkout::get(&self.pool.get()?).await?;
+        let mut query = conn.query(write.sql.clone());
+        if !write.bindings.is_null() {
+            query = query.bind(write.bindings.clone());
+        }
+        let _ = query.await?.check()?;
+        Ok(())
+    }
+
+    async fn enqueue(&self, write: BufferedWrite) -> Result<()> {
+        let _log = self.shared.log.lock().await;
+        if self.len() >= self.capacity {
+            return Err(Error::BufferFull(self.capacity));
+        }
+        self.append(&LogEntry::Write(write.clone())).await?;
+        self.queue().push_back(write);
+        Ok(())
+    }
+
+    /// Removes the oldest write from the queue.
+    async fn pop_front(&self) -> Result<()> {
+        let _log = self.shared.log.lock().await;
+        let _ = self.queue().pop_front();
+        if self.is_empty() {
+            self.truncate().await
+        } else {
+            self.append(&LogEntry::Done { done: 1 }).await
+        }
+    }
+
+    /// Appends `entry` to the file the queue is persisted in, if any.
+    async fn append(&self, entry: &LogEntry) -> Result<()> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.blocking(move || {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            file.write_all(&line)
+        })
+        .await
+    }
+
+    /// Empties the file the queue is persisted in, if any.
+    async fn truncate(&self) -> Result<()> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+        self.blocking(move || fs::File::create(path).map(drop))
+            .await
+    }
+
+    /// Runs the file operation `f` on a thread where blocking is acceptable
+    /// if the pool has a [`Runtime`](crate::Runtime), right away otherwise.
+    async fn blocking<F>(&self, f: F) -> Result<()>
+    where
+        F: FnOnce() -> io::Result<()> + Send + 'static,
+    {
+        let runtime = self.pool.get().ok().and_then(|pool| pool.manager().runtime);
+        match runtime {
+            Some(runtime) => runtime
+                .spawn_blocking(f)
+                .await
+                .map_err(|e| io::Error::other(e.to_string()))??,
+            None => f()?,
+        }
+        Ok(())
+    }
+
+    fn queue(&self) -> MutexGuard<'_, VecDeque<BufferedWrite>> {
+        self.shared
+            .queue
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for OfflineBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OfflineBuffer")
+            .field("pool", &self.pool)
+            .field("capacity", &self.capacity)
+            .field("interval", &self.interval)
+            .field("path", &self.path)
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Write which is executed or queued by an [`OfflineBuffer`] once awaited.
+#[must_use = "writes do nothing unless awaited"]
+#[derive(Debug)]
+pub struct BufferedQuery<'a> {
+    buffer: &'a OfflineBuffer,
+    sql: String,
+    bindings: serde_json::Map<String, serde_json::Value>,
+    error: Option<serde_json::Error>,
+}
+
+impl BufferedQuery<'_> {
+    /// Binds the parameter `key` to `value`.
+    ///
+    /// Values are converted to JSON, so they can be persisted.
+    ///
+    /// Awaiting the write fails if `value` can't be converted to JSON.
+    pub fn bind(mut self, (key, value): (impl Into<String>, impl Serialize)) -> Self {
+        match serde_json::to_value(value) {
+            Ok(value) => {
+                let _ = self.bindings.insert(key.into(), value);
+            }
+            Err(e) => {
+                let _ = self.error.get_or_insert(e);
+            }
+        }
+        self
+    }
+
+    async fn run(self) -> Result<WriteOutcome> {
+        if let Some(e) = self.error {
+            return Err(e.into());
+        }
+        let write = BufferedWrite {
+            sql: self.sql,
+            bindings: match self.bindings.is_empty() {
+                true => serde_json::Value::Null,
+                false => serde_json::Value::Object(self.bindings),
+            },
+        };
+        let buffer = self.buffer;
+        // A replay must not empty the queue between checking it and
+        // executing the write, or the write could overtake queued ones.
+        let _replaying = buffer.shared.replaying.read().await;
+        // Keep the order of the writes until the queue has been replayed.
+        if !buffer.is_empty() {
+            buffer.enqueue(write).await?;
+            return Ok(WriteOutcome::Buffered);
+        }
+        match buffer.execute(&write).await {
+            Ok(()) => Ok(WriteOutcome::Written),
+            Err(e) if unreachable(&e) => {
+                buffer.enqueue(write).await?;
+                Ok(WriteOutcome::Buffered)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<'a> std::future::IntoFuture for BufferedQuery<'a> {
+    type Output = Result<WriteOutcome>;
+    type IntoFuture = futures::future::BoxFuture<'a, Result<WriteOutcome>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.run())
+    }
+}
+
+/// Indicates whether `e` means that the database is unreachable.
+fn unreachable(e: &Error) -> bool {
+    matches!(e.kind(), ErrorKind::Connection | ErrorKind::Timeout)
+}
@@ -0,0 +1,288 @@
+use std::{fmt, marker::PhantomData, str::FromStr};
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{checkout, surql, Error, Pool, Result};
+
+/// Kinds of sort keys which don't survive a round trip through JSON, along
+/// with the cast restoring them.
+const CASTS: &[(&str, &str)] = &[
+    ("record", "type::record"),
+    ("datetime", "<datetime>"),
+    ("uuid", "<uuid>"),
+    ("duration", "<duration>"),
+    ("decimal", "<decimal>"),
+];
+
+/// Opaque token pointing behind the last row of a [`Page`].
+///
+/// The cursor holds the sort key of that row, so pages stay stable while
+/// rows are inserted or removed before it.
+///
+/// Cursors can be sent to clients as a string using the [`Display`]
+/// implementation and parsed back using [`FromStr`].
+///
+/// [`Display`]: fmt::Display
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Cursor {
+    /// Kind of the sort key, either `value` or one of the `CASTS`
+    kind: String,
+    /// Sort key of the last row as JSON, converted to a string for the
+    /// kinds in `CASTS`
+    key: String,
+}
+
+impl Cursor {
+    fn new(kind: String, key: &serde_json::Value) -> Self {
+        Self {
+            kind,
+            key: key.to_string(),
+        }
+    }
+
+    /// Returns the expression turning `param` back into the sort key.
+    fn cast(&self, param: &str) -> String {
+        match CASTS.iter().find(|(kind, _)| *kind == self.kind) {
+            Some((_, cast)) if cast.starts_with('<') => format!("{} {}", cast, param),
+            Some((_, function)) => format!("{}({})", function, param),
+            None => param.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("k")?;
+        for byte in serde_json::json!([self.kind, self.key]).to_string().bytes() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidCursor(s.to_string());
+        let hex = s.strip_prefix('k').ok_or_else(invalid)?;
+        if hex.len() % 2 != 0 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+        let (kind, key): (String, String) =
+            serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+        let known = kind == "value" || CASTS.iter().any(|(k, _)| *k == kind);
+        if !known || serde_json::from_str::<serde_json::Value>(&key).is_err() {
+            return Err(invalid());
+        }
+        Ok(Self { kind, key })
+    }
+}
+
+/// Single page of rows returned by a [`Paginator`].
+#[derive(Clone, Debug)]
+pub struct Page<T> {
+    /// Rows of this page
+    pub items: Vec<T>,
+    /// Cursor of the next page or `None` if this is the last page
+    pub next: Option<Cursor>,
+}
+
+/// Result of the query fetching a single page.
+#[derive(Deserialize)]
+#[serde(bound = "T: DeserializeOwned")]
+struct Fetched<T> {
+    rows: Vec<T>,
+    key: Option<(String, serde_json::Value)>,
+}
+
+/// Splits the results of a `SELECT` query into pages of a fixed size.
+///
+/// Pages are fetched using keyset pagination: the query must end with an
+/// `ORDER BY` clause sorting by a single field of the queried table, and
+/// each page continues after the value of this field in the last row of
+/// the previous one. Rows sharing the sort key of the last row of a page
+/// are skipped, so the field should be unique, e.g. `id`. The query must
+/// not contain `LIMIT`, `START`, `GROUP BY` or `SPLIT` clauses.
+///
+/// ```rust,ignore
+/// let paginator = Paginator::<User>::new(&pool, "SELECT * FROM user ORDER BY id", 50)?;
+/// let page = paginator.page(cursor.as_ref()).await?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct Paginator<T> {
+    pool: Pool,
+    /// Query up to its `WHERE` or `ORDER BY` clause
+    select: String,
+    /// Targets of the query, i.e. everything between `FROM` and its
+    /// `WHERE` or `ORDER BY` clause
+    targets: String,
+    /// Condition of the `WHERE` clause of the query
+    condition: Option<String>,
+    key: String,
+    descending: bool,
+    page_size: usize,
+    _row: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Paginator<T> {
+    /// Creates a new [`Paginator`] for the given `query`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidQuery`] if `query` isn't a single `SELECT`
+    /// statement ending with an `ORDER BY` clause on a single field, or
+    /// contains clauses conflicting with pagination.
+    pub fn new(pool: &Pool, query: impl Into<String>, page_size: usize) -> Result<Self> {
+        let query: String = query.into();
+        let invalid = |reason: &str| Error::InvalidQuery(format!("{}: {}", reason, query));
+        let statements = surql::split(&query);
+        let [statement] = statements.as_slice() else {
+            return Err(invalid("paginated queries must be a single statement"));
+        };
+        let words = surql::top_level_words(statement);
+        let position = |keyword: &str| words.iter().position(|(_, w)| w == keyword);
+        if words.first().map(|(_, w)| w.as_str()) != Some("SELECT") {
+            return Err(invalid("paginated queries must be SELECT statements"));
+        }
+        let from = position("FROM").ok_or_else(|| invalid("query has no FROM clause"))?;
+        let order = position("ORDER")
+            .filter(|i| words.get(i + 1).is_some_and(|(_, w)| w == "BY"))
+            .ok_or_else(|| invalid("paginated queries require an ORDER BY clause"))?;
+        if words[from..order]
+            .iter()
+            .any(|(_, w)| matches!(w.as_str(), "GROUP" | "SPLIT" | "LIMIT" | "START"))
+        {
+            return Err(invalid(
+                "paginated queries can't be grouped, split or limited",
+            ));
+        }
+        let mut ordering = statement[words[order + 1].0 + 2..].split_whitespace();
+        let key = ordering.next().unwrap_or_default().to_string();
+        let descending = match ordering.next().map(str::to_ascii_uppercase).as_deref() {
+            None | Some("ASC") => false,
+            Some("DESC") => true,
+            Some(_) => return Err(invalid("paginated queries must order by a single field")),
+        };
+        let is_field = !key.is_empty()
+            && key.split('.').all(|part| {
+                !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            });
+        if !is_field || ordering.next().is_some() {
+            return Err(invalid("paginated queries must order by a single field"));
+        }
+        let (end, condition) = match position("WHERE").filter(|i| *i > from && *i < order) {
+            Some(i) => (
+                words[i].0,
+                Some(statement[words[i].0 + 5..words[order].0].trim().to_string()),
+            ),
+            None => (words[order].0, None),
+        };
+        Ok(Self {
+            pool: pool.clone(),
+            select: statement[..end].trim_end().to_string(),
+            targets: statement[words[from].0 + 4..end].trim().to_string(),
+            condition,
+            key,
+            descending,
+            page_size: page_size.max(1),
+            _row: PhantomData,
+        })
+    }
+
+    /// Fetches the first page.
+    pub async fn first(&self) -> Result<Page<T>> {
+        self.page(None).await
+    }
+
+    /// Fetches the page following the given `cursor` or the first page if
+    /// no cursor is given.
+    pub async fn page(&self, cursor: Option<&Cursor>) -> Result<Page<T>> {
+        let after = match cursor {
+            Some(cursor) => {
+                let key: serde_json::Value = serde_json::from_str(&cursor.key)
+                    .map_err(|_| Error::InvalidCursor(cursor.to_string()))?;
+                Some((cursor.cast("$__after"), key))
+            }
+            None => None,
+        };
+        let sql = self.sql(after.as_ref().map(|(cast, _)| cast.as_str()));
+        let conn = checkout::get(&self.pool).await?;
+        // Fetch one additional row to find out whether there is a next page.
+        let mut query = conn
+            .query(sql)
+            .bind(("__limit", self.page_size + 1))
+            .bind(("__size", self.page_size));
+        if let Some((_, key)) = after {
+            query = query.bind(("__after", key));
+        }
+        let fetched: Option<Fetched<T>> = query.await?.take(0)?;
+        let Fetched { mut rows, key } = fetched
+            .ok_or_else(|| Error::InvalidQuery(format!("{} returned no page", self.select)))?;
+        let next = if rows.len() > self.page_size {
+            rows.truncate(self.page_size);
+            match key {
+                Some((kind, key)) if !key.is_null() => Some(Cursor::new(kind, &key)),
+                _ => {
+                    return Err(Error::InvalidQuery(format!(
+                        "rows of {} have no `{}` field to continue after",
+                        self.select, self.key
+                    )))
+                }
+            }
+        } else {
+            None
+        };
+        Ok(Page { items: rows, next })
+    }
+
+    /// Returns the query fetching the rows of a page together with the
+    /// sort key of its last row, continuing after the sort key `after`.
+    ///
+    /// Both are fetched by a single statement, so they are consistent with
+    /// each other.
+    fn sql(&self, after: Option<&str>) -> String {
+        let (op, direction) = if self.descending {
+            ("<", "DESC")
+        } else {
+            (">", "ASC")
+        };
+        let filter = match (&self.condition, after) {
+            (None, None) => String::new(),
+            (Some(condition), None) => format!(" WHERE {}", condition),
+            (None, Some(after)) => format!(" WHERE {} {} {}", self.key, op, after),
+            (Some(condition), Some(after)) => {
+                format!(" WHERE ({}) AND {} {} {}", condition, self.key, op, after)
+            }
+        };
+        let order = format!("ORDER BY {} {}", self.key, direction);
+        let tagged: String = CASTS
+            .iter()
+            .map(|(kind, _)| {
+                format!(
+                    "IF type::is::{kind}({key}) THEN ['{kind}', <string> {key}] ELSE ",
+                    kind = kind,
+                    key = self.key
+                )
+            })
+            .collect();
+        format!(
+            "RETURN {{ \
+                rows: ({select}{filter} {order} LIMIT $__limit), \
+                key: array::last((SELECT VALUE ({tagged}['value', {key}] END) \
+                    FROM {targets}{filter} {order} LIMIT $__size)) \
+            }}",
+            select = self.select,
+            filter = filter,
+            order = order,
+            tagged = tagged,
+            key = self.key,
+            targets = self.targets,
+        )
+    }
+}
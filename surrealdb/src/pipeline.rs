@@ -0,0 +1,162 @@
+use std::sync::Arc;
+
+use surrealdb::{opt::QueryResult, Response};
+
+use crate::{client::Binding, surql, Error, Pool, PoolExt, Result};
+
+/// Several statements sent as a single query using a single connection.
+///
+/// Results are indexed by the order in which the statements were added. If
+/// an added statement consists of several statements, the result of the last
+/// one is used.
+///
+/// Bindings are shared by all statements, so statements must use distinct
+/// names for differing parameters.
+///
+/// ```rust,ignore
+/// let mut results = Pipeline::new()
+///     .statement("SELECT * FROM type::thing('user', $id)")
+///     .statement("SELECT count() FROM post WHERE author = type::thing('user', $id) GROUP ALL")
+///     .bind(("id", "tobie"))
+///     .run(&pool)
+///     .await?;
+/// let user: Option<User> = results.take(0)?;
+/// ```
+#[derive(Clone, Default)]
+pub struct Pipeline {
+    statements: Vec<String>,
+    bindings: Vec<Binding>,
+}
+
+impl Pipeline {
+    /// Creates a new empty [`Pipeline`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a statement.
+    ///
+    /// The statement must have a result, so [`Pipeline::run()`] rejects
+    /// statements which are empty or consist only of comments or
+    /// transaction statements like `BEGIN`.
+    #[must_use]
+    pub fn statement(mut self, statement: impl Into<String>) -> Self {
+        self.statements.push(statement.into());
+        self
+    }
+
+    /// Binds parameters to all statements.
+    ///
+    /// See [`Query::bind()`] for details.
+    ///
+    /// [`Query::bind()`]: crate::client::Query::bind
+    #[must_use]
+    pub fn bind(mut self, bindings: impl serde::Serialize + Send + Sync + 'static) -> Self {
+        self.bindings.push(Binding(Arc::new(bindings)));
+        self
+    }
+
+    /// Returns the number of statements added.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.statements.len()
+    }
+
+    /// Returns `true` if no statements were added.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    /// Runs all statements using a single pooled connection.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidQuery`] without running any statement if one
+    /// of them has no result, as the results of the following statements
+    /// couldn't be told apart otherwise.
+    pub async fn run(self, pool: &Pool) -> Result<PipelineResults> {
+        let mut sql = String::new();
+        let mut indexes = Vec::with_capacity(self.statements.len());
+        let mut count = 0;
+        for (i, statement) in self.statements.iter().enumerate() {
+            // Count the results like the server does: neither comments nor
+            // transaction statements have results.
+            let statements = surql::split(statement);
+            let results = statements
+                .iter()
+                .filter(|s| !surql::TRANSACTION_STATEMENTS.contains(&surql::keyword(s).as_str()))
+                .count();
+            if results == 0 {
+                return Err(Error::InvalidQuery(format!(
+                    "statement {} of the pipeline has no result: {:?}",
+                    i, statement
+                )));
+            }
+            count += results;
+            indexes.push(count - 1);
+            // Comments are stripped by the split, so a trailing line comment
+            // can't swallow the separator.
+            for statement in statements {
+                sql.push_str(&statement);
+                sql.push_str(";\n");
+            }
+        }
+        let response = pool.query(sql).bindings(self.bindings).await?;
+        Ok(PipelineResults { response, indexes })
+    }
+}
+
+impl std::fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("statements", &self.statements)
+            .field("bindings", &self.bindings.len())
+            .finish()
+    }
+}
+
+/// Results of a [`Pipeline`].
+#[derive(Debug)]
+pub struct PipelineResults {
+    response: Response,
+    indexes: Vec<usize>,
+}
+
+impl PipelineResults {
+    /// Takes the result of the statement added at `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the statement if it failed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn take<R>(&mut self, index: usize) -> Result<R>
+    where
+        usize: QueryResult<R>,
+    {
+        Ok(self.response.take(self.indexes[index])?)
+    }
+
+    /// Returns the number of results.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.indexes.len()
+    }
+
+    /// Returns `true` if the pipeline contained no statements.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.indexes.is_empty()
+    }
+
+    /// Returns the underlying [`Response`] which contains the results of all
+    /// statements.
+    #[must_use]
+    pub fn into_response(self) -> Response {
+        self.response
+    }
+}
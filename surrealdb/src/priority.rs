@@ -0,0 +1,123 @@
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use tokio::sync::Notify;
+
+use crate::{checkout, Object, Pool, Result};
+
+/// Class of operations a connection is checked out for.
+///
+/// See [`PoolExt::get_for()`] for how classes are prioritized.
+///
+/// [`PoolExt::get_for()`]: crate::PoolExt::get_for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OperationClass {
+    /// Queries which only read data
+    Read,
+    /// Queries which write data
+    Write,
+    /// Administrative tasks like migrations or backups
+    Admin,
+}
+
+/// Number of connections currently checked out for reads.
+#[derive(Debug, Default)]
+pub(crate) struct Reservation {
+    reads: Mutex<usize>,
+    released: Notify,
+}
+
+impl Reservation {
+    /// Waits until fewer than `limit` connections are checked out for reads.
+    async fn acquire(self: &Arc<Self>, limit: impl Fn() -> usize) -> ReadPermit {
+        loop {
+            let released = self.released.notified();
+            {
+                let mut reads = self.reads.lock().unwrap_or_else(PoisonError::into_inner);
+                if *reads < limit() {
+                    *reads += 1;
+                    return ReadPermit(self.clone());
+                }
+            }
+            released.await;
+        }
+    }
+}
+
+/// Slot of a connection checked out for reads, released once dropped.
+#[derive(Debug)]
+struct ReadPermit(Arc<Reservation>);
+
+impl Drop for ReadPermit {
+    fn drop(&mut self) {
+        *self.0.reads.lock().unwrap_or_else(PoisonError::into_inner) -= 1;
+        self.0.released.notify_one();
+    }
+}
+
+/// Retrieves a connection for operations of the given `class`.
+pub(crate) async fn get(pool: &Pool, class: OperationClass) -> Result<PriorityObject> {
+    let manager = pool.manager();
+    let reserved = manager.config.reserved_for_writes as usize;
+    let (permit, conn) = match class {
+        OperationClass::Read if reserved > 0 => {
+            // At least one connection is always left for reads.
+            let limit = || pool.status().max_size.saturating_sub(reserved).max(1);
+            let (permit, conn) =
+                checkout::gated_get(pool, manager.reservation.acquire(limit)).await?;
+            (Some(permit), conn)
+        }
+        _ => (None, checkout::get(pool).await?),
+    };
+    Ok(PriorityObject {
+        conn,
+        class,
+        _permit: permit,
+    })
+}
+
+/// Connection checked out using [`PoolExt::get_for()`].
+///
+/// It dereferences to the pooled [`Object`] and, if it was checked out for
+/// reads, releases its read slot once dropped.
+///
+/// [`PoolExt::get_for()`]: crate::PoolExt::get_for
+pub struct PriorityObject {
+    conn: Object,
+    class: OperationClass,
+    _permit: Option<ReadPermit>,
+}
+
+impl PriorityObject {
+    /// Returns the class of operations this connection was checked out for.
+    #[must_use]
+    pub fn class(&self) -> OperationClass {
+        self.class
+    }
+}
+
+impl Deref for PriorityObject {
+    type Target = Object;
+
+    fn deref(&self) -> &Object {
+        &self.conn
+    }
+}
+
+impl DerefMut for PriorityObject {
+    fn deref_mut(&mut self) -> &mut Object {
+        &mut self.conn
+    }
+}
+
+impl fmt::Debug for PriorityObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PriorityObject")
+            .field("conn", &self.conn)
+            .field("class", &self.class)
+            .finish()
+    }
+}
@@ -0,0 +1,204 @@
+use std::{fmt, future::Future, marker::PhantomData};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{checkout, Pool, Result};
+
+/// Derives [`SurrealRecord`] for a struct.
+///
+/// The table is set using `#[table = "user"]` and defaults to the name of
+/// the struct in snake case.
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+pub use deadpool_surrealdb_macros::SurrealRecord;
+
+/// Typed access to the records of a single table.
+///
+/// Every method checks out a connection from the [`Pool`] for the duration
+/// of a single query. Ids are the ids of the records without the table name.
+///
+/// Records are deserialized including their `id` field, so `T` should either
+/// ignore it or declare it as [`surrealdb::RecordId`].
+///
+/// ```rust,ignore
+/// let users = Repo::<User>::new(&pool, "user");
+/// let user = users.create_with_id("tobie", User { name: "Tobie".into() }).await?;
+/// assert_eq!(users.get("tobie").await?, Some(user));
+/// ```
+pub struct Repo<T> {
+    pool: Pool,
+    table: String,
+    _record: PhantomData<fn() -> T>,
+}
+
+impl<T> Repo<T>
+where
+    T: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    /// Creates a new [`Repo`] for records of `table`.
+    pub fn new(pool: &Pool, table: impl Into<String>) -> Self {
+        Self {
+            pool: pool.clone(),
+            table: table.into(),
+            _record: PhantomData,
+        }
+    }
+
+    /// Returns the name of the table.
+    #[must_use]
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// Creates a record with a generated id.
+    pub async fn create(&self, record: T) -> Result<T> {
+        self.create_record("type::table($table)", None, record)
+            .await
+    }
+
+    /// Creates a record with the given `id`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a record with the same id exists already.
+    pub async fn create_with_id(&self, id: impl Into<String>, record: T) -> Result<T> {
+        self.create_record("type::thing($table, $id)", Some(id.into()), record)
+            .await
+    }
+
+    /// Returns the record with the given `id`, if it exists.
+    pub async fn get(&self, id: impl Into<String>) -> Result<Option<T>> {
+        self.one("SELECT * FROM type::thing($table, $id)", id.into(), None)
+            .await
+    }
+
+    /// Replaces the content of the record with the given `id`.
+    ///
+    /// Returns the updated record or `None` if it doesn't exist.
+    pub async fn update(&self, id: impl Into<String>, record: T) -> Result<Option<T>> {
+        self.one(
+            "UPDATE type::thing($table, $id) CONTENT $record",
+            id.into(),
+            Some(record),
+        )
+        .await
+    }
+
+    /// Deletes the record with the given `id`.
+    ///
+    /// Returns the deleted record or `None` if it didn't exist.
+    pub async fn delete(&self, id: impl Into<String>) -> Result<Option<T>> {
+        self.one(
+            "DELETE type::thing($table, $id) RETURN BEFORE",
+            id.into(),
+            None,
+        )
+        .await
+    }
+
+    /// Returns all records of the table.
+    pub async fn list(&self) -> Result<Vec<T>> {
+        let conn = checkout::get(&self.pool).await?;
+        Ok(conn
+            .query("SELECT * FROM type::table($table)")
+            .bind(("table", self.table.clone()))
+            .await?
+            .take(0)?)
+    }
+
+    async fn create_record(
+        &self,
+        target: &str,
+        id: Option<String>,
+        record: impl Serialize + Send + Sync + 'static,
+    ) -> Result<T> {
+        let conn = checkout::get(&self.pool).await?;
+        let mut records: Vec<T> = conn
+            .query(format!("CREATE {} CONTENT $record", target))
+            .bind(("table", self.table.clone()))
+            .bind(("id", id))
+            .bind(("record", record))
+            .await?
+            .check()?
+            .take(0)?;
+        // CREATE fails instead of returning nothing.
+        Ok(records.remove(0))
+    }
+
+    async fn one(&self, sql: &str, id: String, record: Option<T>) -> Result<Option<T>> {
+        let conn = checkout::get(&self.pool).await?;
+        let mut query = conn
+            .query(sql)
+            .bind(("table", self.table.clone()))
+            .bind(("id", id));
+        if let Some(record) = record {
+            query = query.bind(("record", record));
+        }
+        Ok(query.await?.check()?.take(0)?)
+    }
+}
+
+impl<T> Clone for Repo<T> {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            table: self.table.clone(),
+            _record: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Repo<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Repo")
+            .field("pool", &self.pool)
+            .field("table", &self.table)
+            .finish()
+    }
+}
+
+/// Record type bound to a table.
+///
+/// Implement it using `#[derive(SurrealRecord)]` of the `macros` feature.
+/// Just like [`Repo`], every method checks out a connection from the
+/// [`Pool`] for the duration of a single query.
+///
+/// ```rust,ignore
+/// #[derive(Deserialize, Serialize, SurrealRecord)]
+/// #[table = "user"]
+/// struct User {
+///     name: String,
+/// }
+///
+/// let user = User::create(&pool, &User { name: "Tobie".into() }).await?;
+/// let tobie = User::find(&pool, "tobie").await?;
+/// ```
+pub trait SurrealRecord: Serialize + DeserializeOwned + Send + Sync + 'static {
+    /// Name of the table
+    const TABLE: &'static str;
+
+    /// Returns a [`Repo`] for the table of this record type.
+    fn repo(pool: &Pool) -> Repo<Self> {
+        Repo::new(pool, Self::TABLE)
+    }
+
+    /// Returns the record with the given `id`, if it exists.
+    fn find(
+        pool: &Pool,
+        id: impl Into<String>,
+    ) -> impl Future<Output = Result<Option<Self>>> + Send {
+        let repo = Self::repo(pool);
+        let id = id.into();
+        async move { repo.get(id).await }
+    }
+
+    /// Creates `record` with a generated id.
+    fn create(pool: &Pool, record: &Self) -> impl Future<Output = Result<Self>> + Send {
+        let repo = Self::repo(pool);
+        let content = serde_json::to_value(record);
+        async move {
+            repo.create_record("type::table($table)", None, content?)
+                .await
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::{fmt, sync::Arc};
+
+use tokio::sync::OnceCell;
+
+use crate::{checkout, Object, Pool, Result};
+
+/// Connection shared by all data-access calls handling a single request.
+///
+/// The connection is checked out on the first call of [`RequestConn::get()`]
+/// and returned to the [`Pool`] once all clones are dropped, usually at the
+/// end of the request. Handlers which don't touch the database don't check
+/// out a connection at all, while handlers making several calls check out
+/// only one.
+///
+/// Web frameworks usually carry it in the extensions of the request, see
+/// [`PoolLayer::per_request()`] and the `RequestConn` extractor of the
+/// `axum` integration:
+///
+/// ```rust,ignore
+/// async fn handler(conn: RequestConn) -> Result<Json<Vec<Product>>, DbError> {
+///     let user = users::current(&conn).await?;
+///     let products = products::for_user(&conn, &user).await?;
+///     Ok(Json(products))
+/// }
+/// ```
+///
+/// [`PoolLayer::per_request()`]: crate::tower::PoolLayer::per_request
+#[derive(Clone)]
+pub struct RequestConn(Arc<Inner>);
+
+struct Inner {
+    pool: Pool,
+    conn: OnceCell<Object>,
+}
+
+impl RequestConn {
+    /// Creates a new [`RequestConn`] checking out of `pool` on first use.
+    #[must_use]
+    pub fn new(pool: &Pool) -> Self {
+        Self(Arc::new(Inner {
+            pool: pool.clone(),
+            conn: OnceCell::new(),
+        }))
+    }
+
+    /// Returns the connection of this request, checking it out first if
+    /// this is the first call.
+    ///
+    /// # Errors
+    ///
+    /// Fails if no connection can be checked out. The next call tries
+    /// again.
+    pub async fn get(&self) -> Result<&Object> {
+        self.0
+            .conn
+            .get_or_try_init(|| checkout::get(&self.0.pool))
+            .await
+    }
+
+    /// Returns whether a connection was checked out for this request.
+    #[must_use]
+    pub fn is_checked_out(&self) -> bool {
+        self.0.conn.initialized()
+    }
+
+    /// Returns the [`Pool`] connections are checked out of.
+    #[must_use]
+    pub fn pool(&self) -> &Pool {
+        &self.0.pool
+    }
+}
+
+impl fmt::Debug for RequestConn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestConn")
+            .field("conn", &self.0.conn.get())
+            .finish()
+    }
+}
@@ -0,0 +1,132 @@
+use std::{future::Future, time::Duration};
+
+use crate::{rt, Error, Result, Runtime};
+
+/// Limits on retrying an operation: the number of attempts, the duration of
+/// every single attempt and the duration of all attempts together, plus an
+/// optional exponential backoff between attempts.
+///
+/// Budgets are used for creating connections (see
+/// [`Manager::create_retry()`]) and for queries run on pooled connections
+/// (see [`PoolQuery::retry()`]). The deadline of a query covers checking out
+/// connections as well, so retries of creating connections within retried
+/// queries can't multiply into minute-long hangs.
+///
+/// Enforcing timeouts and backing off requires a [`Runtime`] set for the
+/// [`Manager`].
+///
+/// ```rust,ignore
+/// let budget = RetryBudget::new(3)
+///     .attempt_timeout(Duration::from_secs(2))
+///     .deadline(Duration::from_secs(5));
+/// let response = pool.query("SELECT * FROM product").retry(budget).await?;
+/// ```
+///
+/// [`Manager`]: crate::Manager
+/// [`Manager::create_retry()`]: crate::Manager::create_retry
+/// [`PoolQuery::retry()`]: crate::client::PoolQuery::retry
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryBudget {
+    attempts: u32,
+    attempt_timeout: Option<Duration>,
+    deadline: Option<Duration>,
+    backoff: Option<Duration>,
+}
+
+impl RetryBudget {
+    /// Creates a new [`RetryBudget`] of up to `attempts` attempts, including
+    /// the first one, without any timeouts.
+    #[must_use]
+    pub fn new(attempts: u32) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            attempt_timeout: None,
+            deadline: None,
+            backoff: None,
+        }
+    }
+
+    /// Sets the maximum duration of a single attempt.
+    #[must_use]
+    pub fn attempt_timeout(mut self, timeout: Duration) -> Self {
+        self.attempt_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the maximum duration of all attempts together.
+    #[must_use]
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Waits `initial` before the second attempt, doubling the wait before
+    /// every further attempt. Without a backoff, attempts follow each other
+    /// right away.
+    #[must_use]
+    pub fn backoff(mut self, initial: Duration) -> Self {
+        self.backoff = Some(initial);
+        self
+    }
+
+    /// Limits the budget to a single attempt, keeping its timeouts.
+    pub(crate) fn once(self) -> Self {
+        Self {
+            attempts: 1,
+            ..self
+        }
+    }
+
+    /// Runs `attempt` until it succeeds, fails with an error `retryable`
+    /// returns `false` for or the budget is exhausted.
+    ///
+    /// Exceeding a timeout fails with the error returned by `timed_out`.
+    pub(crate) async fn run<T, F, Fut>(
+        &self,
+        runtime: Option<Runtime>,
+        retryable: impl Fn(&Error) -> bool,
+        timed_out: impl Fn(Duration) -> Error,
+        mut attempt: F,
+    ) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let attempts = async {
+            let mut remaining = self.attempts;
+            let mut backoff = self.backoff;
+            loop {
+                remaining -= 1;
+                let result = match self.attempt_timeout {
+                    Some(timeout) => {
+                        let runtime = runtime.ok_or(Error::NoRuntimeSpecified)?;
+                        match runtime.timeout(timeout, attempt()).await {
+                            Some(result) => result,
+                            None => Err(timed_out(timeout)),
+                        }
+                    }
+                    None => attempt().await,
+                };
+                match result {
+                    Err(e) if remaining > 0 && retryable(&e) => {}
+                    result => return result,
+                }
+                if let Some(delay) = backoff {
+                    let runtime = runtime.ok_or(Error::NoRuntimeSpecified)?;
+                    rt::sleep(runtime, delay).await;
+                    backoff = Some(delay.saturating_mul(2));
+                }
+            }
+        };
+        match self.deadline {
+            Some(deadline) => {
+                let runtime = runtime.ok_or(Error::NoRuntimeSpecified)?;
+                match runtime.timeout(deadline, attempts).await {
+                    Some(result) => result,
+                    None => Err(timed_out(deadline)),
+                }
+            }
+            None => attempts.await,
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::ops::{Deref, DerefMut};
+
+use ::rocket::{
+    fairing::{self, Fairing, Info, Kind},
+    http::Status,
+    request::{FromRequest, Outcome, Request},
+    Build, Rocket,
+};
+
+use crate::{checkout, Config, Error, Object, Pool, Runtime};
+
+/// Default key of the configuration within the `databases` table.
+pub const DEFAULT_NAME: &str = "surrealdb";
+
+/// Fairing creating the [`Pool`] from the configuration of Rocket and
+/// adding it to the managed state.
+///
+/// The [`Config`] is read from `databases.<name>`, e.g. in `Rocket.toml`:
+///
+/// ```toml
+/// [default.databases.surrealdb]
+/// host = "ws://localhost:8000"
+/// ns = "app"
+/// db = "app"
+/// creds = { Root = { user = "root", pass = "root" } }
+/// ```
+///
+/// ```rust,ignore
+/// rocket::build().attach(PoolFairing::default()).mount("/", routes![index])
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct PoolFairing {
+    name: &'static str,
+}
+
+impl PoolFairing {
+    /// Creates a new [`PoolFairing`] reading its configuration from
+    /// `databases.<name>`.
+    #[must_use]
+    pub fn new(name: &'static str) -> Self {
+        Self { name }
+    }
+}
+
+impl Default for PoolFairing {
+    fn default() -> Self {
+        Self::new(DEFAULT_NAME)
+    }
+}
+
+#[::rocket::async_trait]
+impl Fairing for PoolFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "SurrealDB Pool",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        let key = format!("databases.{}", self.name);
+        let config: Config = match rocket.figment().extract_inner(&key) {
+            Ok(config) => config,
+            Err(e) => {
+                ::rocket::error!("Invalid SurrealDB configuration in `{}`: {}", key, e);
+                return Err(rocket);
+            }
+        };
+        match config.create_pool(Some(Runtime::Tokio1)) {
+            Ok(pool) => Ok(rocket.manage(pool)),
+            Err(e) => {
+                ::rocket::error!("Failed to create SurrealDB pool: {}", e);
+                Err(rocket)
+            }
+        }
+    }
+}
+
+/// Request guard checking a connection out of the [`Pool`] managed by
+/// [`PoolFairing`].
+///
+/// ```rust,ignore
+/// #[get("/")]
+/// async fn index(conn: DbConn) -> Option<String> {
+///     conn.server_version().await.ok()
+/// }
+/// ```
+///
+/// Failed checkouts fail the request with the status of
+/// [`Error::http_status()`].
+#[derive(Debug)]
+pub struct DbConn(pub Object);
+
+#[::rocket::async_trait]
+impl<'r> FromRequest<'r> for DbConn {
+    type Error = Error;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Error> {
+        let Some(pool) = req.rocket().state::<Pool>() else {
+            return Outcome::Error((
+                Status::InternalServerError,
+                Error::Connection {
+                    message: "No pool managed by Rocket".into(),
+                    context: None,
+                    source: None,
+                },
+            ));
+        };
+        match checkout::get(pool).await {
+            Ok(conn) => Outcome::Success(Self(conn)),
+            Err(e) => Outcome::Error((Status::new(e.http_status()), e)),
+        }
+    }
+}
+
+impl Deref for DbConn {
+    type Target = Object;
+
+    fn deref(&self) -> &Object {
+        &self.0
+    }
+}
+
+impl DerefMut for DbConn {
+    fn deref_mut(&mut self) -> &mut Object {
+        &mut self.0
+    }
+}
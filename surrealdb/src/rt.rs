@@ -0,0 +1,95 @@
+use std::{
+    fmt,
+    future::{pending, Future},
+    sync::Arc,
+    time::Duration,
+};
+
+use futures::future::BoxFuture;
+
+use crate::Runtime;
+
+/// Spawns background tasks.
+pub trait Spawn: Send + Sync {
+    /// Runs `future` as a detached task.
+    fn spawn(&self, future: BoxFuture<'static, ()>);
+}
+
+/// Provides timers for background tasks.
+pub trait Sleep: Send + Sync {
+    /// Returns a future which completes after `duration`.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// Executor running the background tasks of the crate, like the
+/// [`BackupScheduler`].
+///
+/// It is implemented for every type implementing [`Spawn`] and [`Sleep`],
+/// including [`Runtime`] which is used by default. Set a custom executor
+/// using [`Manager::executor()`] for running background tasks on executors
+/// not supported by [`Runtime`]:
+///
+/// ```rust,ignore
+/// #[derive(Clone)]
+/// struct Smol(Arc<smol::Executor<'static>>);
+///
+/// impl Spawn for Smol {
+///     fn spawn(&self, future: BoxFuture<'static, ()>) {
+///         self.0.spawn(future).detach();
+///     }
+/// }
+///
+/// impl Sleep for Smol {
+///     fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+///         Box::pin(async move {
+///             let _ = smol::Timer::after(duration).await;
+///         })
+///     }
+/// }
+///
+/// let manager = Manager::from_config(&config).executor(Smol(executor));
+/// let pool = Pool::builder(manager).build()?;
+/// ```
+///
+/// [`BackupScheduler`]: crate::backup::BackupScheduler
+/// [`Manager::executor()`]: crate::Manager::executor
+pub trait Executor: Spawn + Sleep {}
+
+impl<T: Spawn + Sleep + ?Sized> Executor for T {}
+
+impl Spawn for Runtime {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        spawn(*self, future);
+    }
+}
+
+impl Sleep for Runtime {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(sleep(*self, duration))
+    }
+}
+
+/// Shared [`Executor`] which can be stored in the [`Manager`].
+///
+/// [`Manager`]: crate::Manager
+#[derive(Clone)]
+pub(crate) struct SharedExecutor(pub(crate) Arc<dyn Executor>);
+
+impl fmt::Debug for SharedExecutor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedExecutor").finish_non_exhaustive()
+    }
+}
+
+/// Waits for `duration` using the timer of the given `runtime`.
+pub(crate) async fn sleep(runtime: Runtime, duration: Duration) {
+    let _ = runtime.timeout(duration, pending::<()>()).await;
+}
+
+/// Runs `future` as a detached task on the given `runtime`.
+pub(crate) fn spawn<F>(runtime: Runtime, future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    runtime.spawn(future);
+}
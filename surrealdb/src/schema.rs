@@ -0,0 +1,367 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Deserialize;
+
+use crate::{checkout, surql, ClientWrapper, Error, Pool, Result};
+
+/// Default name of the table tracking applied definitions.
+pub const DEFAULT_TABLE: &str = "_schema";
+
+const RECORD_APPLIED: &str = "UPSERT type::thing($__table, $__key) \
+     SET key = $__key, statement = $__statement, definition = $__definition";
+
+/// Kinds of `DEFINE` statements supported by the [`SchemaManager`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DefinitionKind {
+    /// `DEFINE TABLE`
+    Table,
+    /// `DEFINE FIELD`
+    Field,
+    /// `DEFINE INDEX`
+    Index,
+    /// `DEFINE ANALYZER`
+    Analyzer,
+}
+
+/// Modifier of a `DEFINE` statement for existing definitions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Modifier {
+    /// `DEFINE ... OVERWRITE`
+    Overwrite,
+    /// `DEFINE ... IF NOT EXISTS`
+    IfNotExists,
+}
+
+#[derive(Clone, Debug)]
+struct Definition {
+    kind: DefinitionKind,
+    name: String,
+    table: Option<String>,
+    statement: String,
+    modifier: Option<Modifier>,
+}
+
+impl Definition {
+    fn parse(statement: &str) -> Result<Self> {
+        let invalid = || Error::InvalidDefinition(statement.to_string());
+        let statement = statement.trim().trim_end_matches(';').trim_end();
+        let mut tokens = statement.split_whitespace().peekable();
+        if !tokens
+            .next()
+            .is_some_and(|t| t.eq_ignore_ascii_case("DEFINE"))
+        {
+            return Err(invalid());
+        }
+        let kind = match surql::keyword(tokens.next().unwrap_or_default()).as_str() {
+            "TABLE" => DefinitionKind::Table,
+            "FIELD" => DefinitionKind::Field,
+            "INDEX" => DefinitionKind::Index,
+            "ANALYZER" => DefinitionKind::Analyzer,
+            _ => return Err(invalid()),
+        };
+        let mut modifier = None;
+        if tokens
+            .next_if(|t| t.eq_ignore_ascii_case("OVERWRITE"))
+            .is_some()
+        {
+            modifier = Some(Modifier::Overwrite);
+        } else if tokens.next_if(|t| t.eq_ignore_ascii_case("IF")).is_some() {
+            modifier = Some(Modifier::IfNotExists);
+            let _ = tokens.next();
+            let _ = tokens.next();
+        }
+        let name = surql::unescape_ident(tokens.next().ok_or_else(invalid)?).to_string();
+        let table = match kind {
+            DefinitionKind::Field | DefinitionKind::Index => {
+                if !tokens.next().is_some_and(|t| t.eq_ignore_ascii_case("ON")) {
+                    return Err(invalid());
+                }
+                let _ = tokens.next_if(|t| t.eq_ignore_ascii_case("TABLE"));
+                Some(surql::unescape_ident(tokens.next().ok_or_else(invalid)?).to_string())
+            }
+            DefinitionKind::Table | DefinitionKind::Analyzer => None,
+        };
+        Ok(Self {
+            kind,
+            name,
+            table,
+            statement: statement.to_string(),
+            modifier,
+        })
+    }
+
+    fn key(&self) -> String {
+        let kind = match self.kind {
+            DefinitionKind::Table => "table",
+            DefinitionKind::Field => "field",
+            DefinitionKind::Index => "index",
+            DefinitionKind::Analyzer => "analyzer",
+        };
+        match &self.table {
+            Some(table) => format!("{}:{}:{}", kind, table, self.name),
+            None => format!("{}:{}", kind, self.name),
+        }
+    }
+
+    /// Returns the statement rewritten to replace an existing definition,
+    /// replacing `IF NOT EXISTS` by `OVERWRITE`.
+    fn overwrite(&self) -> String {
+        // "DEFINE" is followed by the kind of the definition.
+        let rest = self.statement["DEFINE".len()..].trim_start();
+        let kind_len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let head_len = self.statement.len() - rest.len() + kind_len;
+        let mut tail = &self.statement[head_len..];
+        match self.modifier {
+            Some(Modifier::Overwrite) => return self.statement.clone(),
+            Some(Modifier::IfNotExists) => {
+                // Skips "IF NOT EXISTS".
+                for _ in 0..3 {
+                    tail = tail.trim_start();
+                    tail = &tail[tail.find(char::is_whitespace).unwrap_or(tail.len())..];
+                }
+            }
+            None => {}
+        }
+        format!("{} OVERWRITE{}", &self.statement[..head_len], tail)
+    }
+}
+
+/// Outcome of comparing the declared schema with the database.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SchemaPlan {
+    /// Definitions which don't exist in the database yet
+    pub missing: Vec<String>,
+    /// Definitions which exist but weren't applied using the same
+    /// declaration, or were changed in the database since they were applied
+    pub changed: Vec<String>,
+    /// Definitions which are up to date
+    pub unchanged: Vec<String>,
+}
+
+impl SchemaPlan {
+    /// Returns `true` if the database is up to date.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// `DEFINE` statements of the database returned by `INFO FOR DB`.
+#[derive(Debug, Default, Deserialize)]
+struct DbInfo {
+    #[serde(default)]
+    tables: HashMap<String, String>,
+    #[serde(default)]
+    analyzers: HashMap<String, String>,
+}
+
+/// `DEFINE` statements of a table returned by `INFO FOR TABLE`.
+#[derive(Debug, Default, Deserialize)]
+struct TableInfo {
+    #[serde(default)]
+    fields: HashMap<String, String>,
+    #[serde(default)]
+    indexes: HashMap<String, String>,
+}
+
+/// Definitions currently in the database.
+#[derive(Debug, Default)]
+struct Current {
+    db: DbInfo,
+    tables: HashMap<String, TableInfo>,
+}
+
+impl Current {
+    /// Returns the `DEFINE` statement of the database for `definition`.
+    fn get(&self, definition: &Definition) -> Option<&String> {
+        match (definition.kind, definition.table.as_deref()) {
+            (DefinitionKind::Table, _) => self.db.tables.get(&definition.name),
+            (DefinitionKind::Analyzer, _) => self.db.analyzers.get(&definition.name),
+            (DefinitionKind::Field, Some(table)) => self
+                .tables
+                .get(table)
+                .and_then(|t| t.fields.get(&definition.name)),
+            (DefinitionKind::Index, Some(table)) => self
+                .tables
+                .get(table)
+                .and_then(|t| t.indexes.get(&definition.name)),
+            (_, None) => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Applied {
+    key: String,
+    statement: String,
+    /// `DEFINE` statement returned by the database right after applying
+    #[serde(default)]
+    definition: Option<String>,
+}
+
+/// Keeps the database schema in sync with a declarative set of `DEFINE
+/// TABLE`, `DEFINE FIELD`, `DEFINE INDEX` and `DEFINE ANALYZER` statements.
+///
+/// Definitions missing from the database (according to `INFO FOR DB` and
+/// `INFO FOR TABLE`) are applied as declared. Every applied statement is
+/// recorded in a table ([`DEFAULT_TABLE`] by default), together with the
+/// definition the database reports for it afterwards. Definitions whose
+/// declaration changed since they were last applied, or whose definition
+/// was changed in the database by other means, are re-applied using
+/// `DEFINE ... OVERWRITE`. `IF NOT EXISTS` is replaced by `OVERWRITE` for
+/// that.
+#[derive(Clone, Debug, Default)]
+pub struct SchemaManager {
+    definitions: Vec<Definition>,
+    table: Option<String>,
+}
+
+impl SchemaManager {
+    /// Creates a new empty [`SchemaManager`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new [`SchemaManager`] from all `DEFINE` statements of the
+    /// given script.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidDefinition`] for unsupported statements.
+    pub fn from_script(script: &str) -> Result<Self> {
+        surql::split(script)
+            .iter()
+            .try_fold(Self::new(), |schema, statement| schema.define(statement))
+    }
+
+    /// Adds a single `DEFINE` statement.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidDefinition`] for unsupported statements.
+    pub fn define(mut self, statement: &str) -> Result<Self> {
+        self.definitions.push(Definition::parse(statement)?);
+        Ok(self)
+    }
+
+    /// Sets the table used for recording applied definitions.
+    #[must_use]
+    pub fn table(mut self, table: impl Into<String>) -> Self {
+        self.table = Some(table.into());
+        self
+    }
+
+    /// Compares the declared schema with the database without changing it.
+    pub async fn plan(&self, pool: &Pool) -> Result<SchemaPlan> {
+        let conn = checkout::get(pool).await?;
+        self.diff(&conn).await
+    }
+
+    /// Applies all missing and changed definitions.
+    ///
+    /// Returns the plan which was applied.
+    pub async fn sync(&self, pool: &Pool) -> Result<SchemaPlan> {
+        let conn = checkout::get(pool).await?;
+        let plan = self.diff(&conn).await?;
+        let mut applied = Vec::new();
+        for definition in &self.definitions {
+            let statement = if plan.missing.contains(&definition.statement) {
+                definition.statement.clone()
+            } else if plan.changed.contains(&definition.statement) {
+                definition.overwrite()
+            } else {
+                continue;
+            };
+            let _ = conn.query(statement).await?.check()?;
+            applied.push(definition);
+        }
+        if applied.is_empty() {
+            return Ok(plan);
+        }
+
+        let current = self.inspect(&conn).await?;
+        for definition in applied {
+            let _ = conn
+                .query(RECORD_APPLIED)
+                .bind(("__table", self.tracking_table().to_string()))
+                .bind(("__key", definition.key()))
+                .bind(("__statement", definition.statement.clone()))
+                .bind(("__definition", current.get(definition).cloned()))
+                .await?
+                .check()?;
+        }
+        Ok(plan)
+    }
+
+    fn tracking_table(&self) -> &str {
+        self.table.as_deref().unwrap_or(DEFAULT_TABLE)
+    }
+
+    /// Reads the current definitions of all tables this schema defines
+    /// fields or indexes on.
+    async fn inspect(&self, conn: &ClientWrapper) -> Result<Current> {
+        let db: Option<DbInfo> = conn.query("INFO FOR DB").await?.take(0)?;
+        let db = db.unwrap_or_default();
+
+        let tables: HashSet<&str> = self
+            .definitions
+            .iter()
+            .filter_map(|d| d.table.as_deref())
+            .filter(|t| db.tables.contains_key(*t))
+            .collect();
+        let mut table_infos = HashMap::new();
+        for table in tables {
+            let info: Option<TableInfo> = conn
+                .query(format!("INFO FOR TABLE {}", surql::escape_ident(table)))
+                .await?
+                .take(0)?;
+            let _ = table_infos.insert(table.to_string(), info.unwrap_or_default());
+        }
+        Ok(Current {
+            db,
+            tables: table_infos,
+        })
+    }
+
+    async fn diff(&self, conn: &ClientWrapper) -> Result<SchemaPlan> {
+        let current = self.inspect(conn).await?;
+
+        let applied: Vec<Applied> = conn
+            .query(format!(
+                "SELECT key, statement, definition FROM {}",
+                surql::escape_ident(self.tracking_table())
+            ))
+            .await?
+            .take(0)?;
+        let applied: HashMap<String, Applied> =
+            applied.into_iter().map(|a| (a.key.clone(), a)).collect();
+
+        let mut plan = SchemaPlan::default();
+        for definition in &self.definitions {
+            let statement = definition.statement.clone();
+            let Some(actual) = current.get(definition) else {
+                plan.missing.push(statement);
+                continue;
+            };
+            // Both the declaration and the definition in the database must
+            // still be the ones recorded when it was applied.
+            let up_to_date = applied.get(&definition.key()).is_some_and(|a| {
+                normalize(&a.statement) == normalize(&statement)
+                    && a.definition
+                        .as_deref()
+                        .is_some_and(|d| normalize(d) == normalize(actual))
+            });
+            if up_to_date {
+                plan.unchanged.push(statement);
+            } else {
+                plan.changed.push(statement);
+            }
+        }
+        Ok(plan)
+    }
+}
+
+fn normalize(statement: &str) -> String {
+    statement.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,177 @@
+use std::{
+    fmt,
+    future::Future,
+    pin::{pin, Pin},
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures::{
+    channel::{mpsc, oneshot},
+    future,
+    stream::FuturesUnordered,
+    FutureExt, StreamExt,
+};
+use serde::Serialize;
+use surrealdb::Response;
+
+use crate::{
+    client::{Binding, PoolQuery},
+    Error, Pool, Result,
+};
+
+/// Queries running concurrently on distinct pooled connections, see
+/// [`PoolExt::scope()`].
+///
+/// All clones of a [`Scope`] spawn their queries into the same scope.
+///
+/// [`PoolExt::scope()`]: crate::PoolExt::scope
+#[derive(Clone)]
+pub struct Scope {
+    jobs: mpsc::UnboundedSender<Job>,
+}
+
+impl Scope {
+    /// Prepares the given `query` for running within this scope.
+    pub fn query(&self, query: impl Into<String>) -> ScopeQuery<'_> {
+        ScopeQuery {
+            scope: self,
+            sql: query.into(),
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Runs the given `query` within this scope, returning a handle
+    /// resolving to its response.
+    pub fn spawn_query(&self, query: impl Into<String>) -> QueryHandle {
+        self.query(query).spawn()
+    }
+}
+
+impl fmt::Debug for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scope")
+            .field("closed", &self.jobs.is_closed())
+            .finish()
+    }
+}
+
+/// Query prepared using [`Scope::query()`].
+#[must_use = "queries do nothing unless spawned"]
+pub struct ScopeQuery<'a> {
+    scope: &'a Scope,
+    sql: String,
+    bindings: Vec<Binding>,
+}
+
+impl ScopeQuery<'_> {
+    /// Binds parameters to the query.
+    ///
+    /// See [`Query::bind()`] for details.
+    ///
+    /// [`Query::bind()`]: crate::client::Query::bind
+    pub fn bind(mut self, bindings: impl Serialize + Send + Sync + 'static) -> Self {
+        self.bindings.push(Binding(Arc::new(bindings)));
+        self
+    }
+
+    /// Runs the query within the scope, returning a handle resolving to its
+    /// response.
+    pub fn spawn(self) -> QueryHandle {
+        let (reply, response) = oneshot::channel();
+        // Queries spawned after the scope finished are dropped, which
+        // cancels their handles.
+        let _ = self.scope.jobs.unbounded_send(Job {
+            sql: self.sql,
+            bindings: self.bindings,
+            reply,
+        });
+        QueryHandle(response)
+    }
+}
+
+impl fmt::Debug for ScopeQuery<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScopeQuery")
+            .field("sql", &self.sql)
+            .field("bindings", &self.bindings.len())
+            .finish()
+    }
+}
+
+/// Handle of a query spawned into a [`Scope`], resolving to its response.
+///
+/// The query runs whether or not the handle is awaited. It resolves to
+/// [`Error::Canceled`] if the query was spawned after its scope finished or
+/// if the query itself failed, which fails the whole scope anyway.
+#[derive(Debug)]
+pub struct QueryHandle(oneshot::Receiver<Result<Response>>);
+
+impl Future for QueryHandle {
+    type Output = Result<Response>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.0
+            .poll_unpin(cx)
+            .map(|response| response.unwrap_or(Err(Error::Canceled)))
+    }
+}
+
+struct Job {
+    sql: String,
+    bindings: Vec<Binding>,
+    reply: oneshot::Sender<Result<Response>>,
+}
+
+impl Job {
+    async fn run(self, pool: &Pool) -> Result<()> {
+        let response = PoolQuery::new(pool, self.sql)
+            .bindings(self.bindings)
+            .await?
+            .check()?;
+        let _ = self.reply.send(Ok(response));
+        Ok(())
+    }
+}
+
+/// Runs `f` and the queries it spawns, failing with the first error.
+pub(crate) async fn run<F, Fut, T>(pool: &Pool, f: F) -> Result<T>
+where
+    F: FnOnce(Scope) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let parallelism = pool.status().max_size.max(1);
+    let (jobs, mut queue) = mpsc::unbounded();
+    let mut body = pin!(f(Scope { jobs }));
+    let mut output = None;
+    let mut queued = true;
+    let mut running = FuturesUnordered::new();
+    future::poll_fn(|cx| -> Poll<Result<T>> {
+        if output.is_none() {
+            if let Poll::Ready(result) = body.as_mut().poll(cx) {
+                output = Some(result?);
+                queue.close();
+            }
+        }
+        loop {
+            while queued && running.len() < parallelism {
+                match queue.poll_next_unpin(cx) {
+                    Poll::Ready(Some(job)) => running.push(job.run(pool)),
+                    Poll::Ready(None) => queued = false,
+                    Poll::Pending => break,
+                }
+            }
+            match running.poll_next_unpin(cx) {
+                Poll::Ready(Some(result)) => result?,
+                Poll::Ready(None) | Poll::Pending => break,
+            }
+        }
+        if !queued && running.is_empty() {
+            if let Some(output) = output.take() {
+                return Poll::Ready(Ok(output));
+            }
+        }
+        Poll::Pending
+    })
+    .await
+}
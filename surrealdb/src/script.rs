@@ -0,0 +1,222 @@
+use std::{
+    fs,
+    path::Path,
+    pin::pin,
+    time::{Duration, Instant},
+};
+
+use futures::{io::AsyncRead, AsyncReadExt};
+
+use crate::{checkout, surql, ClientWrapper, Error, Pool, Result};
+
+/// SurrealQL script run statement by statement, e.g. an ops runbook or
+/// fixtures.
+///
+/// The statements are run in order on a single pooled connection. Each
+/// statement is sent as a query of its own, except for transactions which
+/// are sent as a whole. Failing statements are reported in the returned
+/// [`ScriptReport`] instead of failing the run.
+///
+/// ```rust,ignore
+/// let report = Script::from_path("runbooks/rotate-keys.surql")?
+///     .transactional(true)
+///     .run(&pool)
+///     .await?;
+/// for outcome in report.failed() {
+///     eprintln!("{}: {}", outcome.statement, outcome.error.as_ref().unwrap());
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Script {
+    statements: Vec<String>,
+    transactional: bool,
+    stop_on_error: bool,
+}
+
+impl Script {
+    /// Creates a new [`Script`] from the SurrealQL `script`.
+    #[must_use]
+    pub fn new(script: &str) -> Self {
+        Self {
+            statements: surql::split(script),
+            transactional: false,
+            stop_on_error: true,
+        }
+    }
+
+    /// Reads the script from the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file can't be read.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(&fs::read_to_string(path)?))
+    }
+
+    /// Reads the script from `reader`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if reading fails or the script is not valid UTF-8.
+    pub async fn read(reader: impl AsyncRead) -> Result<Self> {
+        let mut script = String::new();
+        let _ = pin!(reader).read_to_string(&mut script).await?;
+        Ok(Self::new(&script))
+    }
+
+    /// Runs the whole script in a single transaction, so either all
+    /// statements succeed or none has an effect.
+    ///
+    /// Scripts containing transactions of their own are never wrapped.
+    #[must_use]
+    pub fn transactional(mut self, transactional: bool) -> Self {
+        self.transactional = transactional;
+        self
+    }
+
+    /// Stops at the first failing statement. Defaults to `true`.
+    ///
+    /// The statements after it are not run and not reported.
+    #[must_use]
+    pub fn stop_on_error(mut self, stop_on_error: bool) -> Self {
+        self.stop_on_error = stop_on_error;
+        self
+    }
+
+    /// Returns the statements of the script.
+    #[must_use]
+    pub fn statements(&self) -> &[String] {
+        &self.statements
+    }
+
+    /// Runs the script using a pooled connection.
+    ///
+    /// # Errors
+    ///
+    /// Fails if no connection can be checked out or the connection breaks.
+    /// Errors of single statements are reported in the [`ScriptReport`].
+    pub async fn run(&self, pool: &Pool) -> Result<ScriptReport> {
+        let conn = checkout::get(pool).await?;
+        let mut report = ScriptReport::default();
+        let has_transactions = self
+            .statements
+            .iter()
+            .any(|s| surql::TRANSACTION_STATEMENTS.contains(&surql::keyword(s).as_str()));
+        if self.transactional && !has_transactions {
+            let _ = run_batch(&conn, &self.statements, true, &mut report).await?;
+            return Ok(report);
+        }
+        let mut start = 0;
+        while start < self.statements.len() {
+            let end = match surql::keyword(&self.statements[start]).as_str() {
+                "BEGIN" => self.statements[start..]
+                    .iter()
+                    .position(|s| matches!(surql::keyword(s).as_str(), "COMMIT" | "CANCEL"))
+                    .map_or(self.statements.len(), |i| start + i + 1),
+                _ => start + 1,
+            };
+            let failed = if end - start == 1 {
+                run_single(&conn, &self.statements[start], &mut report).await?
+            } else {
+                run_batch(&conn, &self.statements[start..end], false, &mut report).await?
+            };
+            if failed && self.stop_on_error {
+                break;
+            }
+            start = end;
+        }
+        Ok(report)
+    }
+}
+
+/// Result of a single statement of a [`Script`].
+#[derive(Debug)]
+pub struct StatementOutcome {
+    /// Text of the statement
+    pub statement: String,
+    /// Time the statement took, unless it was run as part of a transaction
+    pub elapsed: Option<Duration>,
+    /// Error the statement failed with, if any
+    pub error: Option<Error>,
+}
+
+/// Results of the statements of a [`Script`] in the order they were run.
+#[derive(Debug, Default)]
+pub struct ScriptReport {
+    /// Results of the statements which were run
+    pub statements: Vec<StatementOutcome>,
+}
+
+impl ScriptReport {
+    /// Indicates whether all statements which were run succeeded.
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.statements.iter().all(|s| s.error.is_none())
+    }
+
+    /// Returns the failed statements.
+    pub fn failed(&self) -> impl Iterator<Item = &StatementOutcome> {
+        self.statements.iter().filter(|s| s.error.is_some())
+    }
+}
+
+/// Runs a single statement. Returns whether it failed.
+async fn run_single(
+    conn: &ClientWrapper,
+    statement: &str,
+    report: &mut ScriptReport,
+) -> Result<bool> {
+    let started = Instant::now();
+    let error = match conn.query(statement).await {
+        Ok(mut response) => response.take_errors().into_values().next().map(Error::from),
+        Err(e) if e.is_connection_lost() => return Err(e),
+        Err(e) => Some(e),
+    };
+    let failed = error.is_some();
+    report.statements.push(StatementOutcome {
+        statement: statement.to_string(),
+        elapsed: Some(started.elapsed()),
+        error,
+    });
+    Ok(failed)
+}
+
+/// Runs `statements` as a single query, wrapped in a transaction if `wrap`
+/// is set. Returns whether one of them failed.
+async fn run_batch(
+    conn: &ClientWrapper,
+    statements: &[String],
+    wrap: bool,
+    report: &mut ScriptReport,
+) -> Result<bool> {
+    let mut sql = String::new();
+    if wrap {
+        sql.push_str("BEGIN TRANSACTION;\n");
+    }
+    for statement in statements {
+        sql.push_str(statement);
+        sql.push_str(";\n");
+    }
+    if wrap {
+        sql.push_str("COMMIT TRANSACTION;\n");
+    }
+    let mut errors = conn.query(sql).await?.take_errors();
+    // Transaction statements have no results of their own.
+    let mut index = 0;
+    let mut failed = false;
+    for statement in statements {
+        let error = if surql::TRANSACTION_STATEMENTS.contains(&surql::keyword(statement).as_str()) {
+            None
+        } else {
+            index += 1;
+            errors.remove(&(index - 1)).map(Error::from)
+        };
+        failed |= error.is_some();
+        report.statements.push(StatementOutcome {
+            statement: statement.clone(),
+            elapsed: None,
+            error,
+        });
+    }
+    Ok(failed)
+}
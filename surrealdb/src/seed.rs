@@ -0,0 +1,163 @@
+use std::{fs, path::Path, sync::Arc};
+
+use serde::Serialize;
+
+use crate::{checkout, client::Binding, surql, Error, Pool, Result};
+
+#[derive(Clone)]
+enum Fixture {
+    Records { table: String, records: Binding },
+    Json { table: String, json: String },
+    Script(String),
+}
+
+impl Fixture {
+    fn table(&self) -> Option<&str> {
+        match self {
+            Self::Records { table, .. } | Self::Json { table, .. } => Some(table),
+            Self::Script(_) => None,
+        }
+    }
+}
+
+/// Loads fixture data into the database.
+///
+/// Fixtures are loaded in the order they were added. Records can be given
+/// as serializable values, as JSON or as SurrealQL scripts:
+///
+/// ```rust,ignore
+/// Seeder::new()
+///     .truncate(true)
+///     .records("person", vec![Person { name: "Tobie".into() }])
+///     .file("fixtures/post.json")?
+///     .file("fixtures/relations.surql")?
+///     .run(&pool)
+///     .await?;
+/// ```
+#[derive(Clone, Default)]
+pub struct Seeder {
+    fixtures: Vec<Fixture>,
+    truncate: bool,
+}
+
+impl Seeder {
+    /// Creates a new empty [`Seeder`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deletes all records of a table before loading records into it.
+    ///
+    /// Tables are only truncated before their first fixture is loaded.
+    /// Scripts are never affected.
+    #[must_use]
+    pub fn truncate(mut self, truncate: bool) -> Self {
+        self.truncate = truncate;
+        self
+    }
+
+    /// Adds `records` to be inserted into `table`.
+    #[must_use]
+    pub fn records<T>(mut self, table: impl Into<String>, records: Vec<T>) -> Self
+    where
+        T: Serialize + Send + Sync + 'static,
+    {
+        self.fixtures.push(Fixture::Records {
+            table: table.into(),
+            records: Binding(Arc::new(records)),
+        });
+        self
+    }
+
+    /// Adds a JSON object or array of objects to be inserted into `table`.
+    #[must_use]
+    pub fn json(mut self, table: impl Into<String>, json: impl Into<String>) -> Self {
+        self.fixtures.push(Fixture::Json {
+            table: table.into(),
+            json: json.into(),
+        });
+        self
+    }
+
+    /// Adds a SurrealQL script to be run.
+    #[must_use]
+    pub fn script(mut self, script: impl Into<String>) -> Self {
+        self.fixtures.push(Fixture::Script(script.into()));
+        self
+    }
+
+    /// Adds the fixture file at `path`.
+    ///
+    /// Files ending in `.json` are inserted into the table named like the
+    /// file without its extension. Files ending in `.surql` are run as
+    /// scripts.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file can't be read or has an unsupported extension.
+    pub fn file(self, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let unsupported = || {
+            Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Unsupported fixture file: {}", path.display()),
+            ))
+        };
+        let extension = path.extension().and_then(|e| e.to_str());
+        match extension {
+            Some("json") => {
+                let table = path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .ok_or_else(unsupported)?
+                    .to_string();
+                Ok(self.json(table, fs::read_to_string(path)?))
+            }
+            Some("surql") => Ok(self.script(fs::read_to_string(path)?)),
+            _ => Err(unsupported()),
+        }
+    }
+
+    /// Loads all fixtures using a pooled connection.
+    pub async fn run(&self, pool: &Pool) -> Result<()> {
+        let conn = checkout::get(pool).await?;
+        let mut truncated: Vec<&str> = Vec::new();
+        for fixture in &self.fixtures {
+            if let Some(table) = fixture.table() {
+                if self.truncate && !truncated.contains(&table) {
+                    let _ = conn
+                        .query(format!("DELETE {}", surql::escape_ident(table)))
+                        .await?
+                        .check()?;
+                    truncated.push(table);
+                }
+            }
+            let query = match fixture {
+                Fixture::Records { table, records } => conn
+                    .query(format!(
+                        "INSERT INTO {} $records",
+                        surql::escape_ident(table)
+                    ))
+                    .bind(("records", records.clone())),
+                Fixture::Json { table, json } => conn.query(format!(
+                    "INSERT INTO {} {}",
+                    surql::escape_ident(table),
+                    json
+                )),
+                Fixture::Script(script) => conn.query(script.clone()),
+            };
+            let _ = query.await?.check()?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for Seeder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Seeder")
+            .field("fixtures", &self.fixtures.len())
+            .field("truncate", &self.truncate)
+            .finish()
+    }
+}
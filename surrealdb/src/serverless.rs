@@ -0,0 +1,39 @@
+use std::sync::OnceLock;
+
+use crate::{Config, Pool, Result, Runtime};
+
+static POOL: OnceLock<Pool> = OnceLock::new();
+
+/// Returns the [`Pool`] shared by all invocations handled by this process,
+/// creating it using the [`Config`] returned by `config` on first use.
+///
+/// ```rust,ignore
+/// async fn handler(event: LambdaEvent<Request>) -> Result<Response, Error> {
+///     let pool = serverless::pool(|| load_config().serverless(), Some(Runtime::Tokio1))?;
+///     let conn = pool.get().await?;
+///     // ...
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Fails if the pool can't be created. The next call tries again.
+pub fn pool(config: impl FnOnce() -> Config, runtime: Option<Runtime>) -> Result<&'static Pool> {
+    if let Some(pool) = POOL.get() {
+        return Ok(pool);
+    }
+    let pool = config().create_pool(runtime)?;
+    // Another thread might have won the race, in which case `pool` is
+    // dropped without ever having created a connection.
+    Ok(POOL.get_or_init(|| pool))
+}
+
+/// Closes the shared [`Pool`], dropping all idle connections right away.
+///
+/// Call this when the environment announces the shutdown of the process.
+/// The pool can't be used afterwards.
+pub fn shutdown() {
+    if let Some(pool) = POOL.get() {
+        pool.close();
+    }
+}
@@ -0,0 +1,164 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use time::OffsetDateTime;
+use tower_sessions_core::{
+    session::{Id, Record},
+    session_store, ExpiredDeletion, SessionStore,
+};
+
+use crate::{checkout, surql, Pool};
+
+/// Default name of the table storing sessions.
+pub const DEFAULT_TABLE: &str = "sessions";
+
+const CREATE: &str = "CREATE type::thing($table, $id) \
+    CONTENT { data: $data, expires_at: time::from::unix($expiry) } RETURN NONE";
+const SAVE: &str = "UPSERT type::thing($table, $id) \
+    CONTENT { data: $data, expires_at: time::from::unix($expiry) } RETURN NONE";
+const LOAD: &str = "SELECT data, time::unix(expires_at) AS expiry \
+    FROM type::thing($table, $id) WHERE expires_at > time::now()";
+const DELETE: &str = "DELETE type::thing($table, $id)";
+const EXISTS: &str = "RETURN record::exists(type::thing($table, $id))";
+
+#[derive(Deserialize)]
+struct Row<D> {
+    data: D,
+    expiry: i64,
+}
+
+/// [`SessionStore`] keeping `tower-sessions` sessions in SurrealDB.
+///
+/// Every session is stored as a record of a table ([`DEFAULT_TABLE`] by
+/// default) with the session id as record id and the fields `data` and
+/// `expires_at`. Expired sessions are never loaded and can be removed using
+/// [`ExpiredDeletion::delete_expired()`].
+///
+/// ```rust,ignore
+/// let store = SurrealSessionStore::new(&pool).table("http_sessions");
+/// let app = Router::new()
+///     .route("/", get(handler))
+///     .layer(SessionManagerLayer::new(store));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SurrealSessionStore {
+    pool: Pool,
+    table: String,
+}
+
+impl SurrealSessionStore {
+    /// Creates a new [`SurrealSessionStore`] using the given `pool`.
+    #[must_use]
+    pub fn new(pool: &Pool) -> Self {
+        Self {
+            pool: pool.clone(),
+            table: DEFAULT_TABLE.to_string(),
+        }
+    }
+
+    /// Sets the table storing sessions.
+    #[must_use]
+    pub fn table(mut self, table: impl Into<String>) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    async fn write(&self, sql: &str, record: &Record) -> crate::Result<()> {
+        let conn = checkout::get(&self.pool).await?;
+        let _ = conn
+            .query(sql)
+            .bind(("table", self.table.clone()))
+            .bind(("id", record.id.to_string()))
+            .bind(("data", record.data.clone()))
+            .bind(("expiry", record.expiry_date.unix_timestamp()))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    async fn exists(&self, id: &Id) -> crate::Result<bool> {
+        let conn = checkout::get(&self.pool).await?;
+        let exists: Option<bool> = conn
+            .query(EXISTS)
+            .bind(("table", self.table.clone()))
+            .bind(("id", id.to_string()))
+            .await?
+            .take(0)?;
+        Ok(exists.unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl SessionStore for SurrealSessionStore {
+    async fn create(&self, record: &mut Record) -> session_store::Result<()> {
+        loop {
+            let Err(e) = self.write(CREATE, record).await else {
+                return Ok(());
+            };
+            if !self.exists(&record.id).await.map_err(backend)? {
+                return Err(backend(e));
+            }
+            // Session id collision
+            record.id = Id::default();
+        }
+    }
+
+    async fn save(&self, record: &Record) -> session_store::Result<()> {
+        self.write(SAVE, record).await.map_err(backend)
+    }
+
+    async fn load(&self, id: &Id) -> session_store::Result<Option<Record>> {
+        let conn = checkout::get(&self.pool).await.map_err(backend)?;
+        let rows: Vec<Row<_>> = conn
+            .query(LOAD)
+            .bind(("table", self.table.clone()))
+            .bind(("id", id.to_string()))
+            .await
+            .map_err(backend)?
+            .take(0)
+            .map_err(|e| session_store::Error::Decode(e.to_string()))?;
+        let Some(row) = rows.into_iter().next() else {
+            return Ok(None);
+        };
+        let expiry_date = OffsetDateTime::from_unix_timestamp(row.expiry)
+            .map_err(|e| session_store::Error::Decode(e.to_string()))?;
+        Ok(Some(Record {
+            id: *id,
+            data: row.data,
+            expiry_date,
+        }))
+    }
+
+    async fn delete(&self, id: &Id) -> session_store::Result<()> {
+        let conn = checkout::get(&self.pool).await.map_err(backend)?;
+        let _ = conn
+            .query(DELETE)
+            .bind(("table", self.table.clone()))
+            .bind(("id", id.to_string()))
+            .await
+            .map_err(backend)?
+            .check()
+            .map_err(|e| backend(e.into()))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExpiredDeletion for SurrealSessionStore {
+    async fn delete_expired(&self) -> session_store::Result<()> {
+        let conn = checkout::get(&self.pool).await.map_err(backend)?;
+        let _ = conn
+            .query(format!(
+                "DELETE {} WHERE expires_at <= time::now()",
+                surql::escape_ident(&self.table)
+            ))
+            .await
+            .map_err(backend)?
+            .check()
+            .map_err(|e| backend(e.into()))?;
+        Ok(())
+    }
+}
+
+fn backend(e: crate::Error) -> session_store::Error {
+    session_store::Error::Backend(e.to_string())
+}
@@ -0,0 +1,108 @@
+use std::{
+    future::Future,
+    pin::pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use futures::future::{self, Either};
+use tokio::sync::Notify;
+
+use crate::{
+    rt::{Executor, Sleep},
+    Error, Pool, Result, WeakPool,
+};
+
+/// Token telling the background tasks of a [`Manager`] to stop, triggered
+/// by [`PoolExt::shutdown()`] and when the manager is dropped along with the
+/// last handle of its [`Pool`].
+///
+/// [`Manager`]: crate::Manager
+/// [`PoolExt::shutdown()`]: crate::PoolExt::shutdown
+#[derive(Debug, Default)]
+pub(crate) struct Shutdown {
+    triggered: AtomicBool,
+    notify: Notify,
+}
+
+impl Shutdown {
+    pub(crate) fn trigger(&self) {
+        self.triggered.store(true, Ordering::Release);
+        self.notify.notify_waiters();
+    }
+
+    fn is_triggered(&self) -> bool {
+        self.triggered.load(Ordering::Acquire)
+    }
+
+    /// Completes once the token is triggered.
+    async fn triggered(&self) {
+        let mut notified = pin!(self.notify.notified());
+        // Register before checking, so a trigger in between isn't missed.
+        let _ = notified.as_mut().enable();
+        if !self.is_triggered() {
+            notified.await;
+        }
+    }
+}
+
+/// [`Pool`] used by background helpers like the [`ServerStats`] collector,
+/// which doesn't keep the pool alive.
+///
+/// Tasks only upgrade it for the work of a single tick, so dropping the last
+/// handle of the pool elsewhere drops the pool and stops the tasks.
+///
+/// [`ServerStats`]: crate::stats::ServerStats
+#[derive(Clone, Debug)]
+pub(crate) struct TaskPool {
+    pool: WeakPool,
+    shutdown: Arc<Shutdown>,
+}
+
+impl TaskPool {
+    pub(crate) fn new(pool: &Pool) -> Self {
+        Self {
+            pool: pool.weak(),
+            shutdown: pool.manager().shutdown.clone(),
+        }
+    }
+
+    /// Returns the pool, failing with [`Error::PoolClosed`] if it was
+    /// dropped.
+    pub(crate) fn get(&self) -> Result<Pool> {
+        self.pool.upgrade().ok_or(Error::PoolClosed)
+    }
+
+    /// Returns whether background tasks of the pool should stop, because it
+    /// was dropped, closed or shut down.
+    pub(crate) fn is_stopping(&self) -> bool {
+        if self.shutdown.is_triggered() {
+            return true;
+        }
+        match self.pool.upgrade() {
+            Some(pool) => pool.is_closed(),
+            None => true,
+        }
+    }
+
+    /// Waits for `duration`, returning `false` right away once the pool is
+    /// shut down or dropped.
+    pub(crate) async fn sleep(&self, executor: &dyn Executor, duration: Duration) -> bool {
+        let shutdown = pin!(self.shutdown.triggered());
+        let _ = future::select(executor.sleep(duration), shutdown).await;
+        !self.is_stopping()
+    }
+
+    /// Runs `future` until it completes or the pool is shut down or dropped,
+    /// whichever happens first.
+    pub(crate) async fn until<T>(&self, future: impl Future<Output = T>) -> Option<T> {
+        let shutdown = pin!(self.shutdown.triggered());
+        match future::select(pin!(future), shutdown).await {
+            Either::Left((output, _)) => Some(output),
+            Either::Right(((), _)) => None,
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use shuttle_service::{
+    error::CustomError, Error as ShuttleError, IntoResource, ResourceFactory, ResourceInputBuilder,
+};
+
+use crate::{Config, Credentials, Pool, Runtime};
+
+/// Shuttle resource providing a [`Pool`].
+///
+/// Shuttle doesn't host SurrealDB, so the connection is configured using
+/// the secrets of the project. The following secrets are read:
+///
+/// - `SURREALDB_HOST` (required)
+/// - `SURREALDB_NS` and `SURREALDB_DB` (both default to `"test"`)
+/// - `SURREALDB_USER` and `SURREALDB_PASS` (root credentials)
+///
+/// Values set on the resource take precedence over the secrets:
+///
+/// ```rust,ignore
+/// #[shuttle_runtime::main]
+/// async fn main(
+///     #[deadpool_surrealdb::shuttle::SurrealPool(ns = "app", db = "app")] pool: Pool,
+/// ) -> ShuttleAxum {
+///     // ...
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SurrealPool {
+    host: Option<String>,
+    ns: Option<String>,
+    db: Option<String>,
+}
+
+impl SurrealPool {
+    /// Sets the database host URL.
+    #[must_use]
+    pub fn host(mut self, host: &str) -> Self {
+        self.host = Some(host.to_string());
+        self
+    }
+
+    /// Sets the namespace.
+    #[must_use]
+    pub fn ns(mut self, ns: &str) -> Self {
+        self.ns = Some(ns.to_string());
+        self
+    }
+
+    /// Sets the database.
+    #[must_use]
+    pub fn db(mut self, db: &str) -> Self {
+        self.db = Some(db.to_string());
+        self
+    }
+}
+
+#[async_trait]
+impl ResourceInputBuilder for SurrealPool {
+    type Input = Config;
+    type Output = Config;
+
+    async fn build(self, factory: &ResourceFactory) -> Result<Config, ShuttleError> {
+        let secrets = factory.get_secrets();
+        let secret = |key: &str| secrets.get(key).map(|s| s.expose().clone());
+        let host = self
+            .host
+            .or_else(|| secret("SURREALDB_HOST"))
+            .ok_or_else(|| {
+                ShuttleError::Custom(custom_error("SURREALDB_HOST secret is missing"))
+            })?;
+        let mut config = Config {
+            host,
+            ..Config::default()
+        };
+        if let Some(ns) = self.ns.or_else(|| secret("SURREALDB_NS")) {
+            config.ns = ns;
+        }
+        if let Some(db) = self.db.or_else(|| secret("SURREALDB_DB")) {
+            config.db = db;
+        }
+        if let (Some(user), Some(pass)) = (secret("SURREALDB_USER"), secret("SURREALDB_PASS")) {
+            config.creds = Credentials::Root { user, pass };
+        }
+        Ok(config)
+    }
+}
+
+#[async_trait]
+impl IntoResource<Pool> for Config {
+    async fn into_resource(self) -> Result<Pool, ShuttleError> {
+        self.create_pool(Some(Runtime::Tokio1))
+            .map_err(|e| ShuttleError::Custom(e.into()))
+    }
+}
+
+fn custom_error(message: &'static str) -> CustomError {
+    std::io::Error::other(message).into()
+}
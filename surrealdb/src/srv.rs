@@ -0,0 +1,120 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    time::Instant,
+};
+
+use hickory_resolver::TokioAsyncResolver;
+
+use crate::{clock::SharedClock, Error, Result};
+
+/// Prefix of hosts which are resolved using DNS SRV records.
+pub(crate) const PREFIX: &str = "srv+";
+
+/// Resolves hosts like `srv+ws://_surreal._tcp.example.com` to the targets
+/// of the SRV record and distributes connections across them.
+#[derive(Debug)]
+pub(crate) struct SrvResolver {
+    scheme: String,
+    name: String,
+    targets: Mutex<Option<Targets>>,
+    next: AtomicUsize,
+}
+
+#[derive(Debug)]
+struct Targets {
+    endpoints: Arc<[String]>,
+    valid_until: Instant,
+}
+
+impl SrvResolver {
+    /// Returns a resolver if `host` refers to an SRV record.
+    pub(crate) fn parse(host: &str) -> Option<Self> {
+        let (scheme, name) = host.strip_prefix(PREFIX)?.split_once("://")?;
+        Some(Self {
+            scheme: scheme.to_string(),
+            name: name.trim_end_matches('/').to_string(),
+            targets: Mutex::new(None),
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// Returns the endpoint the next connection should be made to, skipping
+    /// the endpoints rejected by `accept`.
+    ///
+    /// The SRV record is resolved again once its TTL expired according to
+    /// `clock`. Connections are distributed round-robin across the targets
+    /// with the lowest priority.
+    pub(crate) async fn endpoint(
+        &self,
+        clock: &SharedClock,
+        accept: impl Fn(&str) -> bool,
+    ) -> Result<String> {
+        let endpoints = match self.cached(clock.now()) {
+            Some(endpoints) => endpoints,
+            None => self.resolve(clock).await?,
+        };
+        for _ in 0..endpoints.len() {
+            let next = self.next.fetch_add(1, Ordering::Relaxed);
+            let endpoint = &endpoints[next % endpoints.len()];
+            if accept(endpoint) {
+                return Ok(endpoint.clone());
+            }
+        }
+        Err(Error::Connection {
+            message: format!("All targets of SRV record {} are drained", self.name).into(),
+            context: None,
+            source: None,
+        })
+    }
+
+    fn cached(&self, now: Instant) -> Option<Arc<[String]>> {
+        let targets = self.targets.lock().unwrap_or_else(PoisonError::into_inner);
+        targets
+            .as_ref()
+            .filter(|targets| targets.valid_until > now)
+            .map(|targets| targets.endpoints.clone())
+    }
+
+    async fn resolve(&self, clock: &SharedClock) -> Result<Arc<[String]>> {
+        let resolver = TokioAsyncResolver::tokio_from_system_conf()
+            .map_err(|e| Error::connection("Failed to create DNS resolver", e))?;
+        let lookup = resolver
+            .srv_lookup(self.name.as_str())
+            .await
+            .map_err(|e| Error::connection("Failed to resolve SRV record", e))?;
+        let priority = lookup.iter().map(|srv| srv.priority()).min();
+        let endpoints: Arc<[String]> = lookup
+            .iter()
+            .filter(|srv| Some(srv.priority()) == priority)
+            .map(|srv| {
+                let target = srv.target().to_ascii();
+                format!(
+                    "{}://{}:{}",
+                    self.scheme,
+                    target.trim_end_matches('.'),
+                    srv.port()
+                )
+            })
+            .collect();
+        if endpoints.is_empty() {
+            return Err(Error::Connection {
+                message: format!("SRV record {} has no targets", self.name).into(),
+                context: None,
+                source: None,
+            });
+        }
+        // The resolver measures the TTL using the system clock.
+        let ttl = lookup
+            .as_lookup()
+            .valid_until()
+            .saturating_duration_since(Instant::now());
+        *self.targets.lock().unwrap_or_else(PoisonError::into_inner) = Some(Targets {
+            endpoints: endpoints.clone(),
+            valid_until: clock.now() + ttl,
+        });
+        Ok(endpoints)
+    }
+}
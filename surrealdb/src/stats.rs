@@ -0,0 +1,257 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{checkout, hook, rt::Spawn, shutdown::TaskPool, surql, Error, Pool, Result};
+
+/// Statistics of the server collected by [`ServerStats`].
+#[derive(Clone, Debug, Serialize)]
+pub struct StatsSnapshot {
+    /// Milliseconds since the Unix epoch when the statistics were collected
+    pub timestamp_ms: u64,
+    /// Namespaces of the server, unless the credentials of the pool can't
+    /// read them
+    pub namespaces: Option<Vec<String>>,
+    /// Root users of the server, unless the credentials of the pool can't
+    /// read them
+    pub root_users: Option<Vec<String>>,
+    /// Users of the configured database
+    pub users: Vec<String>,
+    /// Functions of the configured database, without the `fn::` prefix
+    pub functions: Vec<String>,
+    /// Parameters of the configured database
+    pub params: Vec<String>,
+    /// Tables of the configured database
+    pub tables: Vec<TableStats>,
+}
+
+impl StatsSnapshot {
+    /// Returns the number of live queries on all tables.
+    #[must_use]
+    pub fn live_queries(&self) -> usize {
+        self.tables.iter().map(|t| t.live_queries).sum()
+    }
+}
+
+/// Statistics of a single table.
+#[derive(Clone, Debug, Serialize)]
+pub struct TableStats {
+    /// Name of the table
+    pub name: String,
+    /// Number of defined fields
+    pub fields: usize,
+    /// Number of defined indexes
+    pub indexes: usize,
+    /// Number of defined events
+    pub events: usize,
+    /// Number of live queries on the table
+    pub live_queries: usize,
+}
+
+/// Definitions returned by `INFO FOR ROOT`.
+#[derive(Deserialize)]
+struct RootInfo {
+    #[serde(default)]
+    namespaces: BTreeMap<String, String>,
+    #[serde(default)]
+    users: BTreeMap<String, String>,
+}
+
+/// Definitions returned by `INFO FOR DB`.
+#[derive(Default, Deserialize)]
+struct DbInfo {
+    #[serde(default)]
+    functions: BTreeMap<String, String>,
+    #[serde(default)]
+    params: BTreeMap<String, String>,
+    #[serde(default)]
+    tables: BTreeMap<String, String>,
+    #[serde(default)]
+    users: BTreeMap<String, String>,
+}
+
+/// Definitions returned by `INFO FOR TABLE`.
+#[derive(Default, Deserialize)]
+struct TableInfo {
+    #[serde(default)]
+    events: BTreeMap<String, String>,
+    #[serde(default)]
+    fields: BTreeMap<String, String>,
+    #[serde(default)]
+    indexes: BTreeMap<String, String>,
+    #[serde(default)]
+    lives: BTreeMap<String, String>,
+}
+
+/// Collector of server statistics for admin dashboards.
+///
+/// The statistics are collected using `INFO FOR ROOT`, `INFO FOR DB` and
+/// `INFO FOR TABLE` on a pooled connection, either on demand or every
+/// `interval` when spawned. Clones share the collected statistics.
+///
+/// The collector doesn't keep the pool alive. Once the pool is dropped,
+/// collecting fails with [`Error::PoolClosed`] and a spawned collector stops.
+///
+/// ```rust,ignore
+/// let stats = ServerStats::new(&pool).interval(Duration::from_secs(30));
+/// stats.clone().spawn()?;
+/// let snapshot = stats.snapshot().await?;
+/// println!("{} tables, {} live queries", snapshot.tables.len(), snapshot.live_queries());
+/// ```
+#[derive(Clone, Debug)]
+pub struct ServerStats {
+    pool: TaskPool,
+    interval: Duration,
+    latest: Arc<Mutex<Option<StatsSnapshot>>>,
+}
+
+impl ServerStats {
+    /// Creates a new [`ServerStats`] collector for the server of `pool`.
+    pub fn new(pool: &Pool) -> Self {
+        Self {
+            pool: TaskPool::new(pool),
+            interval: Duration::from_secs(60),
+            latest: Arc::default(),
+        }
+    }
+
+    /// Sets the time between two collections when spawned. Defaults to one
+    /// minute.
+    #[must_use]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Returns the most recently collected statistics, if any.
+    #[must_use]
+    pub fn latest(&self) -> Option<StatsSnapshot> {
+        self.latest
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Returns the most recently collected statistics, collecting them
+    /// first if this wasn't done yet.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the statistics need to be collected and collecting them
+    /// fails.
+    pub async fn snapshot(&self) -> Result<StatsSnapshot> {
+        match self.latest() {
+            Some(snapshot) => Ok(snapshot),
+            None => self.refresh().await,
+        }
+    }
+
+    /// Collects the statistics right away.
+    ///
+    /// # Errors
+    ///
+    /// Fails if no connection can be checked out or the database can't be
+    /// inspected. Missing permissions for `INFO FOR ROOT` are not an error.
+    pub async fn refresh(&self) -> Result<StatsSnapshot> {
+        let pool = self.pool.get()?;
+        let conn = checkout::get(&pool).await?;
+        let root: Option<RootInfo> = conn.query("INFO FOR ROOT").await?.take(0).ok().flatten();
+        let db: Option<DbInfo> = conn.query("INFO FOR DB").await?.check()?.take(0)?;
+        let db = db.unwrap_or_default();
+
+        let mut tables = Vec::with_capacity(db.tables.len());
+        if !db.tables.is_empty() {
+            let sql: String = db
+                .tables
+                .keys()
+                .map(|table| format!("INFO FOR TABLE {};", surql::escape_ident(table)))
+                .collect();
+            let mut response = conn.query(sql).await?.check()?;
+            for (i, name) in db.tables.keys().enumerate() {
+                let info: Option<TableInfo> = response.take(i)?;
+                let info = info.unwrap_or_default();
+                tables.push(TableStats {
+                    name: name.clone(),
+                    fields: info.fields.len(),
+                    indexes: info.indexes.len(),
+                    events: info.events.len(),
+                    live_queries: info.lives.len(),
+                });
+            }
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let snapshot = StatsSnapshot {
+            timestamp_ms: u64::try_from(timestamp.as_millis()).unwrap_or(u64::MAX),
+            namespaces: root
+                .as_ref()
+                .map(|root| root.namespaces.keys().cloned().collect()),
+            root_users: root.map(|root| root.users.into_keys().collect()),
+            users: db.users.into_keys().collect(),
+            functions: db.functions.into_keys().collect(),
+            params: db.params.into_keys().collect(),
+            tables,
+        };
+        *self.latest.lock().unwrap_or_else(PoisonError::into_inner) = Some(snapshot.clone());
+        Ok(snapshot)
+    }
+
+    /// Collects the statistics every `interval` until the [`Pool`] is
+    /// closed or dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoRuntimeSpecified`] if neither an [`Executor`] nor
+    /// a [`Runtime`] was configured for the [`Manager`] of the pool. Failing
+    /// collections keep the previous statistics and don't stop collecting.
+    ///
+    /// [`Executor`]: crate::rt::Executor
+    /// [`Runtime`]: crate::Runtime
+    /// [`Manager`]: crate::Manager
+    pub async fn run(self) -> Result<()> {
+        let executor = self
+            .pool
+            .get()?
+            .manager()
+            .background_executor()
+            .ok_or(Error::NoRuntimeSpecified)?;
+        while !self.pool.is_stopping() {
+            let _ = self.refresh().await;
+            if !self.pool.sleep(&*executor, self.interval).await {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects the statistics as a background task on the [`Executor`] of
+    /// the pool until the [`Pool`] is closed or dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoRuntimeSpecified`] if neither an [`Executor`] nor
+    /// a [`Runtime`] was configured for the [`Manager`] of the pool.
+    ///
+    /// [`Executor`]: crate::rt::Executor
+    /// [`Runtime`]: crate::Runtime
+    /// [`Manager`]: crate::Manager
+    pub fn spawn(self) -> Result<()> {
+        let executor = self
+            .pool
+            .get()?
+            .manager()
+            .background_executor()
+            .ok_or(Error::NoRuntimeSpecified)?;
+        let pool = self.pool.clone();
+        executor.spawn(Box::pin(hook::task(pool, "ServerStats", async move {
+            let _ = self.run().await;
+        })));
+        Ok(())
+    }
+}
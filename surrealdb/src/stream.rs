@@ -0,0 +1,101 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{
+    stream::{self, BoxStream},
+    Stream, StreamExt,
+};
+use serde::de::DeserializeOwned;
+
+use crate::{checkout, surql, Error, Object, Pool};
+
+/// [`Stream`] of deserialized rows returned by [`PoolExt::query_stream()`].
+///
+/// The rows are fetched in pages using `LIMIT` and `START` clauses which are
+/// appended to the query. A single connection is checked out for the whole
+/// lifetime of the stream and returned to the [`Pool`] once the last page
+/// has been read or the stream is dropped.
+///
+/// [`PoolExt::query_stream()`]: crate::PoolExt::query_stream
+pub struct RowStream<T> {
+    inner: BoxStream<'static, Result<T, Error>>,
+}
+
+struct State<T> {
+    pool: Pool,
+    conn: Option<Object>,
+    sql: String,
+    page_size: usize,
+    start: usize,
+    rows: VecDeque<T>,
+    done: bool,
+}
+
+impl<T> RowStream<T>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    pub(crate) fn new(pool: Pool, query: String, page_size: usize) -> Self {
+        let state = State {
+            pool,
+            conn: None,
+            sql: surql::paged(&query),
+            page_size: page_size.max(1),
+            start: 0,
+            rows: VecDeque::new(),
+            done: false,
+        };
+        Self {
+            inner: stream::try_unfold(state, next_row).boxed(),
+        }
+    }
+}
+
+async fn next_row<T>(mut state: State<T>) -> Result<Option<(T, State<T>)>, Error>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    loop {
+        if let Some(row) = state.rows.pop_front() {
+            return Ok(Some((row, state)));
+        }
+        if state.done {
+            return Ok(None);
+        }
+        if state.conn.is_none() {
+            state.conn = Some(checkout::get(&state.pool).await?);
+        }
+        let conn = state.conn.as_ref().unwrap();
+        let rows: Vec<T> = conn
+            .query(state.sql.as_str())
+            .bind(("__limit", state.page_size))
+            .bind(("__start", state.start))
+            .await?
+            .take(0)?;
+        if rows.len() < state.page_size {
+            // Return the connection as early as possible.
+            state.done = true;
+            state.conn = None;
+        }
+        state.start += rows.len();
+        state.rows.extend(rows);
+    }
+}
+
+impl<T> Stream for RowStream<T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl<T> fmt::Debug for RowStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RowStream").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,201 @@
+/// Keywords of the statements controlling transactions, which have no
+/// results of their own.
+pub(crate) const TRANSACTION_STATEMENTS: &[&str] = &["BEGIN", "COMMIT", "CANCEL"];
+
+/// Escapes `name` so it can be interpolated into a statement as an
+/// identifier (e.g. a table name).
+pub(crate) fn escape_ident(name: &str) -> String {
+    format!("`{}`", name.replace('\\', "\\\\").replace('`', "\\`"))
+}
+
+/// Appends `LIMIT $__limit START $__start` clauses to the given `SELECT`
+/// statement.
+pub(crate) fn paged(query: &str) -> String {
+    format!(
+        "{} LIMIT $__limit START $__start",
+        query.trim_end().trim_end_matches(';')
+    )
+}
+
+/// Incrementally splits SurrealQL scripts into single statements.
+///
+/// Statements are separated by `;` outside of strings, escaped identifiers,
+/// comments and blocks. Comments are stripped from the returned statements.
+#[derive(Debug, Default)]
+pub(crate) struct Splitter {
+    pending: String,
+    mode: Mode,
+    prev: Option<char>,
+    escaped: bool,
+    depth: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Mode {
+    #[default]
+    Code,
+    Quoted(char),
+    LineComment,
+    BlockComment,
+}
+
+impl Splitter {
+    /// Feeds `text` into the splitter and returns all statements completed
+    /// by it.
+    pub(crate) fn push(&mut self, text: &str) -> Vec<String> {
+        let mut statements = Vec::new();
+        for c in text.chars() {
+            let prev = self.prev.replace(c);
+            match self.mode {
+                Mode::Code => match c {
+                    '-' | '/' if prev == Some(c) => {
+                        let _ = self.pending.pop();
+                        self.mode = Mode::LineComment;
+                    }
+                    '*' if prev == Some('/') => {
+                        let _ = self.pending.pop();
+                        self.mode = Mode::BlockComment;
+                        self.prev = None;
+                    }
+                    '#' => self.mode = Mode::LineComment,
+                    '\'' | '"' | '`' => {
+                        self.pending.push(c);
+                        self.mode = Mode::Quoted(c);
+                    }
+                    '⟨' => {
+                        self.pending.push(c);
+                        self.mode = Mode::Quoted('⟩');
+                    }
+                    '{' | '(' | '[' => {
+                        self.pending.push(c);
+                        self.depth += 1;
+                    }
+                    '}' | ')' | ']' => {
+                        self.pending.push(c);
+                        self.depth = self.depth.saturating_sub(1);
+                    }
+                    ';' if self.depth == 0 => {
+                        statements.extend(self.take());
+                        self.prev = None;
+                    }
+                    _ => self.pending.push(c),
+                },
+                Mode::Quoted(end) => {
+                    self.pending.push(c);
+                    if self.escaped {
+                        self.escaped = false;
+                    } else if c == '\\' {
+                        self.escaped = true;
+                    } else if c == end {
+                        self.mode = Mode::Code;
+                        self.prev = None;
+                    }
+                }
+                Mode::LineComment => {
+                    if c == '\n' {
+                        self.pending.push(c);
+                        self.mode = Mode::Code;
+                    }
+                }
+                Mode::BlockComment => {
+                    if c == '/' && prev == Some('*') {
+                        self.pending.push(' ');
+                        self.mode = Mode::Code;
+                        self.prev = None;
+                    }
+                }
+            }
+        }
+        statements
+    }
+
+    /// Returns the trailing statement which wasn't terminated by a `;`.
+    pub(crate) fn finish(mut self) -> Option<String> {
+        self.take()
+    }
+
+    fn take(&mut self) -> Option<String> {
+        let statement = self.pending.trim().to_string();
+        self.pending.clear();
+        (!statement.is_empty()).then_some(statement)
+    }
+}
+
+/// Returns the leading keyword of `statement` in upper case.
+pub(crate) fn keyword(statement: &str) -> String {
+    statement
+        .trim_start()
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .next()
+        .unwrap_or_default()
+        .to_ascii_uppercase()
+}
+
+/// Splits a complete SurrealQL script into single statements.
+pub(crate) fn split(script: &str) -> Vec<String> {
+    let mut splitter = Splitter::default();
+    let mut statements = splitter.push(script);
+    statements.extend(splitter.finish());
+    statements
+}
+
+/// Returns the byte offsets and upper case text of the words of `statement`
+/// outside of strings, escaped identifiers and brackets, i.e. its keywords
+/// and the identifiers of its clauses.
+///
+/// Parts of field paths, record ids and parameters aren't returned, so a
+/// field named `order` isn't mistaken for the `ORDER` keyword. The
+/// statement must not contain comments, see [`split()`].
+pub(crate) fn top_level_words(statement: &str) -> Vec<(usize, String)> {
+    let mut words = Vec::new();
+    let mut quote = None;
+    let mut escaped = false;
+    let mut depth = 0_usize;
+    let mut word: Option<(usize, bool)> = None;
+    let mut prev = None;
+    for (i, c) in statement.char_indices() {
+        if let Some(end) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == end {
+                quote = None;
+            }
+            prev = Some(c);
+            continue;
+        }
+        if c.is_ascii_alphanumeric() || c == '_' {
+            if word.is_none() {
+                let top_level = depth == 0 && !matches!(prev, Some('.' | ':' | '$'));
+                word = Some((i, top_level));
+            }
+            prev = Some(c);
+            continue;
+        }
+        if let Some((start, true)) = word.take() {
+            words.push((start, statement[start..i].to_ascii_uppercase()));
+        }
+        match c {
+            '\'' | '"' | '`' => quote = Some(c),
+            '⟨' => quote = Some('⟩'),
+            '{' | '(' | '[' => depth += 1,
+            '}' | ')' | ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        prev = Some(c);
+    }
+    if let Some((start, true)) = word {
+        words.push((start, statement[start..].to_ascii_uppercase()));
+    }
+    words
+}
+
+/// Removes a single level of backticks or angle brackets from an identifier.
+pub(crate) fn unescape_ident(ident: &str) -> &str {
+    ident
+        .strip_prefix('`')
+        .and_then(|i| i.strip_suffix('`'))
+        .or_else(|| ident.strip_prefix('⟨').and_then(|i| i.strip_suffix('⟩')))
+        .unwrap_or(ident)
+}
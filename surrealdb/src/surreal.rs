@@ -0,0 +1,6 @@
+pub use surrealdb::{
+    engine::any::Any,
+    opt::auth::{Database, Jwt, Namespace, Record, Root},
+    sql::Thing,
+    Action, Error, Notification, RecordId, RecordIdKey, Response, Surreal, Value,
+};
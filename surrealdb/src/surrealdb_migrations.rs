@@ -0,0 +1,60 @@
+use ::surrealdb_migrations::MigrationRunner;
+use surrealdb::engine::any::Any;
+
+use crate::{ClientWrapper, Error, Pool, Result};
+
+/// Dedicated connection for running migrations of the
+/// [`surrealdb-migrations`](::surrealdb_migrations) crate.
+///
+/// The connection is created by the [`Pool`]'s manager using the configured
+/// credentials but doesn't occupy a slot of the pool.
+///
+/// ```rust,no_run
+/// # use deadpool_surrealdb::{surrealdb_migrations::SurrealMigrations, Pool};
+/// # async fn example(pool: &Pool) -> Result<(), Box<dyn std::error::Error>> {
+/// let migrations = SurrealMigrations::connect(pool).await?;
+/// // Applies the definitions and migrations found in the working directory.
+/// migrations.up().await?;
+/// // Or configure the runner first.
+/// migrations
+///     .runner()
+///     .use_config_file(".surrealdb")
+///     .up_to("20240101_120000_AddPost")
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SurrealMigrations {
+    conn: ClientWrapper,
+}
+
+impl SurrealMigrations {
+    /// Creates a new dedicated connection using the manager of `pool`.
+    pub async fn connect(pool: &Pool) -> Result<Self> {
+        Ok(Self {
+            conn: pool.manager().connect_dedicated().await?,
+        })
+    }
+
+    /// Returns the dedicated connection, e.g. for checking the outcome of
+    /// the migrations.
+    #[must_use]
+    pub fn conn(&self) -> &ClientWrapper {
+        &self.conn
+    }
+
+    /// Returns a [`MigrationRunner`] bound to the dedicated connection.
+    pub fn runner(&self) -> MigrationRunner<'_, Any> {
+        MigrationRunner::new(self.conn.client())
+    }
+
+    /// Applies all pending migrations using the default configuration of
+    /// [`MigrationRunner`].
+    pub async fn up(&self) -> Result<()> {
+        self.runner()
+            .up()
+            .await
+            .map_err(|e| Error::SurrealMigrations(e.into()))
+    }
+}
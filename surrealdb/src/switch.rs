@@ -0,0 +1,223 @@
+use std::{
+    fmt,
+    sync::{atomic::Ordering, Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use crate::{
+    diagnostics::PoolEvent, hook, shutdown::TaskPool, ClientWrapper, Config, Error, Manager, Pool,
+    Result,
+};
+
+/// Switch of a pool to another endpoint, passed to the function set using
+/// [`Manager::on_endpoint_switch()`].
+///
+/// [`Manager::on_endpoint_switch()`]: crate::Manager::on_endpoint_switch
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EndpointSwitch {
+    /// Host new connections were created for before the switch
+    pub from: String,
+    /// Host new connections are created for after the switch
+    pub to: String,
+    /// Generation of the connections created for the new host
+    pub generation: u64,
+}
+
+type HookFn = dyn Fn(&EndpointSwitch) + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct SwitchHook(pub(crate) Arc<HookFn>);
+
+impl fmt::Debug for SwitchHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SwitchHook").finish_non_exhaustive()
+    }
+}
+
+/// Endpoint switchover of a [`Manager`], see [`PoolExt::prepare_switch()`].
+///
+/// Connections are tagged with the generation of the endpoint they were
+/// created for. Connections of former generations are discarded when they
+/// are returned to the pool.
+///
+/// [`PoolExt::prepare_switch()`]: crate::PoolExt::prepare_switch
+#[derive(Debug, Default)]
+pub(crate) struct Switch(Mutex<State>);
+
+#[derive(Debug, Default)]
+struct State {
+    /// Generation of the connections created now
+    generation: u64,
+    /// Manager creating the connections after a switch
+    target: Option<Arc<Manager>>,
+    /// Connections warmed up for the current generation
+    ready: Vec<ClientWrapper>,
+    /// Switch being prepared
+    standby: Option<Standby>,
+    /// Last generation handed out to a standby
+    prepared: u64,
+}
+
+#[derive(Debug)]
+struct Standby {
+    generation: u64,
+    manager: Arc<Manager>,
+    warm: Vec<ClientWrapper>,
+}
+
+impl Switch {
+    /// Returns the generation of new connections and the manager creating
+    /// them if the pool was switched to another endpoint.
+    pub(crate) fn current(&self) -> (u64, Option<Arc<Manager>>) {
+        let state = self.state();
+        (state.generation, state.target.clone())
+    }
+
+    /// Takes a connection warmed up for the current generation.
+    pub(crate) fn take_ready(&self) -> Option<ClientWrapper> {
+        self.state().ready.pop()
+    }
+
+    fn prepare(&self, manager: Arc<Manager>) -> u64 {
+        let mut state = self.state();
+        state.prepared += 1;
+        let generation = state.prepared;
+        state.standby = Some(Standby {
+            generation,
+            manager,
+            warm: Vec::new(),
+        });
+        generation
+    }
+
+    /// Adds a warmed up connection to the standby of the given
+    /// `generation`. Returns `false` if that standby was replaced or
+    /// committed in the meantime.
+    fn add_warm(&self, generation: u64, conn: ClientWrapper) -> bool {
+        let mut state = self.state();
+        match &mut state.standby {
+            Some(standby) if standby.generation == generation => {
+                standby.warm.push(conn);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Directs new connections at the endpoint of `manager` right away,
+    /// dropping any prepared switch.
+    fn set(&self, manager: Arc<Manager>) -> u64 {
+        let mut state = self.state();
+        state.prepared += 1;
+        state.generation = state.prepared;
+        state.target = Some(manager);
+        state.ready.clear();
+        state.standby = None;
+        state.generation
+    }
+
+    fn commit(&self) -> Option<u64> {
+        let mut state = self.state();
+        let standby = state.standby.take()?;
+        state.generation = standby.generation;
+        state.target = Some(standby.manager);
+        state.ready = standby.warm;
+        Some(standby.generation)
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Warms up connections to the server of `config` in the background.
+pub(crate) fn prepare(pool: &Pool, config: Config) -> Result<()> {
+    let manager = pool.manager();
+    let executor = manager
+        .background_executor()
+        .ok_or(Error::NoRuntimeSpecified)?;
+    let target = Arc::new(manager.retarget(Arc::new(config)));
+    let generation = manager.switch.prepare(target.clone());
+    let count = pool.status().size.max(1);
+    let pool = TaskPool::new(pool);
+    executor.spawn(Box::pin(async move {
+        for _ in 0..count {
+            if pool.is_stopping() {
+                break;
+            }
+            let Ok(pool) = pool.get() else {
+                break;
+            };
+            let manager = pool.manager();
+            let id = manager.next_id.fetch_add(1, Ordering::Relaxed);
+            match target.connect(id).await {
+                Ok(conn) => {
+                    let conn = conn.with_generation(generation);
+                    if !manager.switch.add_warm(generation, conn) {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    manager
+                        .errors
+                        .record(&e.with_context(target.error_context(Some(id))));
+                    break;
+                }
+            }
+        }
+    }));
+    Ok(())
+}
+
+/// Directs new checkouts at the endpoint prepared last and closes the idle
+/// connections to the former one.
+pub(crate) fn commit(pool: &Pool) -> bool {
+    let from = host(pool);
+    let Some(generation) = pool.manager().switch.commit() else {
+        return false;
+    };
+    switched(pool, from, generation);
+    true
+}
+
+/// Directs new connections at `host` right away and closes the idle
+/// connections to the former one.
+pub(crate) fn set_endpoint(pool: &Pool, host: String) {
+    let manager = pool.manager();
+    let from = self::host(pool);
+    let (_, current) = manager.switch.current();
+    let config = Config {
+        host,
+        ..Config::clone(&current.as_deref().unwrap_or(manager).config)
+    };
+    let target = Arc::new(manager.retarget(Arc::new(config)));
+    let generation = manager.switch.set(target);
+    switched(pool, from, generation);
+}
+
+/// Returns the host new connections are currently created for.
+fn host(pool: &Pool) -> String {
+    let manager = pool.manager();
+    let (_, target) = manager.switch.current();
+    target.as_deref().unwrap_or(manager).config.host.clone()
+}
+
+fn switched(pool: &Pool, from: String, generation: u64) {
+    let manager = pool.manager();
+    manager.version.clear();
+    let _ = pool.retain(|conn, _| conn.generation() == generation);
+    let to = host(pool);
+    manager.events.record(PoolEvent::Failover {
+        from: from.clone(),
+        to: to.clone(),
+    });
+    if let Some(hook) = &manager.switch_hook {
+        let event = EndpointSwitch {
+            from,
+            to,
+            generation,
+        };
+        if let Err(e) = hook::catch("on_endpoint_switch", || (hook.0)(&event)) {
+            manager.errors.record(&e);
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::{checkout, Object, Pool, Result};
+
+/// Default name of the session parameter holding the tenant.
+pub const DEFAULT_PARAM: &str = "tenant_id";
+
+/// Default time after which idle tenants are forgotten, see
+/// [`TenantScope::evict_idle_after()`].
+pub const DEFAULT_IDLE_TTL: Duration = Duration::from_secs(600);
+
+/// Hands out connections with a session parameter identifying the tenant.
+///
+/// Table permissions can use the parameter for row level tenancy:
+///
+/// ```surql
+/// DEFINE TABLE invoice PERMISSIONS FOR select, update, delete
+///     WHERE tenant = $tenant_id;
+/// ```
+///
+/// The parameter is removed again before the connection is handed out by
+/// the [`Pool`] the next time, so connections checked out using
+/// [`Pool::get()`] never see the tenant of a previous checkout.
+///
+/// With [`TenantScope::max_per_tenant()`] a single tenant can't occupy more
+/// than the given number of connections. Clones of a [`TenantScope`] share
+/// these limits, so create it once and share it instead of creating a new
+/// one for every checkout. Tenants are forgotten again once they went idle,
+/// see [`TenantScope::evict_idle_after()`].
+///
+/// [`Pool::get()`]: deadpool::managed::Pool::get
+#[derive(Clone, Debug)]
+pub struct TenantScope {
+    pool: Pool,
+    param: String,
+    limits: Option<Arc<Limits>>,
+    idle_ttl: Duration,
+}
+
+impl TenantScope {
+    /// Creates a new [`TenantScope`] using the [`DEFAULT_PARAM`].
+    pub fn new(pool: &Pool) -> Self {
+        Self {
+            pool: pool.clone(),
+            param: DEFAULT_PARAM.to_string(),
+            limits: None,
+            idle_ttl: DEFAULT_IDLE_TTL,
+        }
+    }
+
+    /// Sets the name of the session parameter holding the tenant.
+    #[must_use]
+    pub fn param(mut self, param: impl Into<String>) -> Self {
+        self.param = param.into();
+        self
+    }
+
+    /// Limits the number of connections a single tenant can hold at once.
+    ///
+    /// Checkouts exceeding the limit wait for another connection of the same
+    /// tenant to be returned before competing for a slot of the [`Pool`].
+    /// A `max` of 0 is treated as 1, as no tenant could ever get a
+    /// connection otherwise.
+    #[must_use]
+    pub fn max_per_tenant(mut self, max: usize) -> Self {
+        self.limits = Some(Arc::new(Limits {
+            max_per_tenant: max.max(1),
+            tenants: Mutex::new(Tenants {
+                states: HashMap::new(),
+                swept: self.pool.manager().clock.now(),
+            }),
+        }));
+        self
+    }
+
+    /// Forgets the limits and [`TenantMetrics`] of tenants which neither
+    /// hold nor wait for a connection and didn't start a checkout within
+    /// `ttl`. Defaults to [`DEFAULT_IDLE_TTL`].
+    ///
+    /// This keeps the memory used for tracking tenants bounded by the
+    /// number of recently active tenants. Idle tenants are removed during
+    /// checkouts at most once per `ttl`.
+    #[must_use]
+    pub fn evict_idle_after(mut self, ttl: Duration) -> Self {
+        self.idle_ttl = ttl;
+        self
+    }
+
+    /// Retrieves a connection from the [`Pool`] scoped to `tenant`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Timeout`] if waiting for a slot of the tenant's
+    /// budget and the connection takes longer than the wait timeout of the
+    /// [`Pool`].
+    ///
+    /// [`Error::Timeout`]: crate::Error::Timeout
+    pub async fn get(&self, tenant: impl Into<String>) -> Result<TenantObject> {
+        let tenant = tenant.into();
+        let clock = &self.pool.manager().clock;
+        let started = clock.now();
+        let (permit, conn) = match &self.limits {
+            Some(limits) => {
+                let acquire = limits.acquire(&tenant, started, self.idle_ttl);
+                let (permit, conn) = checkout::gated_get(&self.pool, acquire).await?;
+                limits.record_wait(&tenant, clock.elapsed(started));
+                (Some(permit), conn)
+            }
+            None => (None, checkout::get(&self.pool).await?),
+        };
+        conn.set_param(self.param.clone(), tenant).await?;
+        Ok(TenantObject { conn, permit })
+    }
+
+    /// Returns a [`TenantPool`] retrieving connections scoped to `tenant`,
+    /// e.g. for handing it to code accepting an [`SurrealPool`].
+    ///
+    /// [`SurrealPool`]: crate::SurrealPool
+    #[must_use]
+    pub fn tenant(&self, tenant: impl Into<String>) -> TenantPool {
+        TenantPool {
+            scope: self.clone(),
+            tenant: tenant.into(),
+        }
+    }
+
+    /// Returns the [`TenantMetrics`] of all tenants which checked out a
+    /// connection and haven't been evicted for being idle since.
+    ///
+    /// Metrics are only collected if [`TenantScope::max_per_tenant()`] is
+    /// set.
+    #[must_use]
+    pub fn metrics(&self) -> HashMap<String, TenantMetrics> {
+        let Some(limits) = &self.limits else {
+            return HashMap::new();
+        };
+        limits
+            .lock()
+            .states
+            .iter()
+            .map(|(tenant, state)| {
+                let in_use = limits.max_per_tenant - state.semaphore.available_permits();
+                let metrics = TenantMetrics {
+                    in_use,
+                    ..state.metrics
+                };
+                (tenant.clone(), metrics)
+            })
+            .collect()
+    }
+}
+
+/// [`TenantScope`] bound to a single tenant, see [`TenantScope::tenant()`].
+#[derive(Clone, Debug)]
+pub struct TenantPool {
+    scope: TenantScope,
+    tenant: String,
+}
+
+impl TenantPool {
+    /// Retrieves a connection from the [`Pool`] scoped to the tenant.
+    pub async fn get(&self) -> Result<TenantObject> {
+        self.scope.get(self.tenant.clone()).await
+    }
+
+    /// Returns the tenant connections are scoped to.
+    #[must_use]
+    pub fn tenant(&self) -> &str {
+        &self.tenant
+    }
+
+    /// Returns the [`Pool`] connections are retrieved from.
+    #[must_use]
+    pub fn pool(&self) -> &Pool {
+        &self.scope.pool
+    }
+}
+
+/// Statistics of a single tenant of a [`TenantScope`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TenantMetrics {
+    /// Number of connections currently held by the tenant
+    pub in_use: usize,
+    /// Number of completed checkouts
+    pub checkouts: u64,
+    /// Total time spent waiting for connections
+    pub total_wait: Duration,
+    /// Longest time spent waiting for a single connection
+    pub max_wait: Duration,
+}
+
+#[derive(Debug)]
+struct Limits {
+    max_per_tenant: usize,
+    tenants: Mutex<Tenants>,
+}
+
+#[derive(Debug)]
+struct Tenants {
+    states: HashMap<String, TenantState>,
+    /// Time idle tenants were last removed
+    swept: Instant,
+}
+
+#[derive(Debug)]
+struct TenantState {
+    semaphore: Arc<Semaphore>,
+    metrics: TenantMetrics,
+    last_checkout: Instant,
+}
+
+impl Limits {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Tenants> {
+        self.tenants.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    async fn acquire(
+        &self,
+        tenant: &str,
+        now: Instant,
+        idle_ttl: Duration,
+    ) -> OwnedSemaphorePermit {
+        let semaphore = {
+            let mut tenants = self.lock();
+            if now.saturating_duration_since(tenants.swept) >= idle_ttl {
+                // Permits and waiting checkouts hold a reference to the
+                // semaphore, so tenants using it are never removed.
+                tenants.states.retain(|_, state| {
+                    Arc::strong_count(&state.semaphore) > 1
+                        || now.saturating_duration_since(state.last_checkout) < idle_ttl
+                });
+                tenants.swept = now;
+            }
+            let state = tenants
+                .states
+                .entry(tenant.to_string())
+                .or_insert_with(|| TenantState {
+                    semaphore: Arc::new(Semaphore::new(self.max_per_tenant)),
+                    metrics: TenantMetrics::default(),
+                    last_checkout: now,
+                });
+            state.last_checkout = now;
+            state.semaphore.clone()
+        };
+        // The semaphore is never closed.
+        semaphore.acquire_owned().await.unwrap()
+    }
+
+    fn record_wait(&self, tenant: &str, wait: Duration) {
+        if let Some(state) = self.lock().states.get_mut(tenant) {
+            state.metrics.checkouts += 1;
+            state.metrics.total_wait += wait;
+            state.metrics.max_wait = state.metrics.max_wait.max(wait);
+        }
+    }
+}
+
+/// Connection checked out using a [`TenantScope`].
+///
+/// It dereferences to the pooled [`Object`] and releases its slot of the
+/// tenant's budget once dropped.
+pub struct TenantObject {
+    conn: Object,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl TenantObject {
+    pub(crate) fn into_parts(self) -> (Object, Option<OwnedSemaphorePermit>) {
+        (self.conn, self.permit)
+    }
+}
+
+impl Deref for TenantObject {
+    type Target = Object;
+
+    fn deref(&self) -> &Object {
+        &self.conn
+    }
+}
+
+impl DerefMut for TenantObject {
+    fn deref_mut(&mut self) -> &mut Object {
+        &mut self.conn
+    }
+}
+
+impl fmt::Debug for TenantObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TenantObject")
+            .field("conn", &self.conn)
+            .field("limited", &self.permit.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,342 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use surrealdb::{engine::any::Any, Response, Surreal};
+
+use crate::{
+    client::Binding, clock::Clock, schema::SchemaManager, seed::Seeder, Config, Credentials, Error,
+    Manager, Pool, Result, Runtime,
+};
+
+/// Call recorded by a [`MockManager`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MockCall {
+    /// A connection was created
+    Create,
+    /// A connection was recycled
+    Recycle,
+    /// A query was run using [`ClientWrapper::query()`]
+    ///
+    /// [`ClientWrapper::query()`]: crate::ClientWrapper::query
+    Query(String),
+}
+
+#[derive(Clone)]
+enum MockResponse {
+    Value(Binding),
+    Error(String),
+}
+
+#[derive(Default)]
+struct MockState {
+    responses: Vec<(String, MockResponse)>,
+    create_failures: usize,
+    recycle_failures: usize,
+    calls: Vec<MockCall>,
+}
+
+/// Scriptable stand-in for a database, for unit testing code which depends
+/// on a [`Pool`].
+///
+/// Pools created using [`MockManager::create_pool()`] are regular [`Pool`]s
+/// whose connections use an in-memory datastore instead of a server.
+/// Queries with a scripted response return that response instead of being
+/// run, all other queries are run on the in-memory datastore. Queries are
+/// matched by their text with whitespace normalized.
+///
+/// All clones of a [`MockManager`] share their script and recorded calls,
+/// so responses can still be scripted after the pool has been created.
+///
+/// ```rust,ignore
+/// let mock = MockManager::new();
+/// mock.respond("SELECT * FROM user", vec![User { name: "alice".into() }]);
+/// mock.fail_create(1);
+/// let pool = mock.create_pool(4)?;
+///
+/// assert!(pool.get().await.is_err());
+/// let users: Vec<User> = pool.get().await?.query("SELECT * FROM user").await?.take(0)?;
+/// ```
+#[derive(Clone, Default)]
+pub struct MockManager {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockManager {
+    /// Creates a new [`MockManager`] without any scripted responses.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the query `sql` return `value` as the result of its first
+    /// statement.
+    ///
+    /// Responses scripted later take precedence.
+    pub fn respond(&self, sql: impl Into<String>, value: impl Serialize + Send + Sync + 'static) {
+        let response = MockResponse::Value(Binding(Arc::new(value)));
+        self.state()
+            .responses
+            .push((normalize(&sql.into()), response));
+    }
+
+    /// Makes the query `sql` fail with the given error `message`.
+    pub fn respond_error(&self, sql: impl Into<String>, message: impl Into<String>) {
+        let response = MockResponse::Error(message.into());
+        self.state()
+            .responses
+            .push((normalize(&sql.into()), response));
+    }
+
+    /// Makes the next `times` attempts of creating a connection fail.
+    pub fn fail_create(&self, times: usize) {
+        self.state().create_failures = times;
+    }
+
+    /// Makes the next `times` attempts of recycling a connection fail, which
+    /// makes the pool discard the connection and create a new one.
+    pub fn fail_recycle(&self, times: usize) {
+        self.state().recycle_failures = times;
+    }
+
+    /// Returns all calls recorded so far.
+    #[must_use]
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.state().calls.clone()
+    }
+
+    /// Returns the text of all queries recorded so far.
+    #[must_use]
+    pub fn queries(&self) -> Vec<String> {
+        self.state()
+            .calls
+            .iter()
+            .filter_map(|call| match call {
+                MockCall::Query(sql) => Some(sql.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Forgets all calls recorded so far.
+    pub fn clear_calls(&self) {
+        self.state().calls.clear();
+    }
+
+    /// Creates a new [`Pool`] of up to `max_size` mocked connections.
+    ///
+    /// # Errors
+    ///
+    /// See [`Config::create_pool()`].
+    pub fn create_pool(&self, max_size: usize) -> Result<Pool> {
+        let config = Config::new("mem://", "mock", "mock", Credentials::root("root", "root"));
+        let manager = Manager::from_config(&config).mock(self.clone());
+        Ok(Pool::builder(manager).max_size(max_size).build()?)
+    }
+
+    pub(crate) fn record_create(&self) -> Result<()> {
+        let mut state = self.state();
+        state.calls.push(MockCall::Create);
+        if state.create_failures > 0 {
+            state.create_failures -= 1;
+            return Err(Error::Connection {
+                message: "Mocked connection failure".into(),
+                context: None,
+                source: None,
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn record_recycle(&self) -> std::result::Result<(), String> {
+        let mut state = self.state();
+        state.calls.push(MockCall::Recycle);
+        if state.recycle_failures > 0 {
+            state.recycle_failures -= 1;
+            return Err("Mocked recycle failure".to_string());
+        }
+        Ok(())
+    }
+
+    /// Records the query `sql` and returns its scripted response, if any.
+    pub(crate) async fn query(&self, client: &Surreal<Any>, sql: &str) -> Option<Result<Response>> {
+        let response = {
+            let mut state = self.state();
+            state.calls.push(MockCall::Query(sql.to_string()));
+            let sql = normalize(sql);
+            state
+                .responses
+                .iter()
+                .rev()
+                .find(|(s, _)| *s == sql)
+                .map(|(_, response)| response.clone())
+        }?;
+        Some(match response {
+            MockResponse::Value(value) => client
+                .query("RETURN $__mock")
+                .bind(("__mock", value))
+                .await
+                .map_err(Error::from),
+            MockResponse::Error(message) => Err(Error::Surreal(surrealdb::Error::Api(
+                surrealdb::error::Api::Query(message),
+            ))),
+        })
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for MockManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state();
+        f.debug_struct("MockManager")
+            .field("responses", &state.responses.len())
+            .field("create_failures", &state.create_failures)
+            .field("recycle_failures", &state.recycle_failures)
+            .field("calls", &state.calls)
+            .finish()
+    }
+}
+
+fn normalize(sql: &str) -> String {
+    sql.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Creates a [`Pool`] backed by an in-memory datastore using the namespace
+/// `ns` and the database `db`.
+///
+/// See [`MemPool`] for details and for applying a schema or fixtures.
+pub async fn mem_pool(ns: impl Into<String>, db: impl Into<String>) -> Result<Pool> {
+    MemPool::new(ns, db).build().await
+}
+
+/// Builder for a [`Pool`] backed by an in-memory datastore.
+///
+/// Every `mem://` connection has its own datastore, so the pool holds a
+/// single connection by default. This way all checkouts see the same data,
+/// including the schema and fixtures applied when building the pool.
+///
+/// ```rust,ignore
+/// let pool = MemPool::new("test", "test")
+///     .schema(SchemaManager::from_script(include_str!("schema.surql"))?)
+///     .seeder(Seeder::new().file("fixtures/user.json")?)
+///     .build()
+///     .await?;
+/// ```
+#[derive(Debug)]
+pub struct MemPool {
+    config: Config,
+    runtime: Option<Runtime>,
+    schema: Option<SchemaManager>,
+    seeder: Option<Seeder>,
+}
+
+impl MemPool {
+    /// Creates a new [`MemPool`] builder for the namespace `ns` and the
+    /// database `db`.
+    pub fn new(ns: impl Into<String>, db: impl Into<String>) -> Self {
+        let mut config = Config::new("mem://", ns, db, Credentials::root("root", "root"));
+        config.max_connections = 1;
+        config.connect_timeout = 5;
+        config.idle_timeout = 5;
+        Self {
+            config,
+            runtime: None,
+            schema: None,
+            seeder: None,
+        }
+    }
+
+    /// Sets the [`Runtime`] used for enforcing timeouts.
+    #[must_use]
+    pub fn runtime(mut self, runtime: Runtime) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Sets the maximum number of connections.
+    ///
+    /// Connections don't share their data, so this is only useful for tests
+    /// which don't rely on data written using another connection.
+    #[must_use]
+    pub fn max_connections(mut self, max_connections: u32) -> Self {
+        self.config.max_connections = max_connections;
+        self
+    }
+
+    /// Sets the schema synced when building the pool.
+    #[must_use]
+    pub fn schema(mut self, schema: SchemaManager) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Sets the fixtures loaded when building the pool, after syncing the
+    /// schema.
+    #[must_use]
+    pub fn seeder(mut self, seeder: Seeder) -> Self {
+        self.seeder = Some(seeder);
+        self
+    }
+
+    /// Creates the [`Pool`] and applies the schema and fixtures.
+    pub async fn build(self) -> Result<Pool> {
+        let pool = self.config.create_pool(self.runtime)?;
+        if let Some(schema) = &self.schema {
+            let _ = schema.sync(&pool).await?;
+        }
+        if let Some(seeder) = &self.seeder {
+            seeder.run(&pool).await?;
+        }
+        Ok(pool)
+    }
+}
+
+/// [`Clock`] which only advances when told to.
+///
+/// It starts at the instant it was created. Clones share the same time.
+///
+/// ```rust,ignore
+/// let clock = ManualClock::new();
+/// let manager = Manager::from_config(&config).clock(clock.clone());
+/// // ...
+/// clock.advance(Duration::from_secs(60));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    start: Instant,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl ManualClock {
+    /// Creates a new [`ManualClock`] starting now.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed: Arc::new(Mutex::new(Duration::ZERO)),
+        }
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap_or_else(PoisonError::into_inner) += duration;
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + *self.elapsed.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
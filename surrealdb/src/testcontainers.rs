@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use ::testcontainers::{
+    core::{IntoContainerPort, WaitFor},
+    runners::AsyncRunner,
+    ContainerAsync, GenericImage, ImageExt, TestcontainersError,
+};
+use deadpool::managed::Manager as _;
+
+use crate::{rt, Config, Credentials, Error, Manager, Pool, Result, Runtime};
+
+/// Docker image started by [`SurrealDbContainer`].
+pub const IMAGE: &str = "surrealdb/surrealdb";
+
+/// Tag of [`IMAGE`] started by [`SurrealDbContainer::start()`].
+pub const DEFAULT_TAG: &str = "v2.1.4";
+
+/// Port SurrealDB listens on inside the container.
+pub const PORT: u16 = 8000;
+
+const USER: &str = "root";
+const PASS: &str = "root";
+const READY_ATTEMPTS: u32 = 50;
+const READY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// SurrealDB server running in a Docker container.
+///
+/// The server stores its data in memory and accepts the root user `root`
+/// with the password `root`. The container is removed when this value is
+/// dropped.
+///
+/// ```rust,ignore
+/// let surrealdb = SurrealDbContainer::start().await?;
+/// let pool = surrealdb.create_pool()?;
+/// let conn = pool.get().await?;
+/// ```
+pub struct SurrealDbContainer {
+    container: ContainerAsync<GenericImage>,
+    config: Config,
+}
+
+impl SurrealDbContainer {
+    /// Starts a container of [`IMAGE`] with the [`DEFAULT_TAG`].
+    ///
+    /// See [`SurrealDbContainer::start_with_tag()`] for details.
+    pub async fn start() -> Result<Self> {
+        Self::start_with_tag(DEFAULT_TAG).await
+    }
+
+    /// Starts a container of [`IMAGE`] with the given `tag` and waits until
+    /// the server accepts connections.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`Error::Container`] if the container can't be started, or
+    /// with the error of the last connection attempt if the server doesn't
+    /// become ready in time.
+    pub async fn start_with_tag(tag: &str) -> Result<Self> {
+        let container = GenericImage::new(IMAGE, tag)
+            .with_exposed_port(PORT.tcp())
+            .with_wait_for(WaitFor::message_on_stderr("Started web server on "))
+            .with_env_var("SURREAL_USER", USER)
+            .with_env_var("SURREAL_PASS", PASS)
+            .with_env_var("SURREAL_CAPS_ALLOW_ALL", "true")
+            .with_env_var("SURREAL_PATH", "memory")
+            .with_cmd(["start"])
+            .start()
+            .await?;
+        let host = container.get_host().await?;
+        let port = container.get_host_port_ipv4(PORT).await?;
+        let mut config = Config::new(
+            format!("ws://{}:{}", host, port),
+            "test",
+            "test",
+            Credentials::root(USER, PASS),
+        );
+        config.create_if_missing = true;
+
+        let this = Self { container, config };
+        this.wait_ready().await?;
+        Ok(this)
+    }
+
+    /// Returns the [`Config`] for connecting to the server.
+    ///
+    /// It uses the namespace and database `test`, which are created on first
+    /// use.
+    #[must_use]
+    pub fn config(&self) -> Config {
+        self.config.clone()
+    }
+
+    /// Creates a new [`Pool`] connected to the server.
+    ///
+    /// # Errors
+    ///
+    /// See [`Config::create_pool()`].
+    pub fn create_pool(&self) -> Result<Pool> {
+        self.config.create_pool(Some(Runtime::Tokio1))
+    }
+
+    /// Returns the underlying container.
+    #[must_use]
+    pub fn container(&self) -> &ContainerAsync<GenericImage> {
+        &self.container
+    }
+
+    /// The server logs that it started listening a moment before it actually
+    /// accepts connections, so this retries connecting for a while.
+    async fn wait_ready(&self) -> Result<()> {
+        let manager = Manager::from_config(&self.config).runtime(Runtime::Tokio1);
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match manager.create().await {
+                Ok(conn) => return conn.ping().await,
+                Err(e) if attempts >= READY_ATTEMPTS => return Err(e),
+                Err(_) => rt::sleep(Runtime::Tokio1, READY_INTERVAL).await,
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for SurrealDbContainer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SurrealDbContainer")
+            .field("id", &self.container.id())
+            .field("host", &self.config.host)
+            .finish()
+    }
+}
+
+impl From<TestcontainersError> for Error {
+    fn from(e: TestcontainersError) -> Self {
+        Self::Container(Box::new(e))
+    }
+}
@@ -0,0 +1,202 @@
+use std::{
+    fmt,
+    ops::Deref,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures::future::BoxFuture;
+use http::{Request, Response, StatusCode};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::{checkout, request::RequestConn, Object, Pool};
+
+/// [`Layer`] adding the [`Pool`] or a connection checked out of it to the
+/// extensions of every request.
+///
+/// By default the [`Pool`] itself is added. With [`PoolLayer::checkout()`]
+/// a connection is checked out before the request is passed on and added as
+/// [`SharedConn`] instead. If no connection can be checked out the request
+/// is answered right away with the status of [`Error::http_status()`]. With
+/// [`PoolLayer::per_request()`] a [`RequestConn`] is added, which checks out
+/// a connection only once it is used.
+///
+/// [`Error::http_status()`]: crate::Error::http_status
+///
+/// ```rust,ignore
+/// let app = Router::new()
+///     .route("/", get(|Extension(conn): Extension<SharedConn>| async move { ... }))
+///     .layer(PoolLayer::new(&pool).checkout(true));
+/// ```
+#[derive(Clone, Debug)]
+pub struct PoolLayer {
+    pool: Pool,
+    checkout: bool,
+    per_request: bool,
+    stats: Arc<Stats>,
+}
+
+impl PoolLayer {
+    /// Creates a new [`PoolLayer`] adding the `pool` to requests.
+    pub fn new(pool: &Pool) -> Self {
+        Self {
+            pool: pool.clone(),
+            checkout: false,
+            per_request: false,
+            stats: Arc::default(),
+        }
+    }
+
+    /// Checks out a connection for every request.
+    #[must_use]
+    pub fn checkout(mut self, checkout: bool) -> Self {
+        self.checkout = checkout;
+        self
+    }
+
+    /// Adds a [`RequestConn`] to every request, so all data-access calls
+    /// handling the request share a single connection checked out on first
+    /// use.
+    ///
+    /// This is ignored if [`PoolLayer::checkout()`] is enabled.
+    #[must_use]
+    pub fn per_request(mut self, per_request: bool) -> Self {
+        self.per_request = per_request;
+        self
+    }
+
+    /// Returns statistics about the connections checked out so far.
+    ///
+    /// These are shared by all services created by this layer.
+    #[must_use]
+    pub fn stats(&self) -> CheckoutStats {
+        self.stats.snapshot()
+    }
+}
+
+impl<S> Layer<S> for PoolLayer {
+    type Service = PoolService<S>;
+
+    fn layer(&self, inner: S) -> PoolService<S> {
+        PoolService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// [`Service`] created by [`PoolLayer`].
+#[derive(Clone, Debug)]
+pub struct PoolService<S> {
+    inner: S,
+    layer: PoolLayer,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for PoolService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send,
+    ReqBody: Send + 'static,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        // Use the service which was driven to readiness.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let layer = self.layer.clone();
+        Box::pin(async move {
+            if !layer.checkout {
+                if layer.per_request {
+                    let _ = req.extensions_mut().insert(RequestConn::new(&layer.pool));
+                } else {
+                    let _ = req.extensions_mut().insert(layer.pool);
+                }
+                return inner.call(req).await;
+            }
+            let started = Instant::now();
+            let result = checkout::get(&layer.pool).await;
+            layer.stats.record(started.elapsed());
+            match result {
+                Ok(conn) => {
+                    let _ = req.extensions_mut().insert(SharedConn(Arc::new(conn)));
+                    inner.call(req).await
+                }
+                Err(e) => {
+                    let status = StatusCode::from_u16(e.http_status())
+                        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+                    let mut res = Response::new(ResBody::default());
+                    *res.status_mut() = status;
+                    Ok(res)
+                }
+            }
+        })
+    }
+}
+
+/// Connection checked out by [`PoolLayer`] for a single request.
+///
+/// It is returned to the [`Pool`] once all clones are dropped.
+#[derive(Clone)]
+pub struct SharedConn(Arc<Object>);
+
+impl Deref for SharedConn {
+    type Target = Object;
+
+    fn deref(&self) -> &Object {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SharedConn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SharedConn").field(&*self.0).finish()
+    }
+}
+
+/// Statistics about the connections checked out by a [`PoolLayer`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CheckoutStats {
+    /// Number of checkouts, including failed ones
+    pub checkouts: u64,
+    /// Total time spent checking out connections
+    pub total_latency: Duration,
+    /// Longest time spent checking out a single connection
+    pub max_latency: Duration,
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    checkouts: AtomicU64,
+    total_nanos: AtomicU64,
+    max_nanos: AtomicU64,
+}
+
+impl Stats {
+    fn record(&self, latency: Duration) {
+        let nanos = u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX);
+        let _ = self.checkouts.fetch_add(1, Ordering::Relaxed);
+        let _ = self.total_nanos.fetch_add(nanos, Ordering::Relaxed);
+        let _ = self.max_nanos.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> CheckoutStats {
+        CheckoutStats {
+            checkouts: self.checkouts.load(Ordering::Relaxed),
+            total_latency: Duration::from_nanos(self.total_nanos.load(Ordering::Relaxed)),
+            max_latency: Duration::from_nanos(self.max_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use std::{collections::BTreeMap, fmt, sync::Arc};
+
+use crate::hook;
+
+/// Identifiers of the distributed trace a query belongs to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TraceContext {
+    /// Id of the trace
+    pub trace_id: String,
+    /// Id of the span issuing the query
+    pub span_id: String,
+}
+
+impl TraceContext {
+    /// Creates a new [`TraceContext`].
+    pub fn new(trace_id: impl Into<String>, span_id: impl Into<String>) -> Self {
+        Self {
+            trace_id: trace_id.into(),
+            span_id: span_id.into(),
+        }
+    }
+
+    /// Parses a W3C `traceparent` header value like
+    /// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`.
+    #[must_use]
+    pub fn from_traceparent(traceparent: &str) -> Option<Self> {
+        let mut parts = traceparent.trim().split('-');
+        let _version = parts.next()?;
+        let trace_id = parts.next().filter(|id| id.len() == 32)?;
+        let span_id = parts.next().filter(|id| id.len() == 16)?;
+        Some(Self::new(trace_id, span_id))
+    }
+}
+
+type ContextProvider = dyn Fn() -> Option<TraceContext> + Send + Sync;
+
+/// Prepends a comment identifying the service, the current trace and further
+/// tags to the text of every query, e.g.
+///
+/// ```text
+/// /* service='billing',handler='invoices',trace_id='4bf92f35...',span_id='00f067aa...' */ SELECT ...
+/// ```
+///
+/// so slow query logs of the server can be correlated with distributed
+/// traces and grouped by application context. Tags are sorted by their key.
+/// Keys and values are percent encoded so they can't terminate the comment.
+///
+/// ```rust,ignore
+/// let comments = QueryComments::new()
+///     .service("billing")
+///     .tag("region", "eu-west-1")
+///     .context(|| current_span().map(|s| TraceContext::new(s.trace_id(), s.span_id())));
+/// let manager = Manager::from_config(&config).query_comments(comments);
+/// ```
+#[derive(Clone, Default)]
+pub struct QueryComments {
+    service: Option<Arc<str>>,
+    tags: Vec<(String, String)>,
+    context: Option<Arc<ContextProvider>>,
+}
+
+impl QueryComments {
+    /// Creates new [`QueryComments`] without service name and trace context.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the name of the service issuing the queries.
+    #[must_use]
+    pub fn service(mut self, service: impl Into<String>) -> Self {
+        self.service = Some(service.into().into());
+        self
+    }
+
+    /// Adds a tag to the comment of every query.
+    ///
+    /// Tags of single queries can be added using [`Query::tag()`], which
+    /// take precedence over tags with the same key added here.
+    ///
+    /// [`Query::tag()`]: crate::client::Query::tag
+    #[must_use]
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.push((key.into(), value.into()));
+        self
+    }
+
+    /// Sets the function returning the [`TraceContext`] of the current
+    /// span.
+    ///
+    /// It is called right before a query is sent, on the task which awaits
+    /// the query.
+    #[must_use]
+    pub fn context(
+        mut self,
+        context: impl Fn() -> Option<TraceContext> + Send + Sync + 'static,
+    ) -> Self {
+        self.context = Some(Arc::new(context));
+        self
+    }
+
+    /// Returns `sql` with the comment prepended. The given `context` and
+    /// `tags` take precedence over the configured ones.
+    pub(crate) fn apply(
+        &self,
+        sql: String,
+        context: Option<&TraceContext>,
+        tags: &[(String, String)],
+    ) -> String {
+        let provided;
+        let context = match context {
+            Some(context) => Some(context),
+            None => {
+                provided = self
+                    .context
+                    .as_ref()
+                    .and_then(|f| hook::catch("query comment context", &**f).ok().flatten());
+                provided.as_ref()
+            }
+        };
+        let mut fields = Vec::new();
+        if let Some(service) = &self.service {
+            fields.push(format!("service='{}'", encode(service)));
+        }
+        let tags: BTreeMap<_, _> = self.tags.iter().chain(tags).cloned().collect();
+        for (key, value) in &tags {
+            fields.push(format!("{}='{}'", encode(key), encode(value)));
+        }
+        if let Some(context) = context {
+            fields.push(format!("trace_id='{}'", encode(&context.trace_id)));
+            fields.push(format!("span_id='{}'", encode(&context.span_id)));
+        }
+        if fields.is_empty() {
+            return sql;
+        }
+        format!("/* {} */ {}", fields.join(","), sql)
+    }
+}
+
+impl fmt::Debug for QueryComments {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueryComments")
+            .field("service", &self.service)
+            .field("tags", &self.tags)
+            .field("context", &self.context.is_some())
+            .finish()
+    }
+}
+
+fn encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b':') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
@@ -0,0 +1,51 @@
+use std::{
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::{checkout, Pool, Result};
+
+/// Time after which the version of the server is probed again, so upgrades
+/// of the server are noticed.
+const REFRESH_AFTER: Duration = Duration::from_secs(600);
+
+/// Version of the server of a [`Manager`] and when it was probed, see
+/// [`PoolExt::server_version()`].
+///
+/// [`Manager`]: crate::Manager
+/// [`PoolExt::server_version()`]: crate::PoolExt::server_version
+#[derive(Debug, Default)]
+pub(crate) struct VersionCache(Mutex<Option<(String, Instant)>>);
+
+impl VersionCache {
+    /// Forgets the cached version, e.g. after switching to another server.
+    pub(crate) fn clear(&self) {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
+
+/// Returns the cached version of the server of `pool`, probing it if it is
+/// missing or outdated.
+pub(crate) async fn server_version(pool: &Pool) -> Result<String> {
+    let manager = pool.manager();
+    let now = manager.clock.now();
+    if let Some((version, probed)) = &*manager
+        .version
+        .0
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+    {
+        if now.saturating_duration_since(*probed) < REFRESH_AFTER {
+            return Ok(version.clone());
+        }
+    }
+    let conn = checkout::get(pool).await?;
+    // Connections cache the version they saw first, so ask the server.
+    let version = conn.client().version().await?.to_string();
+    *manager
+        .version
+        .0
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = Some((version.clone(), now));
+    Ok(version)
+}
@@ -0,0 +1,40 @@
+// Runs without the default `rt_tokio_1` feature, so async-std is the only
+// runtime of the pool:
+//
+//     cargo test --no-default-features --features rt_async-std_1
+#![cfg(feature = "rt_async-std_1")]
+
+use std::time::Duration;
+
+use deadpool_runtime::Runtime;
+use deadpool_surrealdb::{Config, Credentials, PoolExt};
+
+fn default_config() -> Config {
+    Config::new("mem://", "test", "test", Credentials::root("root", "root"))
+}
+
+#[async_std::test]
+async fn async_std_runtime() {
+    let mut cfg = default_config();
+    cfg.max_connections = 1;
+    cfg.connect_timeout = 5;
+    let pool = cfg.create_pool(Some(Runtime::AsyncStd1)).unwrap();
+    let conn = pool.get().await.unwrap();
+    conn.ping().await.unwrap();
+    let value: Option<i32> = conn
+        .query_with_timeout("RETURN 1 + 1", Duration::from_secs(5))
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(value, Some(2));
+}
+
+#[async_std::test]
+async fn async_std_pool_query() {
+    let mut cfg = default_config();
+    cfg.default_query_timeout = Some(5);
+    let pool = cfg.create_pool(Some(Runtime::AsyncStd1)).unwrap();
+    let value: Option<i32> = pool.query("RETURN 1 + 1").await.unwrap().take(0).unwrap();
+    assert_eq!(value, Some(2));
+}
@@ -1,40 +1,30 @@
+// These tests run the pools on tokio, see `async_std.rs` for async-std.
+#![cfg(feature = "rt_tokio_1")]
+
 use std::{collections::HashMap, env, time::Duration};
 
 use serde::{Deserialize, Serialize};
-use deadpool_surrealdb::{Config, Credentials, Pool};
+use deadpool_surrealdb::{config::ENV_PREFIX, Config, Credentials, Pool, PoolExt};
 use deadpool_runtime::Runtime;
 use surrealdb::Error;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug)]
 struct TestConfig {
-    #[serde(default = "default_config")]
     surrealdb: Config,
 }
 
 fn default_config() -> Config {
-    Config::new(
-        "mem://".to_string(),
-        "test".to_string(),
-        "test".to_string(),
-        Credentials::Root {
-            user: "root".to_string(),
-            pass: "root".to_string(),
-        },
-    )
+    Config::new("mem://", "test", "test", Credentials::root("root", "root"))
 }
 
 impl TestConfig {
+    /// Overrides the in-memory defaults with the `SURREALDB__*` variables,
+    /// panicking on invalid ones instead of silently ignoring them.
     pub fn from_env() -> Self {
-        let cfg = config::Config::builder()
-            .add_source(config::Environment::default().separator("__"))
-            .build()
-            .unwrap();
-
-        let cfg = cfg.try_deserialize::<Self>().unwrap_or_else(|_| TestConfig {
-            surrealdb: default_config(),
-        });
-        
-        cfg
+        let surrealdb = default_config()
+            .merge_env(ENV_PREFIX)
+            .unwrap_or_else(|e| panic!("invalid test configuration: {}", e));
+        TestConfig { surrealdb }
     }
 }
 
@@ -107,6 +97,37 @@ async fn parallel_queries() -> Result<(), Error> {
     Ok(())
 }
 
+#[tokio::test]
+async fn scope() {
+    use deadpool_surrealdb::scope::Scope;
+
+    let pool = create_pool();
+    let values = pool
+        .scope(|s: Scope| async move {
+            let handles: Vec<_> = (0..10)
+                .map(|i| s.query("RETURN $i").bind(("i", i)).spawn())
+                .collect();
+            let mut values = Vec::new();
+            for handle in handles {
+                let value: Option<i64> = handle.await?.take(0)?;
+                values.push(value.unwrap());
+            }
+            Ok::<_, deadpool_surrealdb::Error>(values)
+        })
+        .await
+        .unwrap();
+    assert_eq!(values, (0..10).collect::<Vec<_>>());
+
+    let result = pool
+        .scope(|s: Scope| async move {
+            let _slow = s.spawn_query("SLEEP 5s");
+            let _failing = s.spawn_query("THROW 'boom'");
+            Ok::<_, deadpool_surrealdb::Error>(())
+        })
+        .await;
+    assert!(result.is_err());
+}
+
 #[tokio::test]
 async fn connection_timeout() {
     let mut cfg = TestConfig::from_env();
@@ -163,31 +184,19 @@ async fn connection_health() -> Result<(), surrealdb::Error> {
 async fn auth_methods() -> Result<(), surrealdb::Error> {
     // Test root auth
     let mut cfg = TestConfig::from_env();
-    cfg.surrealdb.creds = Credentials::Root {
-        user: String::new(),
-        pass: String::new(),
-    };
+    cfg.surrealdb.creds = Credentials::root("", "");
     let pool = cfg.surrealdb.create_pool(Some(Runtime::Tokio1)).unwrap();
     let conn = pool.get().await.unwrap();
     assert!(conn.health().await.is_ok());
     
     // Test namespace auth
-    cfg.surrealdb.creds = Credentials::Namespace {
-        user: "test".to_string(),
-        pass: "test".to_string(),
-        ns: "test".to_string(),
-    };
+    cfg.surrealdb.creds = Credentials::namespace("test", "test", "test");
     let pool = cfg.surrealdb.create_pool(Some(Runtime::Tokio1)).unwrap();
     let conn = pool.get().await.unwrap();
     assert!(conn.health().await.is_ok());
     
     // Test database auth
-    cfg.surrealdb.creds = Credentials::Database {
-        user: "test".to_string(),
-        pass: "test".to_string(),
-        ns: "test".to_string(),
-        db: "test".to_string(),
-    };
+    cfg.surrealdb.creds = Credentials::database("test", "test", "test", "test");
     let pool = cfg.surrealdb.create_pool(Some(Runtime::Tokio1)).unwrap();
     let conn = pool.get().await.unwrap();
     assert!(conn.health().await.is_ok());
@@ -195,47 +204,2673 @@ async fn auth_methods() -> Result<(), surrealdb::Error> {
     Ok(())
 }
 
-#[cfg(feature = "serde")]
-#[test]
-fn config_from_env() {
-    let mut env = Env::new();
-    env.set("SURREALDB__HOST", "mem://");
-    env.set("SURREALDB__NS", "test");
-    env.set("SURREALDB__DB", "test");
-    env.set("SURREALDB__CONNECT_TIMEOUT", "10");
-    env.set("SURREALDB__MAX_CONNECTIONS", "20");
-    env.set("SURREALDB__CREDS__ROOT__USER", "");
-    env.set("SURREALDB__CREDS__ROOT__PASS", "");
-    
-    let cfg = TestConfig::from_env();
-    assert_eq!(cfg.surrealdb.host, "mem://");
-    assert_eq!(cfg.surrealdb.ns, "test");
-    assert_eq!(cfg.surrealdb.db, "test");
-    assert_eq!(cfg.surrealdb.connect_timeout, 10);
-    assert_eq!(cfg.surrealdb.max_connections, 20);
-    match cfg.surrealdb.creds {
-        Credentials::Root { user, pass } => {
-            assert_eq!(user, "");
-            assert_eq!(pass, "");
-        }
-        _ => panic!("Expected root credentials"),
+#[tokio::test]
+async fn bulk_insert() {
+    #[derive(Serialize)]
+    struct Item {
+        value: usize,
     }
+
+    let pool = create_pool();
+    let items = (0..25).map(|value| Item { value }).collect();
+    let report = pool.bulk_insert("bulk", items, 10, 2).await;
+
+    assert!(report.is_complete());
+    assert_eq!(report.chunks, 3);
+    assert_eq!(report.inserted, 25);
+
+    // Errors which can't be fixed by retrying fail the chunk right away.
+    let pool = create_pool();
+    let conn = pool.get().await.unwrap();
+    let _ = conn
+        .query("DEFINE FIELD value ON bulk TYPE int ASSERT $value < 25")
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+    drop(conn);
+    let items = vec![Item { value: 25 }];
+    let report = pool.bulk_insert("bulk", items, 10, 1).await;
+    assert_eq!(report.inserted, 0);
+    let failed = &report.failed[0];
+    assert_eq!(failed.attempts, 1);
+    assert!(!failed.error.is_retryable());
+
+    // Chunks which timed out may have been inserted, so they aren't retried.
+    let mut cfg = default_config();
+    cfg.default_query_timeout = Some(1);
+    let pool = cfg.create_pool(Some(Runtime::Tokio1)).unwrap();
+    let conn = pool.get().await.unwrap();
+    let _ = conn
+        .query("DEFINE EVENT slow ON bulk WHEN $event = 'CREATE' THEN { SLEEP 3s }")
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+    drop(conn);
+    let items = vec![Item { value: 1 }];
+    let report = pool.bulk_insert("bulk", items, 10, 1).await;
+    assert_eq!(report.inserted, 0);
+    let failed = &report.failed[0];
+    assert_eq!(failed.attempts, 1);
+    assert!(matches!(
+        failed.error,
+        deadpool_surrealdb::Error::QueryTimeout(_)
+    ));
 }
 
-struct Env {
-    backup: HashMap<String, Option<String>>,
+#[tokio::test]
+async fn bulk_upsert() {
+    use deadpool_surrealdb::bulk::UpsertStrategy;
+
+    #[derive(Serialize)]
+    struct Item {
+        value: usize,
+    }
+
+    let pool = create_pool();
+    let conn = pool.get().await.unwrap();
+    let _ = conn
+        .query("DEFINE FIELD value ON upsert TYPE int ASSERT $value < 20")
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+    drop(conn);
+
+    let items = (0..25).map(|value| (value.to_string(), Item { value })).collect();
+    let report = pool.bulk_upsert("upsert", items, UpsertStrategy::MergePatch, 10, 1).await;
+    assert_eq!(report.chunks, 3);
+    assert_eq!(report.upserted, 20);
+    assert!(report.failed_chunks.is_empty());
+    assert_eq!(report.failed_items.len(), 5);
+    assert_eq!(report.failed_items[0].offset, 20);
+    assert_eq!(report.failed_items[0].id, "20");
+
+    let items = vec![("1".to_string(), Item { value: 2 })];
+    let report = pool.bulk_upsert("upsert", items, UpsertStrategy::Replace, 10, 1).await;
+    assert!(report.is_complete());
 }
 
-impl Env {
-    pub fn new() -> Self {
-        Self {
-            backup: HashMap::new(),
+#[tokio::test]
+async fn query_stream() {
+    use futures::TryStreamExt;
+
+    let pool = create_pool();
+    let conn = pool.get().await.unwrap();
+    let _ = conn
+        .query("FOR $i IN 0..25 { CREATE stream SET value = $i }")
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+    // Return the connection so the stream reuses its in-memory datastore.
+    drop(conn);
+
+    let rows: Vec<usize> = pool
+        .query_stream("SELECT VALUE value FROM stream ORDER BY value", 10)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(rows, (0..25).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn paginator() {
+    use deadpool_surrealdb::paginate::{Cursor, Paginator};
+
+    let pool = create_pool();
+    let conn = pool.get().await.unwrap();
+    let _ = conn
+        .query("FOR $i IN 0..5 { CREATE type::thing('page', $i) SET value = $i }")
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+    drop(conn);
+
+    let paginator =
+        Paginator::<usize>::new(&pool, "SELECT VALUE value FROM page ORDER BY value", 2).unwrap();
+    let page = paginator.first().await.unwrap();
+    assert_eq!(page.items, vec![0, 1]);
+
+    // The cursor points behind the last row instead of at an offset, so
+    // removing rows before it doesn't shift the next page.
+    let conn = pool.get().await.unwrap();
+    let _ = conn.query("DELETE page:0").await.unwrap().check().unwrap();
+    drop(conn);
+    let cursor: Cursor = page.next.unwrap().to_string().parse().unwrap();
+    let page = paginator.page(Some(&cursor)).await.unwrap();
+    assert_eq!(page.items, vec![2, 3]);
+
+    let page = paginator.page(page.next.as_ref()).await.unwrap();
+    assert_eq!(page.items, vec![4]);
+    assert!(page.next.is_none());
+    assert!("garbage".parse::<Cursor>().is_err());
+
+    // Record ids are restored from the cursor.
+    let paginator = Paginator::<usize>::new(
+        &pool,
+        "SELECT VALUE value FROM page WHERE value > 1 OR value = 1 ORDER BY id DESC",
+        3,
+    )
+    .unwrap();
+    let page = paginator.first().await.unwrap();
+    assert_eq!(page.items, vec![4, 3, 2]);
+    let next: Cursor = page.next.unwrap().to_string().parse().unwrap();
+    let page = paginator.page(Some(&next)).await.unwrap();
+    assert_eq!(page.items, vec![1]);
+    assert!(page.next.is_none());
+
+    let result = Paginator::<usize>::new(&pool, "SELECT VALUE value FROM page", 2);
+    assert!(matches!(result, Err(deadpool_surrealdb::Error::InvalidQuery(_))));
+}
+
+#[tokio::test]
+async fn query_with_timeout() {
+    let pool = create_pool();
+    let conn = pool.get().await.unwrap();
+    assert!(!conn.is_suspect());
+
+    let result = conn
+        .query_with_timeout("SLEEP 2s", Duration::from_millis(100))
+        .await;
+    assert!(matches!(
+        result,
+        Err(deadpool_surrealdb::Error::QueryTimeout(_))
+    ));
+    assert!(conn.is_suspect());
+
+    // Suspect connections are discarded instead of being recycled.
+    drop(conn);
+    let conn = pool.get().await.unwrap();
+    assert!(!conn.is_suspect());
+}
+
+#[tokio::test]
+async fn default_query_timeout() {
+    let mut cfg = default_config();
+    cfg.default_query_timeout = Some(1);
+    assert!(matches!(
+        cfg.create_pool(None),
+        Err(deadpool_surrealdb::Error::InvalidConfig(_))
+    ));
+    let pool = cfg.create_pool(Some(Runtime::Tokio1)).unwrap();
+    let conn = pool.get().await.unwrap();
+    let result = conn.query("SLEEP 3s").await;
+    assert!(matches!(
+        result,
+        Err(deadpool_surrealdb::Error::QueryTimeout(_))
+    ));
+    assert!(conn.is_suspect());
+    drop(conn);
+
+    let conn = pool.get().await.unwrap();
+    let _ = conn
+        .query("SLEEP 1500ms")
+        .timeout(Duration::from_secs(5))
+        .await
+        .unwrap();
+    drop(conn);
+
+    let result = pool.query("SLEEP 3s").await;
+    assert!(matches!(
+        result,
+        Err(deadpool_surrealdb::Error::QueryTimeout(_))
+    ));
+
+    let cfg = Config::builder()
+        .host("mem://")
+        .namespace("test")
+        .database("test")
+        .credentials(Credentials::root("root", "root"))
+        .default_query_timeout(30)
+        .build()
+        .unwrap();
+    assert_eq!(cfg.default_query_timeout, Some(30));
+}
+
+#[tokio::test]
+async fn pool_query() {
+    let mut cfg = default_config();
+    cfg.retry_on_disconnect = true;
+    let pool = cfg.create_pool(Some(Runtime::Tokio1)).unwrap();
+    let value: Option<i64> = pool
+        .query("RETURN $value")
+        .bind(("value", 42))
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(value, Some(42));
+    assert_eq!(pool.status().size, 1);
+}
+
+#[tokio::test]
+async fn pipeline() {
+    use deadpool_surrealdb::pipeline::Pipeline;
+
+    let pool = create_pool();
+    let mut results = Pipeline::new()
+        .statement("CREATE person:a SET age = $age")
+        .statement("LET $b = 2; RETURN $b + $age; -- sum")
+        .statement("BEGIN; SELECT VALUE age FROM person; COMMIT;")
+        .bind(("age", 40))
+        .run(&pool)
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 3);
+    let sum: Option<i64> = results.take(1).unwrap();
+    assert_eq!(sum, Some(42));
+    let ages: Vec<i64> = results.take(2).unwrap();
+    assert_eq!(ages, vec![40]);
+
+    let result = Pipeline::new()
+        .statement("RETURN 1")
+        .statement("-- nothing to see here")
+        .run(&pool)
+        .await;
+    assert!(matches!(result, Err(deadpool_surrealdb::Error::InvalidQuery(_))));
+}
+
+#[tokio::test]
+async fn update_if_version() {
+    #[derive(Debug, Deserialize)]
+    struct Account {
+        balance: i64,
+        version: u64,
+    }
+
+    let pool = create_pool();
+    let conn = pool.get().await.unwrap();
+    let _ = conn
+        .query("CREATE account:a SET balance = 10, version = 1")
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+    let patch = HashMap::from([("balance", 20)]);
+    let account: Account = conn
+        .update_if_version("account", "a", 1, patch.clone())
+        .await
+        .unwrap();
+    assert_eq!(account.balance, 20);
+    assert_eq!(account.version, 2);
+
+    let result = conn.update_if_version::<Account, _>("account", "a", 1, patch).await;
+    assert!(matches!(
+        result,
+        Err(deadpool_surrealdb::Error::Conflict { expected_version: 1, .. })
+    ));
+    let err = result.unwrap_err();
+    assert!(err.is_conflict());
+    assert!(!err.is_retryable());
+
+    let _ = conn
+        .query("CREATE account:7 SET balance = 10, version = 1")
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+    let account: Account = conn
+        .update_if_version("account", 7_i64, 1, HashMap::from([("balance", 30)]))
+        .await
+        .unwrap();
+    assert_eq!(account.balance, 30);
+    assert_eq!(account.version, 2);
+    let result = conn
+        .update_if_version::<Account, _>("account", 7_i64, 1, HashMap::from([("balance", 40)]))
+        .await;
+    assert!(matches!(
+        result,
+        Err(deadpool_surrealdb::Error::Conflict { ref id, .. }) if id == "7"
+    ));
+}
+
+#[tokio::test]
+async fn analytics_pool() {
+    use deadpool_surrealdb::AnalyticsConfig;
+
+    let pool = create_pool();
+    assert_eq!(pool.analytics().status().max_size, pool.status().max_size);
+
+    let mut cfg = default_config();
+    cfg.default_query_timeout = Some(1);
+    cfg.reserved_for_writes = 2;
+    cfg.analytics = Some(AnalyticsConfig::default());
+    let pool = cfg.create_pool(Some(Runtime::Tokio1)).unwrap();
+    let analytics = pool.analytics();
+    assert_eq!(analytics.status().max_size, 2);
+    assert_eq!(analytics.timeouts().wait, Some(Duration::from_secs(300)));
+    assert_eq!(analytics.manager().config().default_query_timeout, None);
+    assert_eq!(analytics.manager().config().reserved_for_writes, 0);
+    let conn = analytics.get().await.unwrap();
+    let _ = conn.query("SLEEP 1500ms").await.unwrap();
+    assert_eq!(pool.status().size, 0);
+
+    cfg.analytics = Some(AnalyticsConfig {
+        max_connections: 0,
+        ..AnalyticsConfig::default()
+    });
+    let result = cfg.create_pool(Some(Runtime::Tokio1));
+    assert!(
+        matches!(result, Err(deadpool_surrealdb::Error::InvalidConfig(ref message)) if message.starts_with("analytics pool:"))
+    );
+}
+
+#[tokio::test]
+async fn serverless() {
+    use deadpool_surrealdb::serverless;
+
+    let pool = serverless::pool(|| default_config().serverless(), Some(Runtime::Tokio1)).unwrap();
+    assert_eq!(pool.status().max_size, 1);
+    assert_eq!(pool.status().size, 0);
+    pool.get().await.unwrap().ping().await.unwrap();
+
+    let same = serverless::pool(|| unreachable!(), None).unwrap();
+    assert!(std::ptr::eq(pool, same));
+    serverless::shutdown();
+    assert!(pool.is_closed());
+}
+
+#[tokio::test]
+async fn k8s_probes() {
+    use deadpool_surrealdb::k8s::Probes;
+
+    let pool = create_pool();
+    let probes = Probes::new(&pool)
+        .timeout(Duration::from_secs(5))
+        .failure_threshold(2);
+    assert!(probes.liveness().ok);
+    assert!(probes.readiness().await.ok);
+
+    pool.close();
+    let result = probes.readiness().await;
+    assert!(result.ok);
+    assert_eq!(result.consecutive_failures, 1);
+    assert!(result.error.is_some());
+    assert!(!probes.readiness().await.ok);
+    assert!(!probes.liveness().ok);
+}
+
+#[tokio::test]
+async fn k8s_liveness() {
+    use deadpool_surrealdb::k8s::Probes;
+
+    let pool = create_pool();
+    pool.resize(1);
+    let probes = Probes::new(&pool).failure_threshold(2);
+    let conn = pool.get().await.unwrap();
+    let waiter = tokio::spawn({
+        let pool = pool.clone();
+        async move { pool.get().await.map(drop) }
+    });
+    while pool.status().waiting == 0 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    // A saturated pool only fails once checkouts stop making progress.
+    assert!(probes.liveness().ok);
+    let result = probes.liveness();
+    assert!(result.ok);
+    assert_eq!(result.consecutive_failures, 1);
+    assert!(!probes.liveness().ok);
+
+    drop(conn);
+    waiter.await.unwrap().unwrap();
+    assert!(probes.liveness().ok);
+}
+
+#[tokio::test]
+async fn k8s_check_all() {
+    use deadpool_surrealdb::{k8s::check_all, Manager};
+
+    let main = create_pool();
+    let tenant = default_config().create_pool(Some(Runtime::Tokio1)).unwrap();
+    let cfg = Config {
+        host: "ws://127.0.0.1:1".to_string(),
+        ..default_config()
+    };
+    let broken = Pool::builder(Manager::from_config(&cfg).runtime(Runtime::Tokio1))
+        .build()
+        .unwrap();
+
+    let deadline = Duration::from_secs(5);
+    let report = check_all([("main", &main), ("tenant", &tenant)], deadline).await;
+    assert!(report.is_ok());
+    assert_eq!(report.pools[1].name, "tenant");
+
+    let pools = [("main", &main), ("broken", &broken)];
+    let report = check_all(pools, deadline).await;
+    assert!(!report.is_ok());
+    let failed: Vec<_> = report.failed().map(|pool| pool.name.as_str()).collect();
+    assert_eq!(failed, vec!["broken"]);
+    assert!(report.pools[1].error.is_some());
+    assert_eq!(report.pools[1].fingerprint, cfg.fingerprint());
+}
+
+#[tokio::test]
+async fn custom_executor() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use deadpool_surrealdb::{
+        backup::{BackupScheduler, FsSink},
+        rt::{Sleep, Spawn},
+        Manager,
+    };
+    use futures::future::BoxFuture;
+
+    #[derive(Clone, Default)]
+    struct CountingExecutor {
+        spawned: Arc<AtomicUsize>,
+        slept: Arc<AtomicUsize>,
+    }
+
+    impl Spawn for CountingExecutor {
+        fn spawn(&self, future: BoxFuture<'static, ()>) {
+            self.spawned.fetch_add(1, Ordering::Relaxed);
+            drop(tokio::spawn(future));
         }
     }
-    
-    pub fn set(&mut self, name: &str, value: &str) {
-        self.backup.insert(name.to_string(), env::var(name).ok());
-        env::set_var(name, value);
+
+    impl Sleep for CountingExecutor {
+        fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+            self.slept.fetch_add(1, Ordering::Relaxed);
+            Box::pin(tokio::time::sleep(duration))
+        }
+    }
+
+    let executor = CountingExecutor::default();
+    let manager = Manager::from_config(&default_config()).executor(executor.clone());
+    let pool = Pool::builder(manager).max_size(1).build().unwrap();
+    // Backups fail as the directory doesn't exist, which doesn't stop the scheduler.
+    let sink = FsSink::new(env::temp_dir().join("deadpool-surrealdb-missing"), Runtime::Tokio1);
+    BackupScheduler::new(&pool, sink, Duration::from_millis(10))
+        .spawn()
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    pool.close();
+
+    assert_eq!(executor.spawned.load(Ordering::Relaxed), 1);
+    assert!(executor.slept.load(Ordering::Relaxed) > 1);
+}
+
+#[tokio::test]
+async fn pool_errors() {
+    use deadpool_surrealdb::{Error, TimeoutPhase};
+
+    let mut cfg = default_config();
+    cfg.max_connections = 1;
+    cfg.connect_timeout = 1;
+    let pool = cfg.create_pool(Some(Runtime::Tokio1)).unwrap();
+    let conn = pool.get().await.unwrap();
+    let err = Error::from(pool.get().await.unwrap_err());
+    assert!(matches!(err, Error::Timeout { phase: TimeoutPhase::Wait }));
+    drop(conn);
+
+    pool.close();
+    let err = Error::from(pool.get().await.unwrap_err());
+    assert!(matches!(err, Error::PoolClosed));
+}
+
+#[tokio::test]
+async fn error_sources() {
+    let mut cfg = default_config();
+    cfg.host = "unknown://localhost".to_string();
+    let pool = cfg.create_pool(Some(Runtime::Tokio1)).unwrap();
+    let err = deadpool_surrealdb::Error::from(pool.get().await.unwrap_err());
+    assert!(matches!(err, deadpool_surrealdb::Error::Connection { .. }));
+    let source = std::error::Error::source(&err).unwrap();
+    assert!(source.downcast_ref::<Error>().is_some());
+    assert!(err.is_retryable());
+    assert!(!err.is_auth());
+    assert!(!err.is_connection_lost());
+}
+
+#[tokio::test]
+async fn error_context() {
+    let mut cfg = default_config();
+    cfg.host = "unknown://localhost".to_string();
+    cfg.name = Some("primary".to_string());
+    let pool = cfg.create_pool(Some(Runtime::Tokio1)).unwrap();
+    let err = deadpool_surrealdb::Error::from(pool.get().await.unwrap_err());
+    let context = err.context().unwrap();
+    assert_eq!(context.pool.as_deref(), Some("primary"));
+    assert_eq!(context.endpoint, "unknown://localhost");
+    assert!(context.connection.is_some());
+    let message = err.to_string();
+    assert!(message.contains("pool primary"));
+    assert!(message.contains("endpoint unknown://localhost"));
+
+    let pool = create_pool();
+    let first = pool.get().await.unwrap();
+    let second = pool.get().await.unwrap();
+    assert_ne!(first.id(), second.id());
+}
+
+#[tokio::test]
+async fn error_kind() {
+    use deadpool_surrealdb::{Error, ErrorKind, TimeoutPhase};
+
+    assert_eq!(Error::Timeout { phase: TimeoutPhase::Create }.kind(), ErrorKind::Timeout);
+    assert_eq!(Error::QueryTimeout(Duration::from_secs(1)).kind(), ErrorKind::Timeout);
+    assert_eq!(Error::PoolClosed.kind(), ErrorKind::PoolClosed);
+    assert_eq!(Error::ReadOnly("DELETE".to_string()).kind(), ErrorKind::ReadOnly);
+    assert_eq!(Error::MissingDownScript(1).kind(), ErrorKind::Migration);
+
+    let mut cfg = default_config();
+    cfg.host = "unknown://localhost".to_string();
+    let pool = cfg.create_pool(Some(Runtime::Tokio1)).unwrap();
+    let err = Error::from(pool.get().await.unwrap_err());
+    assert_eq!(err.kind(), ErrorKind::Connection);
+
+    let pool = create_pool();
+    let conn = pool.get().await.unwrap();
+    let err = conn.query("SELECT * FROM").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Query);
+}
+
+#[tokio::test]
+async fn checkout_failure_hook() {
+    use std::sync::{Arc, Mutex};
+
+    use deadpool_surrealdb::{checkout::CheckoutFailure, ErrorKind, Manager};
+
+    let failures = Arc::new(Mutex::new(Vec::<CheckoutFailure>::new()));
+    let recorded = failures.clone();
+    let manager = Manager::from_config(&default_config())
+        .runtime(Runtime::Tokio1)
+        .on_checkout_failure(move |failure| recorded.lock().unwrap().push(*failure));
+    let pool = Pool::builder(manager)
+        .max_size(1)
+        .wait_timeout(Some(Duration::from_millis(50)))
+        .runtime(Runtime::Tokio1)
+        .build()
+        .unwrap();
+
+    let conn = pool.checkout().await.unwrap();
+    assert!(failures.lock().unwrap().is_empty());
+
+    let err = pool.checkout().await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Timeout);
+    let failures = failures.lock().unwrap();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].kind, ErrorKind::Timeout);
+    assert!(failures[0].waited >= Duration::from_millis(50));
+    assert_eq!(failures[0].status.size, 1);
+    assert_eq!(failures[0].status.available, 0);
+    drop(conn);
+}
+
+#[tokio::test]
+async fn panicking_hooks() {
+    use deadpool_surrealdb::{trace::QueryComments, ErrorKind, Manager};
+
+    let comments = QueryComments::new().context(|| panic!("no span"));
+    let manager = Manager::from_config(&default_config())
+        .runtime(Runtime::Tokio1)
+        .query_comments(comments)
+        .on_checkout_failure(|_| panic!("hook failed"));
+    let pool = Pool::builder(manager)
+        .max_size(1)
+        .wait_timeout(Some(Duration::from_millis(50)))
+        .runtime(Runtime::Tokio1)
+        .build()
+        .unwrap();
+
+    let conn = pool.checkout().await.unwrap();
+    let _ = conn.query("RETURN 1").await.unwrap();
+    let err = pool.checkout().await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Timeout);
+    drop(conn);
+
+    let dump = pool.debug_dump();
+    let panicked = dump.recent_errors.iter().find(|e| e.message.contains("panicked"));
+    assert_eq!(panicked.unwrap().message, "on_checkout_failure panicked: hook failed");
+    assert!(pool.checkout().await.is_ok());
+}
+
+#[tokio::test]
+async fn http_status() {
+    use deadpool_surrealdb::{Error, TimeoutPhase};
+
+    assert_eq!(Error::Timeout { phase: TimeoutPhase::Wait }.http_status(), 503);
+    assert_eq!(Error::PoolClosed.http_status(), 503);
+    assert_eq!(Error::QueryTimeout(Duration::from_secs(1)).http_status(), 504);
+    assert_eq!(Error::InvalidCursor("0".to_string()).http_status(), 400);
+    assert_eq!(Error::ReadOnly("DELETE".to_string()).http_status(), 500);
+
+    let pool = create_pool();
+    let conn = pool.get().await.unwrap();
+    let err = conn.query("SELECT * FROM").await.unwrap_err();
+    assert_eq!(err.http_status(), 500);
+}
+
+#[tokio::test]
+async fn address_family() {
+    use deadpool_surrealdb::{AddressFamily, ErrorKind};
+
+    let cfg = Config::builder()
+        .host("ws://localhost:1")
+        .namespace("test")
+        .database("test")
+        .credentials(Credentials::root("root", "root"))
+        .address_family(AddressFamily::PreferIpv6)
+        .build()
+        .unwrap();
+    assert_eq!(cfg.address_family, AddressFamily::PreferIpv6);
+
+    // Nothing listens on port 1, so both address families are tried.
+    let pool = cfg.create_pool(Some(Runtime::Tokio1)).unwrap();
+    let err = deadpool_surrealdb::Error::from(pool.get().await.unwrap_err());
+    assert_eq!(err.kind(), ErrorKind::Connection);
+}
+
+#[tokio::test]
+async fn debug_dump() {
+    use deadpool_surrealdb::{ErrorKind, Manager};
+
+    let mut cfg = default_config();
+    cfg.name = Some("primary".to_string());
+    let pool = Pool::builder(Manager::from_config(&cfg).runtime(Runtime::Tokio1))
+        .max_size(1)
+        .wait_timeout(Some(Duration::from_millis(50)))
+        .runtime(Runtime::Tokio1)
+        .build()
+        .unwrap();
+    let conn = pool.get().await.unwrap();
+    let id = conn.id();
+    assert!(pool.checkout().await.is_err());
+    drop(conn);
+
+    let dump = pool.debug_dump();
+    assert_eq!(dump.config.name.as_deref(), Some("primary"));
+    assert_eq!(dump.config.credentials, "root");
+    assert_eq!(dump.config.fingerprint, cfg.fingerprint());
+    assert_eq!(dump.status.size, 1);
+    assert_eq!(dump.connections.len(), 1);
+    assert_eq!(dump.connections[0].id, id);
+    assert_eq!(dump.recent_errors.len(), 1);
+    assert_eq!(dump.recent_errors[0].kind, ErrorKind::Timeout);
+    assert!(!format!("{:?}", dump).contains("pass"));
+}
+
+#[tokio::test]
+async fn failure_reasons() {
+    use deadpool_surrealdb::{diagnostics::FailureReason, Manager};
+
+    let cfg = Config {
+        host: "ws://127.0.0.1:1".to_string(),
+        ..default_config()
+    };
+    let pool = Pool::builder(Manager::from_config(&cfg).runtime(Runtime::Tokio1))
+        .max_size(1)
+        .runtime(Runtime::Tokio1)
+        .build()
+        .unwrap();
+    assert!(pool.checkout().await.is_err());
+
+    let failures = pool.failures();
+    assert_eq!(failures.create.io, 1);
+    assert_eq!(failures.create.total(), 1);
+    assert_eq!(failures.recycle.total(), 0);
+    assert_eq!(pool.debug_dump().failures, failures);
+    let labelled: Vec<_> = failures.labelled().filter(|(_, _, count)| *count > 0).collect();
+    assert_eq!(labelled, vec![("create", FailureReason::Io, 1)]);
+    assert_eq!(
+        FailureReason::of(&deadpool_surrealdb::Error::QueryTimeout(Duration::from_secs(1))),
+        FailureReason::Timeout
+    );
+}
+
+#[tokio::test]
+async fn recent_events() {
+    use deadpool_surrealdb::diagnostics::PoolEvent;
+
+    let pool = create_pool();
+    let conn = pool.get().await.unwrap();
+    let id = conn.id();
+    conn.mark_suspect();
+    drop(conn);
+    let _ = pool.get().await.unwrap();
+
+    let events: Vec<_> = pool.recent_events().into_iter().map(|e| e.event).collect();
+    assert!(matches!(&events[0], PoolEvent::Created { id: created, .. } if *created == id));
+    assert_eq!(
+        events[1],
+        PoolEvent::Evicted {
+            id,
+            reason: "Connection marked as suspect"
+        }
+    );
+    assert!(matches!(events[2], PoolEvent::Created { .. }));
+    assert_eq!(pool.debug_dump().recent_events.len(), 3);
+    let json = serde_json::to_value(&pool.recent_events()[1]).unwrap();
+    assert_eq!(json["type"], "evicted");
+}
+
+#[tokio::test]
+async fn diagnose() {
+    use deadpool_surrealdb::diagnostics;
+
+    let steps = diagnostics::diagnose(&default_config(), Runtime::Tokio1).await;
+    let names: Vec<_> = steps.iter().map(|step| step.name).collect();
+    assert_eq!(names, ["connect", "use ns/db", "ping"]);
+    assert!(steps.iter().all(|step| step.error.is_none()));
+
+    let mut cfg = default_config();
+    cfg.host = "unknown://localhost".to_string();
+    let steps = diagnostics::diagnose(&cfg, Runtime::Tokio1).await;
+    assert_eq!(steps.len(), 1);
+    assert!(steps[0].error.is_some());
+}
+
+#[tokio::test]
+async fn manager_config() {
+    let mut cfg = default_config();
+    cfg.name = Some("primary".to_string());
+    let pool = cfg.create_pool(Some(Runtime::Tokio1)).unwrap();
+    let config = pool.manager().config();
+    assert_eq!(config.name.as_deref(), Some("primary"));
+    assert_eq!(config.host, "mem://");
+
+    let shared = pool.manager().shared_config();
+    let manager = deadpool_surrealdb::Manager::from_shared_config(shared.clone());
+    assert!(std::sync::Arc::ptr_eq(&manager.shared_config(), &shared));
+
+    let manager = deadpool_surrealdb::Manager::from(default_config());
+    assert_eq!(manager.config().host, "mem://");
+}
+
+#[tokio::test]
+async fn engine_config() {
+    use deadpool_surrealdb::Manager;
+
+    let engine = surrealdb::opt::Config::default().query_timeout(Duration::from_secs(5));
+    let manager = Manager::from_config(&default_config())
+        .runtime(Runtime::Tokio1)
+        .engine_config(engine);
+    let pool = Pool::builder(manager).max_size(1).build().unwrap();
+    let conn = pool.get().await.unwrap();
+    let value: Option<i32> = conn.query("RETURN 1 + 1").await.unwrap().take(0).unwrap();
+    assert_eq!(value, Some(2));
+}
+
+#[tokio::test]
+async fn error_classification() {
+    use deadpool_surrealdb::{Error, TimeoutPhase};
+
+    let timeout = Error::Timeout { phase: TimeoutPhase::Wait };
+    assert!(timeout.is_retryable());
+    assert!(!Error::PoolClosed.is_retryable());
+    assert!(!Error::ReadOnly("DELETE".to_string()).is_retryable());
+
+    let pool = create_pool();
+    let conn = pool.get().await.unwrap();
+    let err = conn.query("SELECT * FROM").await.unwrap_err();
+    assert!(!err.is_retryable());
+    assert!(!err.is_auth());
+    assert!(!err.is_connection_lost());
+}
+
+#[tokio::test]
+async fn ping_and_version() {
+    let pool = create_pool();
+    let conn = pool.get().await.unwrap();
+    conn.ping().await.unwrap();
+    let version = conn.server_version().await.unwrap();
+    assert!(!version.is_empty());
+    assert_eq!(conn.server_version().await.unwrap(), version);
+}
+
+#[cfg(feature = "sessions")]
+#[tokio::test]
+async fn session_store() {
+    use std::sync::Arc;
+
+    use deadpool_surrealdb::sessions::SurrealSessionStore;
+    use time::{Duration, OffsetDateTime};
+    use tower_sessions_core::{session::Expiry, ExpiredDeletion, Session, SessionStore};
+
+    let pool = create_pool();
+    let store = Arc::new(SurrealSessionStore::new(&pool).table("test_sessions"));
+
+    let session = Session::new(None, store.clone(), Some(Expiry::OnInactivity(Duration::hours(1))));
+    session.insert("user", "alice").await.unwrap();
+    session.save().await.unwrap();
+    let id = session.id().unwrap();
+
+    let loaded = Session::new(Some(id), store.clone(), None);
+    assert_eq!(loaded.get::<String>("user").await.unwrap(), Some("alice".to_string()));
+
+    let expiry = Expiry::AtDateTime(OffsetDateTime::now_utc() - Duration::hours(1));
+    let expired = Session::new(None, store.clone(), Some(expiry));
+    expired.insert("user", "bob").await.unwrap();
+    expired.save().await.unwrap();
+    let expired_id = expired.id().unwrap();
+    assert!(store.load(&expired_id).await.unwrap().is_none());
+    store.delete_expired().await.unwrap();
+
+    store.delete(&id).await.unwrap();
+    assert!(store.load(&id).await.unwrap().is_none());
+}
+
+#[cfg(feature = "test-utils")]
+#[tokio::test]
+async fn manual_clock() {
+    use deadpool_surrealdb::{test_utils::ManualClock, Manager};
+
+    let clock = ManualClock::new();
+    let mut cfg = default_config();
+    cfg.max_idle_time = Some(10);
+    let manager = Manager::from_config(&cfg).clock(clock.clone());
+    let pool = Pool::builder(manager).max_size(1).build().unwrap();
+
+    let id = pool.get().await.unwrap().id();
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(pool.get().await.unwrap().id(), id);
+    clock.advance(Duration::from_secs(11));
+    assert_ne!(pool.get().await.unwrap().id(), id);
+}
+
+#[cfg(feature = "test-utils")]
+#[tokio::test]
+async fn session_expired() {
+    use deadpool_surrealdb::test_utils::MockManager;
+
+    let mock = MockManager::new();
+    mock.respond_error("SELECT * FROM user", "The session has expired");
+    mock.respond_error("SELECT * FROM post", "boom");
+    let pool = mock.create_pool(1).unwrap();
+    let conn = pool.get().await.unwrap();
+    assert!(conn.query("SELECT * FROM user").await.unwrap_err().is_session_expired());
+    assert!(!conn.query("SELECT * FROM post").await.unwrap_err().is_session_expired());
+
+    let cfg = Config::builder()
+        .host("ws://localhost:8000")
+        .namespace("test")
+        .database("test")
+        .credentials(Credentials::root("root", "root"))
+        .reauth_on_expiry(true)
+        .build()
+        .unwrap();
+    assert!(cfg.reauth_on_expiry);
+}
+
+#[cfg(feature = "test-utils")]
+#[tokio::test]
+async fn offline_buffer() {
+    use deadpool_surrealdb::{
+        offline::{ConflictAction, OfflineBuffer, WriteOutcome},
+        test_utils::MockManager,
+    };
+
+    let mock = MockManager::new();
+    let pool = mock.create_pool(1).unwrap();
+    let path = env::temp_dir().join(format!("offline-buffer-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let buffer = OfflineBuffer::new(&pool, 2).persist(&path).unwrap();
+
+    mock.fail_create(2);
+    let outcome = buffer
+        .write("UPSERT reading:1 SET value = $value")
+        .bind(("value", 1))
+        .await
+        .unwrap();
+    assert_eq!(outcome, WriteOutcome::Buffered);
+    let outcome = buffer.write("UPSERT reading:2 SET value = 2").await.unwrap();
+    assert_eq!(outcome, WriteOutcome::Buffered);
+    assert!(buffer.write("UPSERT reading:3 SET value = 3").await.is_err());
+    let result = buffer
+        .write("UPSERT reading:3 SET value = $value")
+        .bind(("value", HashMap::from([((1, 2), 3)])))
+        .await;
+    assert!(matches!(result, Err(deadpool_surrealdb::Error::Serialization(_))));
+    assert_eq!(OfflineBuffer::new(&pool, 2).persist(&path).unwrap().len(), 2);
+
+    // A line cut off while appending it is ignored.
+    let mut log = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(&path, format!("{}{{\"sql\":\"UPSE", log)).unwrap();
+    assert_eq!(OfflineBuffer::new(&pool, 2).persist(&path).unwrap().len(), 2);
+    // Corrupt lines anywhere else are reported.
+    std::fs::write(&path, format!("garbage\n{}", log)).unwrap();
+    assert!(matches!(
+        OfflineBuffer::new(&pool, 2).persist(&path),
+        Err(deadpool_surrealdb::Error::Serialization(_))
+    ));
+    log.push_str("{\"done\":1}\n");
+    std::fs::write(&path, log).unwrap();
+    let reloaded = OfflineBuffer::new(&pool, 2).persist(&path).unwrap();
+    assert_eq!(reloaded.pending()[0].sql, "UPSERT reading:2 SET value = 2");
+
+    assert_eq!(buffer.replay().await.unwrap(), 0);
+    assert_eq!(buffer.replay().await.unwrap(), 2);
+    assert!(buffer.is_empty());
+    assert!(OfflineBuffer::new(&pool, 2).persist(&path).unwrap().is_empty());
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+    let outcome = buffer.write("UPSERT reading:3 SET value = 3").await.unwrap();
+    assert_eq!(outcome, WriteOutcome::Written);
+    let conn = pool.get().await.unwrap();
+    let values: Vec<i64> = conn
+        .query("SELECT VALUE value FROM reading ORDER BY value")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(values, vec![1, 2, 3]);
+    drop(conn);
+    let _ = std::fs::remove_file(&path);
+
+    let pool = mock.create_pool(1).unwrap();
+    let skipping = OfflineBuffer::new(&pool, 10);
+    let stopping = skipping.clone().on_conflict(|_, _| ConflictAction::Stop);
+    mock.respond_error("UPSERT reading:4 SET value = 4", "Conflict");
+    mock.fail_create(1);
+    let outcome = stopping.write("UPSERT reading:4 SET value = 4").await.unwrap();
+    assert_eq!(outcome, WriteOutcome::Buffered);
+    assert_eq!(stopping.replay().await.unwrap(), 0);
+    assert_eq!(skipping.len(), 1);
+    assert_eq!(skipping.replay().await.unwrap(), 0);
+    assert!(stopping.is_empty());
+}
+
+#[cfg(feature = "test-utils")]
+#[tokio::test]
+async fn mock_manager() {
+    use deadpool_surrealdb::test_utils::{MockCall, MockManager};
+
+    let mock = MockManager::new();
+    mock.respond("SELECT * FROM user", vec!["alice", "bob"]);
+    mock.respond_error("SELECT * FROM  post", "boom");
+    mock.fail_create(1);
+    let pool = mock.create_pool(1).unwrap();
+
+    assert!(pool.get().await.is_err());
+    let conn = pool.get().await.unwrap();
+    let users: Vec<String> = conn.query("SELECT *\nFROM user").await.unwrap().take(0).unwrap();
+    assert_eq!(users, vec!["alice", "bob"]);
+    assert!(conn.query("SELECT * FROM post").await.is_err());
+    let value: Option<i32> = conn.query("RETURN 1").await.unwrap().take(0).unwrap();
+    assert_eq!(value, Some(1));
+    drop(conn);
+
+    mock.fail_recycle(1);
+    let _conn = pool.get().await.unwrap();
+    assert_eq!(
+        mock.calls(),
+        vec![
+            MockCall::Create,
+            MockCall::Create,
+            MockCall::Query("SELECT *\nFROM user".to_string()),
+            MockCall::Query("SELECT * FROM post".to_string()),
+            MockCall::Query("RETURN 1".to_string()),
+            MockCall::Recycle,
+            MockCall::Create,
+        ]
+    );
+}
+
+#[cfg(feature = "test-utils")]
+#[tokio::test]
+async fn mem_pool() {
+    use deadpool_surrealdb::{schema::SchemaManager, seed::Seeder, test_utils::MemPool};
+
+    let pool = MemPool::new("fixture", "fixture")
+        .runtime(Runtime::Tokio1)
+        .schema(SchemaManager::from_script("DEFINE TABLE user SCHEMALESS;").unwrap())
+        .seeder(Seeder::new().json("user", r#"[{"id": "alice"}, {"id": "bob"}]"#))
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(pool.status().max_size, 1);
+    let conn = pool.get().await.unwrap();
+    let count: Option<usize> = conn
+        .query("RETURN count(SELECT * FROM user)")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(count, Some(2));
+    drop(conn);
+
+    let pool = deadpool_surrealdb::test_utils::mem_pool("a", "b").await.unwrap();
+    pool.get().await.unwrap().ping().await.unwrap();
+}
+
+#[cfg(feature = "test-utils")]
+#[tokio::test]
+async fn chaos_manager() {
+    use deadpool_surrealdb::{chaos::ChaosManager, Manager};
+
+    let manager = ChaosManager::new(Manager::from_config(&default_config()))
+        .seed(7)
+        .runtime(Runtime::Tokio1)
+        .create_failure_rate(0.5)
+        .create_delay(1.0, Duration::from_millis(1))
+        .drop_rate(1.0);
+    let pool = deadpool::managed::Pool::builder(manager)
+        .max_size(1)
+        .build()
+        .unwrap();
+
+    let mut created = 0;
+    let mut idle = false;
+    for _ in 0..20 {
+        idle = match pool.get().await {
+            Ok(conn) => {
+                conn.ping().await.unwrap();
+                created += 1;
+                true
+            }
+            Err(_) => false,
+        };
+    }
+    let stats = pool.manager().stats();
+    assert!(created > 0);
+    assert!(stats.create_failures > 0);
+    assert_eq!(stats.create_failures + created, 20);
+    assert_eq!(stats.delays, 20);
+    // Every connection is dropped instead of being recycled.
+    assert_eq!(stats.drops, created - usize::from(idle));
+}
+
+#[cfg(feature = "srv")]
+#[tokio::test]
+async fn srv_resolution_failure() {
+    use deadpool_surrealdb::ErrorKind;
+
+    let mut cfg = default_config();
+    cfg.host = "srv+ws://_surreal._tcp.deadpool-surrealdb.invalid".to_string();
+    let pool = cfg.create_pool(Some(Runtime::Tokio1)).unwrap();
+    let err = deadpool_surrealdb::Error::from(pool.get().await.unwrap_err());
+    // Without network access the lookup may time out instead.
+    match err.kind() {
+        ErrorKind::Connection => assert!(err.to_string().contains("SRV record")),
+        kind => assert_eq!(kind, ErrorKind::Timeout),
+    }
+}
+
+#[cfg(feature = "testcontainers")]
+#[tokio::test]
+async fn testcontainers() {
+    use deadpool_surrealdb::testcontainers::SurrealDbContainer;
+
+    let surrealdb = SurrealDbContainer::start().await.unwrap();
+    let pool = surrealdb.create_pool().unwrap();
+    let conn = pool.get().await.unwrap();
+    let value: Option<i32> = conn.query("RETURN 1 + 1").await.unwrap().take(0).unwrap();
+    assert_eq!(value, Some(2));
+}
+
+#[cfg(feature = "axum")]
+#[tokio::test]
+async fn axum_extractor() {
+    use axum_core::{extract::FromRequestParts, response::IntoResponse};
+    use deadpool_surrealdb::axum::{DbConn, DbError};
+
+    let mut cfg = default_config();
+    cfg.max_connections = 1;
+    cfg.connect_timeout = 1;
+    let pool = cfg.create_pool(Some(Runtime::Tokio1)).unwrap();
+    let (mut parts, _) = http::Request::new(()).into_parts();
+
+    let DbConn(conn) = DbConn::from_request_parts(&mut parts, &pool).await.unwrap();
+    conn.ping().await.unwrap();
+
+    let error: DbError = DbConn::from_request_parts(&mut parts, &pool).await.unwrap_err();
+    assert_eq!(error.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(error.into_response().status(), http::StatusCode::SERVICE_UNAVAILABLE);
+
+    let error = deadpool_surrealdb::Error::InvalidCursor("0".to_string());
+    assert_eq!(error.into_response().status(), http::StatusCode::BAD_REQUEST);
+}
+
+#[cfg(feature = "actix")]
+#[tokio::test]
+async fn actix_extractor() {
+    use actix_web::{test::TestRequest, FromRequest};
+    use deadpool_surrealdb::actix::{DbConn, DbConnConfig};
+
+    let pool = create_pool();
+    let config = DbConnConfig::default().timeout(Duration::from_millis(100));
+    let req = TestRequest::default()
+        .app_data(actix_web::web::Data::new(pool))
+        .app_data(config)
+        .to_http_request();
+    let DbConn(conn) = DbConn::extract(&req).await.unwrap();
+    conn.ping().await.unwrap();
+
+    let req = TestRequest::default().to_http_request();
+    let error = DbConn::extract(&req).await.unwrap_err();
+    assert_eq!(
+        error.as_response_error().status_code(),
+        actix_web::http::StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
+
+#[cfg(feature = "rocket")]
+#[tokio::test]
+async fn rocket_fairing() {
+    use deadpool_surrealdb::rocket::PoolFairing;
+    use rocket::figment::Figment;
+
+    let figment = Figment::from(rocket::Config::default())
+        .merge(("databases.surrealdb", default_config()));
+    let rocket = rocket::custom(figment)
+        .attach(PoolFairing::default())
+        .ignite()
+        .await
+        .unwrap();
+    let pool = rocket.state::<Pool>().unwrap();
+    pool.get().await.unwrap().ping().await.unwrap();
+
+    let rocket = rocket::custom(rocket::Config::default()).attach(PoolFairing::new("missing"));
+    assert!(rocket.ignite().await.is_err());
+}
+
+#[cfg(feature = "tower")]
+#[tokio::test]
+async fn tower_layer() {
+    use deadpool_surrealdb::tower::{PoolLayer, SharedConn};
+    use futures::future::{ready, Ready};
+    use std::{
+        convert::Infallible,
+        task::{Context, Poll},
+    };
+    use tower_layer::Layer;
+    use tower_service::Service;
+
+    #[derive(Clone)]
+    struct Handler;
+
+    impl Service<http::Request<()>> for Handler {
+        type Response = http::Response<String>;
+        type Error = Infallible;
+        type Future = Ready<Result<Self::Response, Infallible>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: http::Request<()>) -> Self::Future {
+            let body = match (
+                req.extensions().get::<Pool>(),
+                req.extensions().get::<SharedConn>(),
+            ) {
+                (Some(_), _) => "pool",
+                (_, Some(_)) => "conn",
+                _ => "none",
+            };
+            ready(Ok(http::Response::new(body.to_string())))
+        }
+    }
+
+    let mut cfg = default_config();
+    cfg.max_connections = 1;
+    cfg.connect_timeout = 1;
+    let pool = cfg.create_pool(Some(Runtime::Tokio1)).unwrap();
+
+    let mut service = PoolLayer::new(&pool).layer(Handler);
+    let res = service.call(http::Request::new(())).await.unwrap();
+    assert_eq!(res.body(), "pool");
+
+    let layer = PoolLayer::new(&pool).checkout(true);
+    let mut service = layer.layer(Handler);
+    let res = service.call(http::Request::new(())).await.unwrap();
+    assert_eq!(res.body(), "conn");
+    assert_eq!(layer.stats().checkouts, 1);
+
+    let _conn = pool.get().await.unwrap();
+    let res = service.call(http::Request::new(())).await.unwrap();
+    assert_eq!(res.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(layer.stats().checkouts, 2);
+}
+
+#[tokio::test]
+async fn request_conn() {
+    use deadpool_surrealdb::request::RequestConn;
+
+    let pool = create_pool();
+    let conn = RequestConn::new(&pool);
+    assert!(!conn.is_checked_out());
+    assert_eq!(pool.status().size, 0);
+
+    let shared = conn.clone();
+    let id = conn.get().await.unwrap().id();
+    assert_eq!(shared.get().await.unwrap().id(), id);
+    assert!(shared.is_checked_out());
+    let status = pool.status();
+    assert_eq!((status.size, status.available), (1, 0));
+
+    drop(conn);
+    drop(shared);
+    assert_eq!(pool.status().available, 1);
+}
+
+#[tokio::test]
+async fn query_comments() {
+    use deadpool_surrealdb::{
+        trace::{QueryComments, TraceContext},
+        Manager,
+    };
+
+    let context = TraceContext::from_traceparent(
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+    )
+    .unwrap();
+    assert_eq!(context.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_eq!(context.span_id, "00f067aa0ba902b7");
+    assert!(TraceContext::from_traceparent("garbage").is_none());
+
+    let comments = QueryComments::new()
+        .service("billing */ REMOVE TABLE person; /*")
+        .tag("region", "eu-west-1")
+        .context(move || Some(context.clone()));
+    let manager = Manager::from_config(&default_config())
+        .runtime(Runtime::Tokio1)
+        .query_comments(comments);
+    let pool = Pool::builder(manager).build().unwrap();
+    let conn = pool.get().await.unwrap();
+    let value: Option<i64> = conn
+        .query("RETURN 1")
+        .trace(TraceContext::new("trace", "span"))
+        .tag("handler", "*/ REMOVE TABLE person; /*")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(value, Some(1));
+}
+
+#[tokio::test]
+async fn repo() {
+    use deadpool_surrealdb::repo::Repo;
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Person {
+        name: String,
+    }
+
+    let pool = create_pool();
+    let people = Repo::<Person>::new(&pool, "person");
+    let a = Person { name: "a".to_string() };
+    assert_eq!(people.create_with_id("a", a).await.unwrap().name, "a");
+    assert!(people.create_with_id("a", Person { name: "x".to_string() }).await.is_err());
+    let _ = people.create(Person { name: "b".to_string() }).await.unwrap();
+    assert_eq!(people.list().await.unwrap().len(), 2);
+
+    let updated = people.update("a", Person { name: "c".to_string() }).await.unwrap();
+    assert_eq!(updated.map(|p| p.name).as_deref(), Some("c"));
+    assert!(people.update("missing", Person { name: "d".to_string() }).await.unwrap().is_none());
+    assert_eq!(people.get("a").await.unwrap().map(|p| p.name).as_deref(), Some("c"));
+    assert!(people.delete("a").await.unwrap().is_some());
+    assert!(people.get("a").await.unwrap().is_none());
+}
+
+#[cfg(feature = "macros")]
+#[tokio::test]
+async fn surreal_record() {
+    use deadpool_surrealdb::repo::SurrealRecord;
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq, SurrealRecord)]
+    #[table = "user"]
+    struct User {
+        name: String,
+    }
+
+    #[derive(Deserialize, Serialize, SurrealRecord)]
+    struct BlogPost {}
+
+    assert_eq!(User::TABLE, "user");
+    assert_eq!(BlogPost::TABLE, "blog_post");
+
+    let pool = create_pool();
+    let user = User { name: "a".to_string() };
+    assert_eq!(User::create(&pool, &user).await.unwrap(), user);
+    let _ = User::repo(&pool)
+        .create_with_id("b", User { name: "b".to_string() })
+        .await
+        .unwrap();
+    assert_eq!(User::find(&pool, "b").await.unwrap().map(|u| u.name).as_deref(), Some("b"));
+    assert!(User::find(&pool, "missing").await.unwrap().is_none());
+    assert_eq!(User::repo(&pool).list().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn export() {
+    let pool = create_pool();
+    let mut out = Vec::new();
+    let mut chunks = 0;
+    let progress = pool
+        .export_with_progress(&mut out, |p| chunks = p.chunks)
+        .await
+        .unwrap();
+    assert!(progress.bytes > 0);
+    assert_eq!(progress.bytes, out.len() as u64);
+    assert_eq!(progress.chunks, chunks);
+    // The export doesn't occupy a slot of the pool nor show up in its
+    // events.
+    assert_eq!(pool.status().size, 0);
+    assert!(pool.recent_events().is_empty());
+}
+
+#[tokio::test]
+async fn import() {
+    use deadpool_surrealdb::import::ImportOptions;
+
+    let pool = create_pool();
+    let script = "
+        -- people; with a comment
+        CREATE person:1 SET name = 'a;b';
+        CREATE person:2 SET name = \"c\";
+        BEGIN TRANSACTION;
+        CREATE person:3;
+        CREATE person:4;
+        COMMIT TRANSACTION;
+        IF true { CREATE person:5; };
+        SELECT * FROM person
+    ";
+    let options = ImportOptions {
+        statements_per_chunk: 2,
+        transactional: true,
+    };
+    let summary = pool.import(script.as_bytes(), options).await.unwrap();
+    assert_eq!(summary.statements, 8);
+    assert_eq!(summary.chunks, 3);
+    assert_eq!(pool.status().size, 0);
+    assert!(pool.recent_events().is_empty());
+}
+
+#[tokio::test]
+async fn backup_retention() {
+    use deadpool_surrealdb::backup::{BackupScheduler, FsSink};
+
+    let dir = env::temp_dir().join(format!("deadpool-surrealdb-backup-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for secs in 0..3 {
+        std::fs::write(dir.join(format!("backup-{:020}.surql", secs)), "").unwrap();
+    }
+
+    let pool = create_pool();
+    let sink = FsSink::new(&dir, Runtime::Tokio1);
+    let scheduler = BackupScheduler::new(&pool, sink, Duration::from_secs(3600)).keep_last(2);
+    let name = scheduler.backup_now().await.unwrap();
+    let list = || {
+        let mut names: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        names
+    };
+    assert_eq!(list(), vec![format!("backup-{:020}.surql", 2), name.clone()]);
+
+    // Backups within the same second get distinct names.
+    let next = scheduler.backup_now().await.unwrap();
+    assert_ne!(next, name);
+    assert_eq!(list(), vec![name, next]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn migrations() {
+    use deadpool_surrealdb::migrations::{Migration, Migrator};
+
+    let pool = create_pool();
+    let migrator = Migrator::new([
+        Migration::new(2, "index", "DEFINE INDEX name ON person FIELDS name;"),
+        Migration::new(1, "person", "DEFINE TABLE person; DEFINE FIELD name ON person;")
+            .with_down("REMOVE TABLE person"),
+    ]);
+
+    let report = migrator.clone().dry_run(true).up(&pool).await.unwrap();
+    assert!(report.dry_run);
+    assert_eq!(report.applied, vec![1, 2]);
+
+    let report = pool.migrate(&migrator).await.unwrap();
+    assert!(!report.dry_run);
+    assert_eq!(report.applied, vec![1, 2]);
+    assert_eq!(pool.status().size, 0);
+    assert!(pool.recent_events().is_empty());
+}
+
+#[tokio::test]
+async fn schema_sync() {
+    use deadpool_surrealdb::schema::SchemaManager;
+
+    let pool = create_pool();
+    let schema = SchemaManager::from_script(
+        "DEFINE TABLE account SCHEMAFULL;
+         DEFINE FIELD email ON TABLE account TYPE string;
+         DEFINE INDEX email ON account FIELDS email UNIQUE;",
+    )
+    .unwrap();
+
+    let plan = schema.sync(&pool).await.unwrap();
+    assert_eq!(plan.missing.len(), 3);
+    assert!(schema.plan(&pool).await.unwrap().is_empty());
+
+    let schema = schema.define("DEFINE FIELD name ON account TYPE string").unwrap();
+    let plan = schema.sync(&pool).await.unwrap();
+    assert_eq!(plan.missing, vec!["DEFINE FIELD name ON account TYPE string"]);
+    assert_eq!(plan.unchanged.len(), 3);
+
+    // Definitions changed without the schema manager are detected. The
+    // connection is returned before syncing, so the pool keeps using the
+    // same one.
+    let define = |sql: &'static str| {
+        let pool = pool.clone();
+        async move {
+            let conn = pool.get().await.unwrap();
+            let _ = conn.query(sql).await.unwrap().check().unwrap();
+        }
+    };
+    define("DEFINE FIELD OVERWRITE email ON account TYPE int").await;
+    let plan = schema.plan(&pool).await.unwrap();
+    assert_eq!(plan.changed, vec!["DEFINE FIELD email ON TABLE account TYPE string"]);
+    let _ = schema.sync(&pool).await.unwrap();
+    assert!(schema.plan(&pool).await.unwrap().is_empty());
+
+    // `IF NOT EXISTS` is replaced by `OVERWRITE` for changed definitions.
+    let schema =
+        SchemaManager::from_script("DEFINE FIELD IF NOT EXISTS age ON account TYPE int").unwrap();
+    let _ = schema.sync(&pool).await.unwrap();
+    define("DEFINE FIELD OVERWRITE age ON account TYPE string").await;
+    assert_eq!(schema.sync(&pool).await.unwrap().changed.len(), 1);
+    let conn = pool.get().await.unwrap();
+    let info: Option<serde_json::Value> =
+        conn.query("INFO FOR TABLE account").await.unwrap().take(0).unwrap();
+    let age = info.unwrap()["fields"]["age"].as_str().unwrap().to_string();
+    assert!(age.contains("TYPE int"), "{}", age);
+
+    assert!(SchemaManager::new().define("SELECT * FROM account").is_err());
+}
+
+#[cfg(feature = "surrealdb-migrations")]
+#[tokio::test]
+async fn surrealdb_migrations() {
+    use deadpool_surrealdb::surrealdb_migrations::SurrealMigrations;
+
+    let dir = env::temp_dir().join(format!("deadpool-surrealdb-migrations-{}", std::process::id()));
+    for folder in ["schemas", "events", "migrations"] {
+        std::fs::create_dir_all(dir.join(folder)).unwrap();
+    }
+    std::fs::write(dir.join("schemas/post.surql"), "DEFINE TABLE post SCHEMALESS;").unwrap();
+    let config_file = dir.join(".surrealdb");
+    std::fs::write(&config_file, format!("[core]\npath = {:?}\n", dir)).unwrap();
+
+    let pool = create_pool();
+    let migrations = SurrealMigrations::connect(&pool).await.unwrap();
+    assert_eq!(pool.status().size, 0);
+    migrations
+        .runner()
+        .use_config_file(config_file.to_str().unwrap())
+        .up()
+        .await
+        .unwrap();
+    let info: Option<serde_json::Value> = migrations
+        .conn()
+        .query("INFO FOR DB")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert!(info.unwrap()["tables"].get("post").is_some());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn ensure_ns_db() {
+    let mut cfg = default_config();
+    cfg.ns = "provisioned".to_string();
+    cfg.create_if_missing = true;
+    let pool = cfg.create_pool(Some(Runtime::Tokio1)).unwrap();
+    pool.ensure_ns_db().await.unwrap();
+    let conn = pool.get().await.unwrap();
+    let _ = conn.query("INFO FOR DB").await.unwrap().check().unwrap();
+}
+
+#[tokio::test]
+async fn seed() {
+    use deadpool_surrealdb::seed::Seeder;
+
+    #[derive(Serialize)]
+    struct Person {
+        name: String,
+    }
+
+    let pool = create_pool();
+    let seeder = Seeder::new()
+        .truncate(true)
+        .records("person", vec![Person { name: "a".to_string() }])
+        .json("person", r#"[{ "name": "b" }, { "name": "c" }]"#)
+        .script("UPSERT post:1 SET author = 'a'");
+    pool.seed(&seeder).await.unwrap();
+    pool.seed(&seeder).await.unwrap();
+
+    let conn = pool.get().await.unwrap();
+    let mut names: Vec<String> = conn
+        .query("SELECT VALUE name FROM person")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    names.sort();
+    assert_eq!(names, vec!["a", "b", "c"]);
+    assert!(Seeder::new().file("fixtures.txt").is_err());
+}
+
+#[tokio::test]
+async fn run_script() {
+    use deadpool_surrealdb::script::Script;
+
+    let pool = create_pool();
+    let script = Script::new("UPSERT script:1; THROW 'boom'; UPSERT script:2");
+    assert_eq!(script.statements().len(), 3);
+
+    let report = pool.run_script(&script).await.unwrap();
+    assert_eq!(report.statements.len(), 2);
+    assert!(report.statements[0].error.is_none());
+    assert!(report.statements[1].error.is_some());
+
+    let report = pool
+        .run_script(&script.clone().stop_on_error(false))
+        .await
+        .unwrap();
+    assert_eq!(report.statements.len(), 3);
+    assert_eq!(report.failed().count(), 1);
+    assert!(report.statements[2].elapsed.is_some());
+
+    let report = pool
+        .run_script(
+            &Script::new("CREATE script:3; THROW 'boom'")
+                .transactional(true)
+                .stop_on_error(false),
+        )
+        .await
+        .unwrap();
+    assert!(!report.is_success());
+    let conn = pool.get().await.unwrap();
+    let created: Option<serde_json::Value> = conn
+        .query("SELECT * FROM script:3")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert!(created.is_none());
+}
+
+#[cfg(feature = "macros")]
+#[tokio::test]
+async fn surql_macro() {
+    use deadpool_surrealdb::surql;
+
+    let pool = create_pool();
+    let query = surql!("RETURN $value");
+    assert_eq!(query.sql(), "RETURN $value");
+    let value: Option<i64> = query
+        .on_pool(&pool)
+        .bind(("value", 42))
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(value, Some(42));
+}
+
+#[tokio::test]
+async fn server_stats() {
+    use deadpool_surrealdb::stats::ServerStats;
+
+    let pool = create_pool();
+    let conn = pool.get().await.unwrap();
+    let _ = conn
+        .query(
+            "DEFINE TABLE stats_a; DEFINE FIELD name ON stats_a; \
+             DEFINE INDEX name ON stats_a FIELDS name; DEFINE TABLE stats_b",
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+    drop(conn);
+
+    let stats = ServerStats::new(&pool);
+    assert!(stats.latest().is_none());
+    let snapshot = stats.snapshot().await.unwrap();
+    let a = snapshot.tables.iter().find(|t| t.name == "stats_a").unwrap();
+    assert_eq!((a.fields, a.indexes, a.events), (1, 1, 0));
+    assert!(snapshot.tables.iter().any(|t| t.name == "stats_b"));
+    assert_eq!(snapshot.live_queries(), 0);
+    assert_eq!(stats.clone().latest().unwrap().timestamp_ms, snapshot.timestamp_ms);
+}
+
+#[tokio::test]
+async fn shutdown() {
+    use deadpool_surrealdb::stats::ServerStats;
+
+    let pool = create_pool();
+    let stats = ServerStats::new(&pool).interval(Duration::from_secs(3600));
+    let run = tokio::spawn(stats.clone().run());
+    while stats.latest().is_none() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let query = tokio::spawn({
+        let pool = pool.clone();
+        async move { pool.query("SLEEP 200ms").await.map(drop) }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    pool.shutdown();
+    assert!(pool.is_closed());
+    drop(pool);
+    let stopped = tokio::time::timeout(Duration::from_secs(1), run).await;
+    assert!(stopped.unwrap().unwrap().is_ok());
+    // The query already holding a connection finishes without panicking.
+    let _ = query.await.unwrap();
+}
+
+#[tokio::test]
+async fn pool_dropped() {
+    use deadpool_surrealdb::{autoscale::Autoscaler, stats::ServerStats};
+
+    let pool = create_pool();
+    let stats = ServerStats::new(&pool).interval(Duration::from_secs(3600));
+    let collector = tokio::spawn(stats.clone().run());
+    let autoscaler = tokio::spawn(
+        Autoscaler::new(&pool)
+            .interval(Duration::from_millis(10))
+            .run(),
+    );
+    while stats.latest().is_none() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let conn = pool.get().await.unwrap();
+    let query = tokio::spawn(async move { conn.query("SLEEP 100ms").await.map(drop) });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    // Dropping the last handle without closing the pool stops its tasks
+    // instead of leaking them along with the pool.
+    drop(pool);
+    let stopped = tokio::time::timeout(Duration::from_secs(1), collector).await;
+    assert!(stopped.unwrap().unwrap().is_ok());
+    let stopped = tokio::time::timeout(Duration::from_secs(1), autoscaler).await;
+    assert!(stopped.unwrap().unwrap().is_ok());
+    assert!(matches!(
+        stats.refresh().await,
+        Err(deadpool_surrealdb::Error::PoolClosed)
+    ));
+    // The connection checked out before finishes its query.
+    query.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn connection_metrics() {
+    use deadpool_surrealdb::Manager;
+
+    let pool = create_pool();
+    let conn = pool.get().await.unwrap();
+    assert_eq!(conn.metrics().queries, 0);
+    let _ = conn.query("RETURN $a; RETURN 2").bind(("a", 1)).await.unwrap();
+    let metrics = conn.metrics();
+    assert_eq!((metrics.queries, metrics.statements), (1, 2));
+    // Bytes are only counted on request.
+    assert_eq!(metrics.transferred_bytes(), 0);
+    drop(conn);
+
+    let cfg = Config {
+        count_bytes: true,
+        ..default_config()
+    };
+    let pool = Pool::builder(Manager::from_config(&cfg)).max_size(1).build().unwrap();
+    let conn = pool.get().await.unwrap();
+    let _ = conn.query("RETURN $a; RETURN 2").bind(("a", 1)).await.unwrap();
+    let metrics = conn.metrics();
+    assert!(metrics.request_bytes >= "RETURN $a; RETURN 2".len() as u64);
+    assert!(metrics.response_bytes > 0);
+    drop(conn);
+
+    let cfg = Config {
+        max_transferred_bytes: Some(1),
+        ..default_config()
+    };
+    let pool = Pool::builder(Manager::from_config(&cfg)).max_size(1).build().unwrap();
+    let conn = pool.get().await.unwrap();
+    let id = conn.id();
+    let _ = conn.query("RETURN 1").await.unwrap();
+    drop(conn);
+    assert_ne!(pool.get().await.unwrap().id(), id);
+}
+
+#[tokio::test]
+async fn pool_server_version() {
+    let pool = create_pool();
+    let version = pool.server_version().await.unwrap();
+    assert!(!version.is_empty());
+    assert_eq!(pool.server_version().await.unwrap(), version);
+    let conn = pool.get().await.unwrap();
+    assert_eq!(conn.server_version().await.unwrap(), version);
+}
+
+#[tokio::test]
+async fn surreal_reexports() {
+    use deadpool_surrealdb::surreal::{Any, RecordId, Surreal};
+
+    let pool = create_pool();
+    let conn = pool.get().await.unwrap();
+    let client: &Surreal<Any> = conn.client();
+    let id: Option<RecordId> = client.query("RETURN person:1").await.unwrap().take(0).unwrap();
+    assert_eq!(id, Some(RecordId::from(("person", 1_i64))));
+}
+
+#[tokio::test]
+async fn surreal_pool_facade() {
+    use std::sync::Arc;
+
+    use deadpool_surrealdb::{tenant::TenantScope, SurrealPool};
+
+    let pool: Arc<dyn SurrealPool> = Arc::new(create_pool());
+    pool.ping().await.unwrap();
+    let conn = pool.get().await.unwrap();
+    assert_eq!(pool.status().available, 0);
+    drop(conn);
+    assert_eq!(pool.status().available, 1);
+
+    let scope = TenantScope::new(&create_pool()).max_per_tenant(1);
+    let tenant: Arc<dyn SurrealPool> = Arc::new(scope.tenant("acme"));
+    let conn = tenant.get().await.unwrap();
+    let id: Option<String> = conn.query("RETURN $tenant_id").await.unwrap().take(0).unwrap();
+    assert_eq!(id.as_deref(), Some("acme"));
+    assert_eq!(scope.metrics()["acme"].in_use, 1);
+    drop(conn);
+    assert_eq!(scope.metrics()["acme"].in_use, 0);
+}
+
+#[tokio::test]
+async fn close_idle() {
+    let pool = create_pool();
+    let (a, b) = (pool.get().await.unwrap(), pool.get().await.unwrap());
+    drop(a);
+    assert_eq!(pool.close_idle(), 1);
+    let status = pool.status();
+    assert_eq!((status.size, status.available), (1, 0));
+    drop(b);
+    assert_eq!(pool.status().available, 1);
+    assert_eq!(pool.close_idle(), 1);
+    assert_eq!(pool.status().size, 0);
+}
+
+#[tokio::test]
+async fn read_only() {
+    let mut cfg = default_config();
+    cfg.read_only = true;
+    let pool = cfg.create_pool(Some(Runtime::Tokio1)).unwrap();
+    let conn = pool.get().await.unwrap();
+    assert!(conn.is_read_only());
+    let _ = conn.query("SELECT * FROM person").await.unwrap();
+    match conn.query("SELECT * FROM person; delete person").await {
+        Err(deadpool_surrealdb::Error::ReadOnly(keyword)) => assert_eq!(keyword, "DELETE"),
+        r => panic!("Unexpected result: {:?}", r.map(|_| ())),
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Record {
+        id: surrealdb::RecordId,
+    }
+
+    // The record methods shadowing those of the client are rejected as well.
+    let rejected = [
+        conn.create::<Option<Record>>("person").err(),
+        conn.insert::<Vec<Record>>("person").err(),
+        conn.upsert::<Vec<Record>>("person").err(),
+        conn.update::<Vec<Record>>("person").err(),
+        conn.delete::<Vec<Record>>("person").err(),
+    ];
+    let keywords: Vec<_> = rejected
+        .into_iter()
+        .map(|e| match e {
+            Some(deadpool_surrealdb::Error::ReadOnly(keyword)) => keyword,
+            e => panic!("Unexpected error: {:?}", e),
+        })
+        .collect();
+    assert_eq!(keywords, ["CREATE", "INSERT", "UPSERT", "UPDATE", "DELETE"]);
+    let _: Vec<Record> = conn.select("person").await.unwrap();
+
+    let pool = create_pool();
+    let conn = pool.get().await.unwrap();
+    let created: Option<Record> = conn.create("person").unwrap().await.unwrap();
+    assert_eq!(created.unwrap().id.table(), "person");
+}
+
+#[tokio::test]
+async fn tenant_scope() {
+    let pool = create_pool();
+    let conn = pool.get_for_tenant("acme").await.unwrap();
+    let tenant: Option<String> = conn.query("RETURN $tenant_id").await.unwrap().take(0).unwrap();
+    assert_eq!(tenant.as_deref(), Some("acme"));
+    drop(conn);
+
+    let conn = pool.get().await.unwrap();
+    let tenant: Option<String> = conn.query("RETURN $tenant_id").await.unwrap().take(0).unwrap();
+    assert_eq!(tenant, None);
+}
+
+#[tokio::test]
+async fn scoped_param() {
+    let pool = create_pool();
+    let conn = pool.get().await.unwrap();
+    conn.set("sticky", 1).await.unwrap();
+    {
+        let _sticky = conn.scoped_set("sticky", 2).await.unwrap();
+        let _scoped = conn.scoped_set("scoped", "yes").await.unwrap();
+        let sticky: Option<i64> = conn.query("RETURN $sticky").await.unwrap().take(0).unwrap();
+        assert_eq!(sticky, Some(2));
+    }
+    let sticky: Option<i64> = conn.query("RETURN $sticky").await.unwrap().take(0).unwrap();
+    assert_eq!(sticky, Some(1));
+    let scoped: Option<String> = conn.query("RETURN $scoped").await.unwrap().take(0).unwrap();
+    assert_eq!(scoped, None);
+
+    let guard = conn.scoped_set("sticky", 3).await.unwrap();
+    guard.restore().await.unwrap();
+    let sticky: Option<i64> = conn.query("RETURN $sticky").await.unwrap().take(0).unwrap();
+    assert_eq!(sticky, Some(1));
+
+    // Guards dropped right before the connection is returned don't leak.
+    let guard = conn.scoped_set("scoped", "yes").await.unwrap();
+    drop(guard);
+    drop(conn);
+    let conn = pool.get().await.unwrap();
+    let scoped: Option<String> = conn.query("RETURN $scoped").await.unwrap().take(0).unwrap();
+    assert_eq!(scoped, None);
+
+    let guard = conn.scoped_set("Mode_2", 4).await.unwrap();
+    let scoped: Option<i64> = conn.query("RETURN $Mode_2").await.unwrap().take(0).unwrap();
+    assert_eq!(scoped, Some(4));
+    guard.restore().await.unwrap();
+    let scoped: Option<i64> = conn.query("RETURN $Mode_2").await.unwrap().take(0).unwrap();
+    assert_eq!(scoped, None);
+
+    // Values are restored with their original type.
+    conn.set("owner", surrealdb::RecordId::from(("user", "a")))
+        .await
+        .unwrap();
+    let guard = conn.scoped_set("owner", "nobody").await.unwrap();
+    guard.restore().await.unwrap();
+    let owner: Option<bool> = conn
+        .query("RETURN type::is::record($owner) AND $owner = user:a")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(owner, Some(true));
+
+    for key in ["", "a-b", "a`b", "a; REMOVE TABLE x"] {
+        assert!(matches!(
+            conn.scoped_set(key, 1).await,
+            Err(deadpool_surrealdb::Error::InvalidQuery(_))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn tenant_limits() {
+    use deadpool_surrealdb::tenant::TenantScope;
+
+    let pool = create_pool();
+    let scope = TenantScope::new(&pool).max_per_tenant(1);
+    let conn = scope.get("noisy").await.unwrap();
+    let blocked = tokio::time::timeout(Duration::from_millis(100), scope.get("noisy")).await;
+    assert!(blocked.is_err());
+    let _other = scope.get("quiet").await.unwrap();
+
+    let metrics = scope.metrics();
+    assert_eq!(metrics["noisy"].in_use, 1);
+    assert_eq!(metrics["noisy"].checkouts, 1);
+    assert_eq!(metrics["quiet"].in_use, 1);
+
+    drop(conn);
+    let _conn = scope.get("noisy").await.unwrap();
+    assert_eq!(scope.metrics()["noisy"].checkouts, 2);
+}
+
+#[tokio::test]
+async fn tenant_limits_wait_timeout() {
+    use deadpool_surrealdb::{tenant::TenantScope, Manager, TimeoutPhase};
+
+    let manager = Manager::from_config(&default_config()).runtime(Runtime::Tokio1);
+    let pool = Pool::builder(manager)
+        .max_size(2)
+        .wait_timeout(Some(Duration::from_millis(50)))
+        .runtime(Runtime::Tokio1)
+        .build()
+        .unwrap();
+    let scope = TenantScope::new(&pool).max_per_tenant(1);
+    let conn = scope.get("noisy").await.unwrap();
+    let err = scope.get("noisy").await.unwrap_err();
+    assert!(matches!(
+        err,
+        deadpool_surrealdb::Error::Timeout {
+            phase: TimeoutPhase::Wait
+        }
+    ));
+    drop(conn);
+
+    // A limit of 0 is treated as 1 instead of blocking every checkout.
+    let scope = TenantScope::new(&pool).max_per_tenant(0);
+    let conn = scope.get("acme").await.unwrap();
+    assert_eq!(scope.metrics()["acme"].in_use, 1);
+    assert!(scope.get("acme").await.is_err());
+    drop(conn);
+    assert!(scope.get("acme").await.is_ok());
+}
+
+#[cfg(feature = "test-utils")]
+#[tokio::test]
+async fn tenant_eviction() {
+    use deadpool_surrealdb::{tenant::TenantScope, test_utils::ManualClock, Manager};
+
+    let clock = ManualClock::new();
+    let manager = Manager::from_config(&default_config()).clock(clock.clone());
+    let pool = Pool::builder(manager).max_size(2).build().unwrap();
+    let scope = TenantScope::new(&pool)
+        .max_per_tenant(1)
+        .evict_idle_after(Duration::from_secs(60));
+    drop(scope.get("idle").await.unwrap());
+    let busy = scope.get("busy").await.unwrap();
+
+    clock.advance(Duration::from_secs(61));
+    drop(scope.get("active").await.unwrap());
+    let metrics = scope.metrics();
+    assert!(!metrics.contains_key("idle"));
+    assert_eq!(metrics["busy"].in_use, 1);
+    assert_eq!(metrics["active"].checkouts, 1);
+
+    // Tenants holding a connection keep their limit.
+    let blocked = tokio::time::timeout(Duration::from_millis(100), scope.get("busy")).await;
+    assert!(blocked.is_err());
+    drop(busy);
+}
+
+#[tokio::test]
+async fn reserved_for_writes() {
+    use deadpool_surrealdb::{priority::OperationClass, Manager};
+
+    let config = Config {
+        reserved_for_writes: 1,
+        ..default_config()
+    };
+    let pool = Pool::builder(Manager::from_config(&config)).max_size(2).build().unwrap();
+
+    let read = pool.get_for(OperationClass::Read).await.unwrap();
+    assert_eq!(read.class(), OperationClass::Read);
+    let blocked =
+        tokio::time::timeout(Duration::from_millis(100), pool.get_for(OperationClass::Read)).await;
+    assert!(blocked.is_err());
+
+    let write = pool.get_for(OperationClass::Write).await.unwrap();
+    let _ = write.query("RETURN 1").await.unwrap();
+    drop(write);
+
+    let waiting = tokio::spawn({
+        let pool = pool.clone();
+        async move { pool.get_for(OperationClass::Read).await.map(|_| ()) }
+    });
+    drop(read);
+    waiting.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn reserved_for_writes_wait_timeout() {
+    use std::sync::{Arc, Mutex};
+
+    use deadpool_surrealdb::{
+        checkout::CheckoutFailure, priority::OperationClass, ErrorKind, Manager, TimeoutPhase,
+    };
+
+    let failures = Arc::new(Mutex::new(Vec::<CheckoutFailure>::new()));
+    let recorded = failures.clone();
+    let config = Config {
+        reserved_for_writes: 1,
+        ..default_config()
+    };
+    let manager = Manager::from_config(&config)
+        .runtime(Runtime::Tokio1)
+        .on_checkout_failure(move |failure| recorded.lock().unwrap().push(*failure));
+    let pool = Pool::builder(manager)
+        .max_size(2)
+        .wait_timeout(Some(Duration::from_millis(50)))
+        .runtime(Runtime::Tokio1)
+        .build()
+        .unwrap();
+
+    // The only read slot is held, so the next read can't get a connection.
+    let read = pool.get_for(OperationClass::Read).await.unwrap();
+    let err = pool.get_for(OperationClass::Read).await.unwrap_err();
+    assert!(matches!(
+        err,
+        deadpool_surrealdb::Error::Timeout {
+            phase: TimeoutPhase::Wait
+        }
+    ));
+    assert_eq!(failures.lock().unwrap().len(), 1);
+    assert_eq!(failures.lock().unwrap()[0].kind, ErrorKind::Timeout);
+
+    drop(read);
+    assert!(pool.get_for(OperationClass::Read).await.is_ok());
+}
+
+#[cfg(feature = "graphql")]
+#[tokio::test]
+async fn graphql_loader() {
+    use async_graphql::dataloader::Loader;
+    use deadpool_surrealdb::graphql::SurrealLoader;
+
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    struct Person {
+        name: String,
+    }
+
+    let pool = create_pool();
+    let conn = pool.get().await.unwrap();
+    let _ = conn
+        .query("CREATE person:a SET name = 'A'; CREATE person:b SET name = 'B'")
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+    drop(conn);
+
+    let loader = SurrealLoader::<Person>::new(&pool, "person");
+    let keys = vec!["a".to_string(), "b".to_string(), "missing".to_string()];
+    let people = loader.load(&keys).await.unwrap();
+    assert_eq!(people.len(), 2);
+    assert_eq!(people["b"].name, "B");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn config_from_env() {
+    let mut env = Env::new();
+    env.set("SURREALDB__HOST", "mem://");
+    env.set("SURREALDB__NS", "test");
+    env.set("SURREALDB__DB", "test");
+    env.set("SURREALDB__CONNECT_TIMEOUT", "10");
+    env.set("SURREALDB__MAX_CONNECTIONS", "20");
+    env.set("SURREALDB__CREDS__ROOT__USER", "");
+    env.set("SURREALDB__CREDS__ROOT__PASS", "");
+    
+    let cfg = TestConfig::from_env();
+    assert_eq!(cfg.surrealdb.host, "mem://");
+    assert_eq!(cfg.surrealdb.ns, "test");
+    assert_eq!(cfg.surrealdb.db, "test");
+    assert_eq!(cfg.surrealdb.connect_timeout, 10);
+    assert_eq!(cfg.surrealdb.max_connections, 20);
+    match cfg.surrealdb.creds {
+        Credentials::Root { user, pass } => {
+            assert_eq!(user, "");
+            assert_eq!(pass, "");
+        }
+        _ => panic!("Expected root credentials"),
+    }
+}
+
+#[test]
+fn config_from_surreal_env() {
+    let mut env = Env::new();
+    env.set("SURREAL_URL", "ws://localhost:8000");
+    env.set("SURREAL_NS", "app");
+    env.set("SURREAL_DB", "main");
+    env.set("SURREAL_USER", "admin");
+    env.set("SURREAL_PASS", "secret");
+
+    let cfg = Config::from_surreal_env().unwrap();
+    assert_eq!(cfg.host, "ws://localhost:8000");
+    assert_eq!(cfg.ns, "app");
+    assert_eq!(cfg.db, "main");
+    match cfg.creds {
+        Credentials::Root { user, pass } => {
+            assert_eq!(user, "admin");
+            assert_eq!(pass, "secret");
+        }
+        _ => panic!("Expected root credentials"),
+    }
+
+    env.set("SURREAL_URL", "localhost:8000");
+    assert!(matches!(
+        Config::from_surreal_env(),
+        Err(deadpool_surrealdb::Error::InvalidConfig(message)) if message.contains("SURREAL_URL")
+    ));
+    env.remove("SURREAL_URL");
+    assert!(matches!(
+        Config::from_surreal_env(),
+        Err(deadpool_surrealdb::Error::InvalidConfig(message)) if message == "SURREAL_URL is not set"
+    ));
+}
+
+#[test]
+fn config_merge_env() {
+    let mut env = Env::new();
+    env.set("PARTIAL__HOST", "ws://localhost:8000");
+    env.set("PARTIAL__MAX_CONNECTIONS", "20");
+    env.set("PARTIAL__MAX_IDLE_TIME", "30");
+    env.set("PARTIAL__CREDS__DATABASE__USER", "app");
+    env.set("PARTIAL__CREDS__DATABASE__PASS", "secret");
+
+    let cfg = default_config().merge_env("PARTIAL__").unwrap();
+    assert_eq!(cfg.host, "ws://localhost:8000");
+    assert_eq!(cfg.ns, "test");
+    assert_eq!(cfg.max_connections, 20);
+    assert_eq!(cfg.max_idle_time, Some(30));
+    assert_eq!(cfg.connect_timeout, default_config().connect_timeout);
+    match cfg.creds {
+        Credentials::Database { user, pass, ns, db } => {
+            assert_eq!((user.as_str(), pass.as_str()), ("app", "secret"));
+            assert_eq!((ns.as_str(), db.as_str()), ("test", "test"));
+        }
+        _ => panic!("Expected database credentials"),
+    }
+
+    env.set("PARTIAL__MAX_CONNECTIONS", "many");
+    env.set("PARTIAL__READ_ONLY", "yes");
+    let e = default_config().merge_env("PARTIAL__").unwrap_err();
+    let names: Vec<_> = e.invalid.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["PARTIAL__MAX_CONNECTIONS", "PARTIAL__READ_ONLY"]);
+}
+
+#[test]
+fn config_validate() {
+    use deadpool_surrealdb::ErrorKind;
+
+    assert!(default_config().validate().is_ok());
+    let cfg = Config {
+        creds: Credentials::database("app", "secret", "test", "other"),
+        ..default_config()
+    };
+    let e = cfg.create_pool(Some(Runtime::Tokio1)).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::Config);
+    assert_eq!(
+        e.to_string(),
+        "Invalid configuration: database credentials are for `test`/`other`, \
+         but the pool uses `test`/`test`"
+    );
+
+    let cfg = Config {
+        host: "ws://localhost:8000".to_string(),
+        creds: Credentials::root("", ""),
+        ..default_config()
+    };
+    assert!(cfg.validate().unwrap_err().to_string().contains("without a user"));
+    let cfg = Config {
+        reserved_for_writes: 10,
+        ..default_config()
+    };
+    assert!(cfg.validate().is_err());
+    let cfg = Config {
+        host: "localhost:8000".to_string(),
+        ..default_config()
+    };
+    assert!(cfg.validate().is_err());
+
+    // Legacy RocksDB hosts need a path but no credentials.
+    let cfg = Config {
+        host: "file://".to_string(),
+        ..default_config()
+    };
+    assert!(cfg.validate().unwrap_err().to_string().contains("has no path"));
+    let cfg = Config {
+        host: "file:///var/lib/surrealdb".to_string(),
+        creds: Credentials::root("", ""),
+        ..default_config()
+    };
+    assert!(cfg.validate().is_ok());
+}
+
+#[test]
+fn config_fingerprint() {
+    let cfg = default_config();
+    let fingerprint = cfg.fingerprint();
+    assert_eq!(fingerprint.len(), 16);
+    assert!(fingerprint.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(cfg.clone().fingerprint(), fingerprint);
+
+    let other_pass = Config {
+        creds: Credentials::root("root", "changed"),
+        ..default_config()
+    };
+    assert_eq!(other_pass.fingerprint(), fingerprint);
+    let other_size = Config {
+        max_connections: 20,
+        ..default_config()
+    };
+    assert_ne!(other_size.fingerprint(), fingerprint);
+    // Values are delimited, so moving characters between settings changes it.
+    let moved = Config {
+        ns: format!("{}{}", cfg.ns, cfg.db),
+        db: String::new(),
+        ..default_config()
+    };
+    assert_ne!(moved.fingerprint(), fingerprint);
+
+    let manager = deadpool_surrealdb::Manager::from_config(&cfg);
+    let pool = Pool::builder(manager).build().unwrap();
+    assert_eq!(pool.fingerprint(), fingerprint);
+}
+
+#[test]
+fn config_display() {
+    let creds = Credentials::root("admin", "secret");
+    let mut cfg = Config::new("ws://localhost:8000", "app", "main", creds);
+    cfg.name = Some("primary".to_string());
+    let display = cfg.to_string();
+    assert!(display.starts_with("name=primary, host=ws://localhost:8000, ns=app, db=main"));
+    assert!(display.contains("credentials=root"));
+    assert!(!display.contains("admin"));
+    assert!(!display.contains("secret"));
+}
+
+#[tokio::test]
+async fn pressure() {
+    use deadpool_surrealdb::Manager;
+
+    let manager = Manager::from_config(&default_config()).runtime(Runtime::Tokio1);
+    let pool = Pool::builder(manager).max_size(1).build().unwrap();
+
+    let conn = pool.checkout().await.unwrap();
+    let pressure = pool.pressure();
+    assert_eq!(pressure.utilization, 1.0);
+    assert_eq!(pressure.saturation, 0.0);
+    let gauges = pool.gauges();
+    assert_eq!((gauges.idle, gauges.in_use, gauges.waiting), (0, 1, 0));
+    assert_eq!(pool.debug_dump().status.in_use, 1);
+
+    let waiting = tokio::spawn({
+        let pool = pool.clone();
+        async move { pool.checkout().await.map(drop) }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(pool.pressure().saturation, 1.0);
+    assert_eq!(pool.gauges().waiting, 1);
+    drop(conn);
+    waiting.await.unwrap().unwrap();
+    let gauges = pool.gauges();
+    assert_eq!((gauges.idle, gauges.in_use, gauges.waiting), (1, 0, 0));
+
+    let pressure = pool.pressure();
+    assert_eq!(pressure.saturation, 0.0);
+    assert!(pressure.wait_p95 >= Duration::from_millis(40));
+}
+
+#[tokio::test]
+async fn autoscaler() {
+    use deadpool_surrealdb::{autoscale::Autoscaler, Manager};
+
+    let manager = Manager::from_config(&default_config()).runtime(Runtime::Tokio1);
+    let pool = Pool::builder(manager).max_size(1).build().unwrap();
+    let mut autoscaler = Autoscaler::new(&pool)
+        .max_connections(2)
+        .grow_after(1)
+        .shrink_after(2)
+        .cooldown(Duration::ZERO);
+
+    let conn = pool.get().await.unwrap();
+    assert_eq!(autoscaler.tick(), None);
+    let waiting = tokio::spawn({
+        let pool = pool.clone();
+        async move { pool.get().await.map(drop) }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let resize = autoscaler.tick().unwrap();
+    assert_eq!((resize.from, resize.to), (1, 2));
+    waiting.await.unwrap().unwrap();
+    drop(conn);
+
+    assert_eq!(autoscaler.tick(), None);
+    let resize = autoscaler.tick().unwrap();
+    assert_eq!((resize.from, resize.to), (2, 1));
+    assert_eq!(pool.status().max_size, 1);
+}
+
+#[tokio::test]
+async fn query_cache() {
+    use deadpool_surrealdb::{cache::QueryCache, Manager};
+
+    let manager = Manager::from_config(&default_config()).runtime(Runtime::Tokio1);
+    let pool = Pool::builder(manager)
+        .max_size(1)
+        .runtime(Runtime::Tokio1)
+        .build()
+        .unwrap();
+
+    let cache = QueryCache::new(&pool);
+    let first = cache.query::<Option<i64>>("RETURN 1").await.unwrap();
+    assert_eq!(first.value, Some(1));
+    let conn = pool.get().await.unwrap();
+    // Fresh results don't need a connection.
+    let second = cache.query::<Option<i64>>("RETURN 1").await.unwrap();
+    assert_eq!(second, first);
+    assert_eq!(cache.len(), 1);
+    drop(conn);
+
+    let cache = QueryCache::new(&pool)
+        .ttl(Duration::ZERO)
+        .serve_stale(Duration::from_millis(50));
+    let fresh = cache
+        .query::<Option<i64>>("RETURN $value")
+        .bind(("value", 2))
+        .await
+        .unwrap();
+    assert!(!fresh.stale);
+    let conn = pool.get().await.unwrap();
+    let stale = cache
+        .query::<Option<i64>>("RETURN $value")
+        .bind(("value", 2))
+        .await
+        .unwrap();
+    assert!(stale.stale);
+    assert_eq!(stale.into_inner(), Some(2));
+    assert!(cache.query::<Option<i64>>("RETURN 3").await.is_err());
+    drop(conn);
+    let fresh = cache
+        .query::<Option<i64>>("RETURN $value")
+        .bind(("value", 2))
+        .await
+        .unwrap();
+    assert!(!fresh.stale);
+}
+
+#[tokio::test]
+async fn coalescer() {
+    use deadpool_surrealdb::{coalesce::Coalescer, Manager};
+
+    let manager = Manager::from_config(&default_config()).runtime(Runtime::Tokio1);
+    let pool = Pool::builder(manager).max_size(1).build().unwrap();
+    let coalescer = Coalescer::new(&pool);
+
+    let conn = pool.get().await.unwrap();
+    let tasks: Vec<_> = (0..5)
+        .map(|_| {
+            let coalescer = coalescer.clone();
+            tokio::spawn(async move {
+                coalescer
+                    .query::<Option<i64>>("RETURN $n")
+                    .bind(("n", 42))
+                    .await
+            })
+        })
+        .collect();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(coalescer.in_flight(), 1);
+    assert_eq!(pool.status().waiting, 1);
+    drop(conn);
+
+    for task in tasks {
+        assert_eq!(task.await.unwrap().unwrap(), Some(42));
+    }
+    assert_eq!(coalescer.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn endpoint_switch() {
+    use deadpool_surrealdb::Manager;
+
+    async fn ns(pool: &Pool) -> Option<String> {
+        let conn = pool.get().await.unwrap();
+        conn.query("RETURN session::ns()").await.unwrap().take(0).unwrap()
+    }
+
+    let manager = Manager::from_config(&default_config()).runtime(Runtime::Tokio1);
+    let pool = Pool::builder(manager).max_size(2).build().unwrap();
+    assert!(!pool.commit_switch());
+    assert_eq!(ns(&pool).await.as_deref(), Some("test"));
+
+    let mut cfg = default_config();
+    cfg.ns = "switched".to_string();
+    pool.prepare_switch(cfg).unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(ns(&pool).await.as_deref(), Some("test"));
+
+    assert!(pool.commit_switch());
+    assert_eq!(pool.status().size, 0);
+    assert_eq!(ns(&pool).await.as_deref(), Some("switched"));
+}
+
+#[tokio::test]
+async fn set_endpoint() {
+    use std::sync::{Arc, Mutex};
+
+    use deadpool_surrealdb::{switch::EndpointSwitch, Manager};
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let manager = Manager::from_config(&default_config()).on_endpoint_switch({
+        let events = events.clone();
+        move |event: &EndpointSwitch| events.lock().unwrap().push(event.clone())
+    });
+    let pool = Pool::builder(manager).max_size(2).build().unwrap();
+    let idle = pool.get().await.unwrap();
+    let busy = pool.get().await.unwrap();
+    drop(idle);
+
+    pool.set_endpoint("mem://");
+    assert_eq!(pool.status().size, 1);
+    drop(busy);
+    assert_eq!(pool.status().size, 1);
+    let conn = pool.get().await.unwrap();
+    let _ = conn.query("RETURN 1").await.unwrap();
+    drop(conn);
+    assert_eq!(pool.status().size, 1);
+
+    let events = events.lock().unwrap();
+    assert_eq!(
+        *events,
+        vec![EndpointSwitch {
+            from: "mem://".to_string(),
+            to: "mem://".to_string(),
+            generation: 1,
+        }]
+    );
+}
+
+#[tokio::test]
+async fn retry_budget() {
+    use std::time::Instant;
+
+    use deadpool_surrealdb::{retry::RetryBudget, Manager};
+
+    let deadline = Duration::from_millis(200);
+    let budget = RetryBudget::new(10)
+        .attempt_timeout(Duration::from_millis(50))
+        .deadline(deadline);
+
+    let pool = create_pool();
+    let started = Instant::now();
+    let result = pool.query("SLEEP 2s").retry(budget).await;
+    assert!(matches!(
+        result,
+        Err(deadpool_surrealdb::Error::QueryTimeout(d)) if d == deadline
+    ));
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    // The server accepts connections but never answers.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let host = format!("ws://{}", listener.local_addr().unwrap());
+    let config = Config::new(host, "test", "test", Credentials::root("root", "root"));
+    let manager = Manager::from_config(&config)
+        .runtime(Runtime::Tokio1)
+        .create_retry(budget);
+    let pool = Pool::builder(manager).build().unwrap();
+    let started = Instant::now();
+    let e = deadpool_surrealdb::Error::from(pool.get().await.unwrap_err());
+    assert!(matches!(e, deadpool_surrealdb::Error::Timeout { .. }));
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn health_state() {
+    use std::sync::{Arc, Mutex};
+
+    use deadpool_surrealdb::{
+        health::{HealthState, HealthThresholds, HealthTransition},
+        Manager,
+    };
+
+    let transitions = Arc::new(Mutex::new(Vec::new()));
+    let creds = Credentials::root("root", "root");
+    let config = Config::new("ws://127.0.0.1:1", "test", "test", creds);
+    let manager = Manager::from_config(&config)
+        .health_thresholds(HealthThresholds {
+            window: 4,
+            ..HealthThresholds::default()
+        })
+        .on_health_change({
+            let transitions = transitions.clone();
+            move |t: &HealthTransition| transitions.lock().unwrap().push((t.from, t.to))
+        });
+    let pool = Pool::builder(manager).max_size(1).build().unwrap();
+    assert_eq!(pool.health(), HealthState::Healthy);
+
+    assert!(pool.get().await.is_err());
+    assert_eq!(pool.health(), HealthState::Unhealthy);
+
+    // One failure among the last four attempts doesn't recover the pool yet.
+    pool.set_endpoint("mem://");
+    for _ in 0..3 {
+        drop(pool.get().await.unwrap());
+    }
+    assert_eq!(pool.health(), HealthState::Unhealthy);
+    drop(pool.get().await.unwrap());
+    assert_eq!(pool.health(), HealthState::Healthy);
+
+    assert_eq!(
+        *transitions.lock().unwrap(),
+        vec![
+            (HealthState::Healthy, HealthState::Unhealthy),
+            (HealthState::Unhealthy, HealthState::Healthy),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn drain_endpoint() {
+    let pool = default_config().create_pool(Some(Runtime::Tokio1)).unwrap();
+    let conn = pool.get().await.unwrap();
+    assert_eq!(conn.endpoint(), "mem://");
+    drop(conn);
+
+    pool.drain_endpoint("mem://");
+    assert_eq!(pool.drained_endpoints(), vec!["mem://"]);
+    assert_eq!(pool.status().size, 0);
+    assert!(pool.checkout().await.is_err());
+
+    pool.undrain_endpoint("mem://");
+    assert!(pool.drained_endpoints().is_empty());
+    pool.checkout().await.unwrap();
+}
+
+#[tokio::test]
+async fn connection_budget() {
+    use deadpool_surrealdb::{budget::ConnectionBudget, Manager};
+
+    let budget = ConnectionBudget::new(1);
+    let pool = |budget: &ConnectionBudget| {
+        let manager = Manager::from_config(&default_config()).connection_budget(budget.clone());
+        Pool::builder(manager)
+            .runtime(Runtime::Tokio1)
+            .create_timeout(Some(Duration::from_millis(100)))
+            .build()
+            .unwrap()
+    };
+    let a = pool(&budget);
+    let b = pool(&budget);
+
+    let conn = a.get().await.unwrap();
+    assert_eq!(budget.in_use(), 1);
+    assert!(b.get().await.is_err());
+
+    drop(conn);
+    a.resize(0);
+    assert_eq!(budget.in_use(), 0);
+    b.get().await.unwrap();
+    assert_eq!(budget.in_use(), 1);
+}
+
+#[cfg(feature = "fake-server")]
+#[tokio::test]
+async fn fake_server() {
+    use deadpool_surrealdb::fake_server::FakeServer;
+
+    let server = FakeServer::start().await.unwrap();
+    server.respond("RETURN 42", 42);
+    let pool = server
+        .config("test", "test")
+        .create_pool(Some(Runtime::Tokio1))
+        .unwrap();
+
+    let mut response = pool.get().await.unwrap().query("RETURN 42").await.unwrap();
+    let value: Option<i64> = response.take(0).unwrap();
+    assert_eq!(value, Some(42));
+    assert_eq!(server.queries(), vec!["RETURN 42"]);
+    assert!(server.requests().iter().any(|method| method == "signin"));
+
+    server.reject_auth(true);
+    let pool = server
+        .config("test", "test")
+        .create_pool(Some(Runtime::Tokio1))
+        .unwrap();
+    assert!(pool.checkout().await.unwrap_err().is_auth());
+}
+
+#[cfg(feature = "fake-server")]
+#[tokio::test]
+async fn recycle_invalidated() {
+    use deadpool_surrealdb::{fake_server::FakeServer, Manager};
+
+    let server = FakeServer::start().await.unwrap();
+    let manager = Manager::from_config(&server.config("test", "test"));
+    let pool = Pool::builder(manager).max_size(1).build().unwrap();
+
+    let conn = pool.get().await.unwrap();
+    conn.invalidate().await.unwrap();
+    assert!(conn.query("RETURN 1").await.unwrap_err().is_permission_denied());
+    drop(conn);
+
+    let signins = |server: &FakeServer| {
+        server.requests().iter().filter(|method| *method == "signin").count()
+    };
+    let before = signins(&server);
+    let conn = pool.get().await.unwrap();
+    let _ = conn.query("RETURN 1").await.unwrap();
+    assert_eq!(signins(&server), before + 1);
+    drop(conn);
+
+    // Healthy sessions are not signed in again.
+    let _ = pool.get().await.unwrap();
+    assert_eq!(signins(&server), before + 1);
+}
+
+struct Env {
+    backup: HashMap<String, Option<String>>,
+}
+
+impl Env {
+    pub fn new() -> Self {
+        Self {
+            backup: HashMap::new(),
+        }
+    }
+    
+    pub fn set(&mut self, name: &str, value: &str) {
+        let _ = self.backup.entry(name.to_string()).or_insert_with(|| env::var(name).ok());
+        env::set_var(name, value);
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        let _ = self.backup.entry(name.to_string()).or_insert_with(|| env::var(name).ok());
+        env::remove_var(name);
     }
 }
 
@@ -323,3 +323,16 @@ async fn retain_fnmut() {
     }
     assert_eq!(pool.status().size, 0);
 }
+
+#[tokio::test]
+async fn weak_pool() {
+    let mgr = Manager {};
+    let pool = Pool::builder(mgr).max_size(1).build().unwrap();
+    let weak = pool.weak();
+    let upgraded = weak.upgrade().unwrap();
+    drop(upgraded.get().await.unwrap());
+    assert_eq!(upgraded.status().size, 1);
+    drop(upgraded);
+    drop(pool);
+    assert!(weak.upgrade().is_none());
+}